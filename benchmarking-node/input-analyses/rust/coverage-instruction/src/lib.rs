@@ -42,8 +42,8 @@ advice! { return_            (
 advice! { const_             (v: WasmValue                                                           , l: Location) { add_location(l); v                         } }
 advice! { local              (v: WasmValue, _i: LocalIndex, _l: LocalOp                              , l: Location) { add_location(l); v                         } }
 advice! { global             (v: WasmValue, _i: GlobalIndex, _g: GlobalOp                            , l: Location) { add_location(l); v                         } }
-advice! { load               (i: LoadIndex, o: LoadOffset, op: LoadOperation                         , l: Location) { add_location(l); op.perform(&i, &o)        } }
-advice! { store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation        , l: Location) { add_location(l); op.perform(&i, &v, &o);   } }
+advice! { load               (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, l: Location) { add_location(l); op.perform(&i, &o, &m, &a) } }
+advice! { store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, l: Location) { add_location(l); op.perform(&i, &v, &o, &m, &a); } }
 advice! { memory_size        (s: WasmValue, _i: MemoryIndex                                          , l: Location) { add_location(l); s                         } }
 advice! { memory_grow        (a: WasmValue, i: MemoryIndex                                           , l: Location) { add_location(l); i.grow(a)                 } }
 advice! { block pre          (_bi: BlockInputCount, _ba: BlockArity                                  , l: Location) { add_location(l);                           } }
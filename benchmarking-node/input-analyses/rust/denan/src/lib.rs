@@ -7,7 +7,7 @@ trait Denan {
 impl Denan for WasmValue {
     fn denan(self) -> Self {
         match self.type_() {
-            WasmType::I32 | WasmType::I64 => self,
+            WasmType::I32 | WasmType::I64 | WasmType::FuncRef | WasmType::ExternRef => self,
             WasmType::F32 => self.as_f32().is_nan().then(|| 0_f32.into()).unwrap_or(self),
             WasmType::F64 => self.as_f64().is_nan().then(|| 0_f64.into()).unwrap_or(self),
         }
@@ -26,8 +26,8 @@ advice! {
     const_ (v: WasmValue, _l: Location) { v.denan() }
     local (v: WasmValue, _i: LocalIndex, _l: LocalOp, _l: Location) { v.denan() }
     global (v: WasmValue, _i: GlobalIndex, _g: GlobalOp, _l: Location) { v.denan() }
-    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, _l: Location) { op.perform(&i, &o).denan() }
-    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, _l: Location) { op.perform(&i, &v.denan(), &o); }
+    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { op.perform(&i, &o, &m, &a).denan() }
+    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { op.perform(&i, &v.denan(), &o, &m, &a); }
 }
 
 // Source of original pass:
@@ -23,8 +23,8 @@ advice! { return_            (
 advice! { const_             (v: WasmValue                                                           , _l: Location) { v                         } }
 advice! { local              (v: WasmValue, _i: LocalIndex, _l: LocalOp                              , _l: Location) { v                         } }
 advice! { global             (v: WasmValue, _i: GlobalIndex, _g: GlobalOp                            , _l: Location) { v                         } }
-advice! { load               (i: LoadIndex, o: LoadOffset, op: LoadOperation                         , _l: Location) { op.perform(&i, &o)        } }
-advice! { store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation        , _l: Location) { op.perform(&i, &v, &o);   } }
+advice! { load               (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { op.perform(&i, &o, &m, &a) } }
+advice! { store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { op.perform(&i, &v, &o, &m, &a); } }
 advice! { memory_size        (s: WasmValue, _i: MemoryIndex                                          , _l: Location) { s                         } }
 advice! { memory_grow        (a: WasmValue, i: MemoryIndex                                           , _l: Location) { i.grow(a)                 } }
 advice! { block pre          (_bi: BlockInputCount, _ba: BlockArity                                  , _l: Location) {                           } }
@@ -98,8 +98,8 @@ advice! {
     const_             (v: WasmValue                                                           , loc: Location) { inc_instr(loc, Instruction::Const);            v                         }
     local              (v: WasmValue, _i: LocalIndex, _l: LocalOp                              , loc: Location) { inc_instr(loc, Instruction::Local);            v                         }
     global             (v: WasmValue, _i: GlobalIndex, _g: GlobalOp                            , loc: Location) { inc_instr(loc, Instruction::Global);           v                         }
-    load               (i: LoadIndex, o: LoadOffset, op: LoadOperation                         , loc: Location) { inc_instr(loc, Instruction::Load);             op.perform(&i, &o)        }
-    store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation        , loc: Location) { inc_instr(loc, Instruction::Store);            op.perform(&i, &v, &o);   }
+    load               (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, loc: Location) { inc_instr(loc, Instruction::Load);             op.perform(&i, &o, &m, &a) }
+    store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, loc: Location) { inc_instr(loc, Instruction::Store);            op.perform(&i, &v, &o, &m, &a); }
     memory_size        (s: WasmValue, _i: MemoryIndex                                          , loc: Location) { inc_instr(loc, Instruction::MemorySize);       s                         }
     memory_grow        (a: WasmValue, i: MemoryIndex                                           , loc: Location) { inc_instr(loc, Instruction::MemoryGrow);       i.grow(a)                 }
     block pre          (_bi: BlockInputCount, _ba: BlockArity                                  , loc: Location) { inc_instr(loc, Instruction::BlockPre);                                   }
@@ -75,9 +75,9 @@ pub fn get_nth_operation(index: i32) -> i32 {
 }
 
 advice! {
-    store (store_index: StoreIndex, value: WasmValue, offset: StoreOffset, operation: StoreOperation, location: Location) {
+    store (store_index: StoreIndex, value: WasmValue, offset: StoreOffset, operation: StoreOperation, memory: MemoryIndex, alignment: MemoryAlignment, location: Location) {
         // perform unaltered operation
-        operation.perform(&store_index, &value, &offset);
+        operation.perform(&store_index, &value, &offset, &memory, &alignment);
 
         // perform analysis
         let offset = offset.value();
@@ -89,9 +89,9 @@ advice! {
         add_access(funct_index, instr_index, address, access_kind);
     }
 
-    load (load_index: LoadIndex, offset: LoadOffset, operation: LoadOperation, location: Location) {
+    load (load_index: LoadIndex, offset: LoadOffset, operation: LoadOperation, memory: MemoryIndex, alignment: MemoryAlignment, location: Location) {
         // perform unaltered operation
-        let outcome = operation.perform(&load_index, &offset);
+        let outcome = operation.perform(&load_index, &offset, &memory, &alignment);
 
         // // perform analysis
         let offset = offset.value();
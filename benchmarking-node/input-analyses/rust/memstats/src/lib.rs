@@ -31,7 +31,7 @@ pub fn get_write_at(mem_idx: i32) -> i32 {
 }
 
 advice! {
-    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, _l: Location) {
+    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) {
         let reads = unsafe { addr_of_mut!(READS).as_mut().unwrap() };
         let size = match op {
             I32Load8S | I32Load8U | I64Load8S | I64Load8U => 1,
@@ -40,10 +40,10 @@ advice! {
             I64Load | F64Load => 8,
         };
         increase_at(reads, (i.value() as i64 + o.value()) as _, size);
-        op.perform(&i, &o)
+        op.perform(&i, &o, &m, &a)
     }
 
-    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, _l: Location) {
+    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) {
         let writes = unsafe { addr_of_mut!(WRITES).as_mut().unwrap() };
         let touched_bytes = match op {
             I32Store8 | I64Store8 => 1,
@@ -52,6 +52,6 @@ advice! {
             I64Store | F64Store => 8,
         };
         increase_at(writes, (i.value() as i64 + o.value()) as _, touched_bytes);
-        op.perform(&i, &v, &o);
+        op.perform(&i, &v, &o, &m, &a);
     }
 }
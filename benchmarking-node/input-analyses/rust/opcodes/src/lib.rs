@@ -70,8 +70,8 @@ advice! {
     const_             (v: WasmValue                                                           , _l: Location) { inc_instr(InstructionIndex::Const);            v                         }
     local              (v: WasmValue, _i: LocalIndex, _l: LocalOp                              , _l: Location) { inc_instr(InstructionIndex::Local);            v                         }
     global             (v: WasmValue, _i: GlobalIndex, _g: GlobalOp                            , _l: Location) { inc_instr(InstructionIndex::Global);           v                         }
-    load               (i: LoadIndex, o: LoadOffset, op: LoadOperation                         , _l: Location) { inc_instr(InstructionIndex::Load);             op.perform(&i, &o)        }
-    store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation        , _l: Location) { inc_instr(InstructionIndex::Store);            op.perform(&i, &v, &o);   }
+    load               (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { inc_instr(InstructionIndex::Load);             op.perform(&i, &o, &m, &a) }
+    store              (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { inc_instr(InstructionIndex::Store);            op.perform(&i, &v, &o, &m, &a); }
     memory_size        (s: WasmValue, _i: MemoryIndex                                          , _l: Location) { inc_instr(InstructionIndex::MemorySize);       s                         }
     memory_grow        (a: WasmValue, i: MemoryIndex                                           , _l: Location) { inc_instr(InstructionIndex::MemoryGrow);       i.grow(a)                 }
     block pre          (_bi: BlockInputCount, _ba: BlockArity                                  , _l: Location) { inc_instr(InstructionIndex::BlockPre);                                   }
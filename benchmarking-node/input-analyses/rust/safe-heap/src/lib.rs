@@ -11,11 +11,11 @@ use wastrumentation_rs_stdlib::*;
 
 const SIZE_AS_BYTES: i64 = i64::pow(2, 16);
 
-fn bounds_check(index: i64, bytes: i64, offset: i64) {
+fn bounds_check(index: i64, bytes: i64, offset: i64, memory: i32) {
     // strict_add asserts that no overflow occurred
     let last_target_byte = index.strict_add(bytes).strict_add(offset);
     assert!(last_target_byte != 0);
-    assert!(last_target_byte <= (base_memory_size(0) as i64) * SIZE_AS_BYTES);
+    assert!(last_target_byte <= (base_memory_size(memory) as i64) * SIZE_AS_BYTES);
 }
 
 fn alignment_check(index: i64, size: i64) {
@@ -23,28 +23,51 @@ fn alignment_check(index: i64, size: i64) {
     assert!(index & (size - 1) == 0); // size == 4 (_32) | 8 (_64)
 }
 
-fn safe_load(i: LoadIndex, o: LoadOffset, op: LoadOperation) -> WasmValue {
+fn safe_load(
+    i: LoadIndex,
+    o: LoadOffset,
+    op: LoadOperation,
+    m: MemoryIndex,
+    a: MemoryAlignment,
+) -> WasmValue {
     // Bound check: check for reading past valid memory: if pointer + offset + bytes
-    bounds_check(i.value() as i64, op.target_value_size() as i64, o.value());
+    bounds_check(
+        i.value() as i64,
+        op.target_value_size() as i64,
+        o.value(),
+        m.value() as i32,
+    );
     // Alignment check
     alignment_check(i.value() as i64, op.target_value_size() as i64);
     // Perform
-    op.perform(&i, &o)
+    op.perform(&i, &o, &m, &a)
 }
 
-fn safe_store(i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation) -> () {
+fn safe_store(
+    i: StoreIndex,
+    v: WasmValue,
+    o: StoreOffset,
+    op: StoreOperation,
+    m: MemoryIndex,
+    a: MemoryAlignment,
+) -> () {
     // Bound check: check for reading past valid memory: if pointer + offset + bytes
-    bounds_check(i.value() as i64, op.target_value_size() as i64, o.value());
+    bounds_check(
+        i.value() as i64,
+        op.target_value_size() as i64,
+        o.value(),
+        m.value() as i32,
+    );
     // Alignment check
     alignment_check(i.value() as i64, op.target_value_size() as i64);
     // Perform
-    op.perform(&i, &v, &o);
+    op.perform(&i, &v, &o, &m, &a);
 }
 
 // Target program events: loads & stores
 advice! {
-    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, _l: Location) { safe_load(i, o, op)  }
-    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, _l: Location) { safe_store(i, v, o, op); }
+    load (i: LoadIndex, o: LoadOffset, op: LoadOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { safe_load(i, o, op, m, a)  }
+    store (i: StoreIndex, v: WasmValue, o: StoreOffset, op: StoreOperation, m: MemoryIndex, a: MemoryAlignment, _l: Location) { safe_store(i, v, o, op, m, a); }
 }
 
 // In `SafeHeap.cpp` the inspiration source for `fn bounds_check` is `makeBoundsCheck`
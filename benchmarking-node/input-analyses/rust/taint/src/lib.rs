@@ -418,13 +418,15 @@ advice! { load (
         store_index: LoadIndex,
         offset: LoadOffset,
         operation: LoadOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         _location: Location,
     ) {
         values().and_then(|vs| vs.pop());
         let taint = memory_get(&store_index, &offset);
         values().map(|vs| vs.push(taint));
 
-        let value = operation.perform(&store_index, &offset);
+        let value = operation.perform(&store_index, &offset, &memory, &alignment);
         value
     }
 }
@@ -434,13 +436,15 @@ advice! { store (
         value: WasmValue,
         offset: StoreOffset,
         operation: StoreOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         _location: Location,
     ) {
         let taint = values().and_then(|vs| vs.pop()).unwrap_or(Taint::new());
         values().and_then(|vs| vs.pop());
         memory_set(&store_index, taint, &offset);
 
-        operation.perform(&store_index, &value, &offset);
+        operation.perform(&store_index, &value, &offset, &memory, &alignment);
     }
 }
 
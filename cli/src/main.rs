@@ -1,11 +1,14 @@
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use clio::*;
 use rust_to_wasm_compiler::WasiSupport;
 use serde::Deserialize;
-use wastrumentation::compiler::Compiles;
-use wastrumentation::{Configuration, Wastrumenter};
+use wastrumentation::compiler::{CompileCache, Compiles};
+use wastrumentation::{
+    Configuration, CustomSections, FunctionPattern, SideTables, TargetSelection, Wastrumenter,
+};
 use wastrumentation_lang_rust::compile::compiler::Compiler as RustCompiler;
 use wastrumentation_lang_rust::compile::options::RustSource;
 use wastrumentation_lang_rust::generate::analysis::{Hook as AnalysisHook, RustAnalysisSpec};
@@ -30,9 +33,47 @@ struct Args {
     #[arg(long, required = false, num_args = 1..)]
     targets: Option<Vec<u32>>,
 
+    /// Target functions by export or name-section name, `glob:<glob>` or `regex:<regex>`
+    #[arg(long, required = false, num_args = 1..)]
+    target_names: Option<Vec<FunctionPattern>>,
+
+    /// Target functions of a single hook, e.g. `load=12,13`, overriding `--targets` for that hook
+    #[arg(long, required = false, num_args = 1.., value_parser = parse_hook_targets)]
+    hook_targets: Option<Vec<(Hook, Vec<u32>)>>,
+
+    /// Also fire block & loop post hooks when a branch or return leaves them
+    #[arg(long)]
+    unwinding: bool,
+
+    /// Maintain an AFL-style edge coverage bitmap, exported as `__wastrumentation_coverage_map`
+    #[arg(long)]
+    coverage: bool,
+
+    /// Keep the name section & custom sections, naming injected functions `__wastr_<name>`.
+    /// DWARF is dropped, as instrumentation invalidates it.
+    #[arg(long)]
+    preserve_custom_sections: bool,
+
+    /// Report the DWARF source location of every instrumented site to the analysis
+    #[arg(long)]
+    source_locations: bool,
+
+    /// Have trap calls pass a site id, resolved through a site table in the instrumented module
+    #[arg(long)]
+    compact_locations: bool,
+
     /// Output path for the instrumented module
     #[arg(short, long)]
     output_path: Output,
+
+    /// Output path for a JSON manifest of every injected trap call
+    #[arg(long)]
+    manifest_path: Option<Output>,
+
+    /// Directory caching the compiled analysis & instrumentation library across runs,
+    /// for Rust only
+    #[arg(long)]
+    compile_cache: Option<std::path::PathBuf>,
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Deserialize, PartialEq, Eq, Copy, Hash)]
@@ -42,6 +83,12 @@ enum Hook {
     CallPost,
     CallIndirectPre,
     CallIndirectPost,
+    /// Before a `call` of an imported function. An import reached through
+    /// `call_indirect` is not reported, only by `CallIndirectPre`.
+    ImportCallPre,
+    /// After a `call` of an imported function. An import reached through
+    /// `call_indirect` is not reported, only by `CallIndirectPost`.
+    ImportCallPost,
     IfThen,
     IfThenPost,
     IfThenElse,
@@ -54,6 +101,8 @@ enum Hook {
     Binary,
     Drop,
     Return,
+    FunctionEntry,
+    FunctionExit,
     Const,
     Local,
     Global,
@@ -61,10 +110,16 @@ enum Hook {
     Load,
     MemorySize,
     MemoryGrow,
+    MemoryFill,
+    MemoryCopy,
+    MemoryInit,
+    DataDrop,
     BlockPre,
     BlockPost,
     LoopPre,
     LoopPost,
+    LoopIteration,
+    BasicBlockEnter,
     RefFunc,
     RefNull,
     RefIsNull,
@@ -86,6 +141,8 @@ impl From<&Hook> for AnalysisHook {
             Hook::CallPost => AnalysisHook::CallPost,
             Hook::CallIndirectPre => AnalysisHook::CallIndirectPre,
             Hook::CallIndirectPost => AnalysisHook::CallIndirectPost,
+            Hook::ImportCallPre => AnalysisHook::ImportCallPre,
+            Hook::ImportCallPost => AnalysisHook::ImportCallPost,
             Hook::IfThen => AnalysisHook::IfThen,
             Hook::IfThenPost => AnalysisHook::IfThenPost,
             Hook::IfThenElse => AnalysisHook::IfThenElse,
@@ -98,6 +155,8 @@ impl From<&Hook> for AnalysisHook {
             Hook::Binary => AnalysisHook::Binary,
             Hook::Drop => AnalysisHook::Drop,
             Hook::Return => AnalysisHook::Return,
+            Hook::FunctionEntry => AnalysisHook::FunctionEntry,
+            Hook::FunctionExit => AnalysisHook::FunctionExit,
             Hook::Const => AnalysisHook::Const,
             Hook::Local => AnalysisHook::Local,
             Hook::Global => AnalysisHook::Global,
@@ -105,10 +164,16 @@ impl From<&Hook> for AnalysisHook {
             Hook::Load => AnalysisHook::Load,
             Hook::MemorySize => AnalysisHook::MemorySize,
             Hook::MemoryGrow => AnalysisHook::MemoryGrow,
+            Hook::MemoryFill => AnalysisHook::MemoryFill,
+            Hook::MemoryCopy => AnalysisHook::MemoryCopy,
+            Hook::MemoryInit => AnalysisHook::MemoryInit,
+            Hook::DataDrop => AnalysisHook::DataDrop,
             Hook::BlockPre => AnalysisHook::BlockPre,
             Hook::BlockPost => AnalysisHook::BlockPost,
             Hook::LoopPre => AnalysisHook::LoopPre,
             Hook::LoopPost => AnalysisHook::LoopPost,
+            Hook::LoopIteration => AnalysisHook::LoopIteration,
+            Hook::BasicBlockEnter => AnalysisHook::BasicBlockEnter,
             Hook::RefFunc => AnalysisHook::RefFunc,
             Hook::RefNull => AnalysisHook::RefNull,
             Hook::RefIsNull => AnalysisHook::RefIsNull,
@@ -124,6 +189,18 @@ impl From<&Hook> for AnalysisHook {
     }
 }
 
+fn parse_hook_targets(hook_targets: &str) -> Result<(Hook, Vec<u32>), String> {
+    let (hook, targets) = hook_targets
+        .split_once('=')
+        .ok_or_else(|| format!("expected `<hook>=<targets>`, found `{hook_targets}`"))?;
+    let hook = Hook::from_str(hook, true)?;
+    let targets = targets
+        .split(',')
+        .map(|target| target.trim().parse::<u32>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<u32>, String>>()?;
+    Ok((hook, targets))
+}
+
 fn main() -> anyhow::Result<()> {
     let Args {
         mut input_program_path,
@@ -131,6 +208,15 @@ fn main() -> anyhow::Result<()> {
         mut output_path,
         hooks,
         targets,
+        target_names,
+        hook_targets,
+        unwinding,
+        coverage,
+        preserve_custom_sections,
+        source_locations,
+        compact_locations,
+        manifest_path,
+        compile_cache,
     } = Args::parse();
 
     let mut wasm_module = Vec::new();
@@ -150,21 +236,57 @@ fn main() -> anyhow::Result<()> {
     }
     .into();
 
+    // Restrict every trap of a hook to the targets of that hook
+    let mut hook_target_indices: HashMap<String, HashSet<u32>> = HashMap::new();
+    for (hook, targets) in hook_targets.unwrap_or_default() {
+        for trap_name in AnalysisHook::from(&hook).trap_names() {
+            hook_target_indices
+                .entry(trap_name)
+                .or_default()
+                .extend(&targets);
+        }
+    }
+    let hook_targets = hook_target_indices
+        .into_iter()
+        .map(|(trap_name, indices)| (trap_name, TargetSelection::Indices(indices)))
+        .collect();
+
     let instrumentation_language_compiler = RustCompiler::setup_compiler()?;
     let analysis_language_compiler = RustCompiler::setup_compiler()?;
     let configuration = Configuration {
         target_indices: targets,
+        target_names,
         primary_selection: None,
+        unwinding,
+        coverage,
+        hook_targets,
+        custom_sections: if preserve_custom_sections {
+            CustomSections::Preserve
+        } else {
+            CustomSections::Strip
+        },
+        source_locations,
+        manifest: manifest_path.is_some(),
+        compact_locations,
+        ..Default::default()
     };
 
-    let instrumented_wasm_module = Wastrumenter::new(
+    let mut wastrumenter = Wastrumenter::new(
         Box::new(instrumentation_language_compiler),
         Box::new(analysis_language_compiler),
-    )
-    .wastrument(&wasm_module, analysis, &configuration)
-    .expect("Instrumenting failed");
+    );
+    if let Some(compile_cache) = compile_cache {
+        wastrumenter = wastrumenter.with_compile_cache(CompileCache::new(compile_cache));
+    }
+    let (instrumented_wasm_module, SideTables { manifest, .. }) = wastrumenter
+        .wastrument_with_side_tables(&wasm_module, analysis, &configuration)
+        .expect("Instrumenting failed");
 
     output_path.write_all(&instrumented_wasm_module)?;
 
+    if let (Some(mut manifest_path), Some(manifest)) = (manifest_path, manifest) {
+        manifest_path.write_all(manifest.to_json()?.as_bytes())?;
+    }
+
     Ok(())
 }
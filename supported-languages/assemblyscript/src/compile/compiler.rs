@@ -5,6 +5,7 @@ use asc_compiler_rs::{error::CompilerSetupError, options::CompilerOptions};
 
 use wastrumentation::compiler::{CompilationError, CompilationResult, Compiles};
 
+// Without a `cache_key`, AssemblyScript is compiled on every run, even with a compile cache
 impl Compiles<AssemblyScript> for Compiler {
     type CompilerOptions = CompilerOptions;
     type CompilerSetupError = CompilerSetupError;
@@ -5,6 +5,9 @@ enum WasmType {
     f32 = 1,
     i64 = 2,
     f64 = 3,
+    // References are passed as `i32` handles, read them using `getArg<i32>`
+    ref_func = 4,
+    ref_extern = 5,
 }
 
 @external("wastrumentation_stack", "wastrumentation_stack_load_type")
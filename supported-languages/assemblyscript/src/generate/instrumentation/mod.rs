@@ -51,7 +51,7 @@ impl WaspSignature<'_> {
                 .return_types
                 .iter()
                 .chain(self.argument_types.iter())
-                .map(ToString::to_string)
+                .map(|ty| ty.stack_type_name().to_string())
                 .collect::<Vec<String>>()
                 .join(", ");
             format!("<{comma_separated_types}>")
@@ -154,7 +154,7 @@ fn generate_allocate_specialized(signature: &WaspSignature) -> String {
     let signature_args_typs_ident = signature_args
         .iter()
         .enumerate()
-        .map(|(index, ty)| format!("a{index}: {ty}"))
+        .map(|(index, ty)| format!("a{index}: {}", ty.stack_type_name()))
         .collect::<Vec<String>>()
         .join(", ");
 
@@ -260,6 +260,7 @@ fn generate_load_specialized(signature: &WaspSignature) -> String {
         .enumerate()
         .map(|(index, arg_i_ret_type)| {
             let mangled_name = signature.generate_load_name(SignatureSide::Argument, index);
+            let arg_i_ret_type = arg_i_ret_type.stack_type_name();
             format!(
                 "
 export function {mangled_name}(stack_ptr: usize): {arg_i_ret_type} {{
@@ -272,6 +273,7 @@ export function {mangled_name}(stack_ptr: usize): {arg_i_ret_type} {{
         .enumerate()
         .map(|(index, ret_i_ret_type)| {
             let mangled_name = signature.generate_load_name(SignatureSide::Return, index);
+            let ret_i_ret_type = ret_i_ret_type.stack_type_name();
             format!(
                 "
 export function {mangled_name}(stack_ptr: usize): {ret_i_ret_type} {{
@@ -331,6 +333,7 @@ fn generate_store_specialized(signature: &WaspSignature) -> String {
         .enumerate()
         .map(|(index, arg_i_ret_type)| {
             let mangled_name = signature.generate_store_name(SignatureSide::Argument, index);
+            let arg_i_ret_type = arg_i_ret_type.stack_type_name();
             format!(
                 "
 export function {mangled_name}(stack_ptr: usize, a{index}: {arg_i_ret_type}): void {{
@@ -343,6 +346,7 @@ export function {mangled_name}(stack_ptr: usize, a{index}: {arg_i_ret_type}): vo
         .enumerate()
         .map(|(index, ret_i_ret_type)| {
             let mangled_name = signature.generate_store_name(SignatureSide::Return, index);
+            let ret_i_ret_type = ret_i_ret_type.stack_type_name();
             format!(
                 "
 export function {mangled_name}(stack_ptr: usize, a{index}: {ret_i_ret_type}): void {{
@@ -460,7 +464,7 @@ fn generate_store_rets_specialized(signature: &WaspSignature) -> String {
     let rets_signature = signature_rets
         .iter()
         .enumerate()
-        .map(|(index, ty)| format!("a{index}: {ty}"));
+        .map(|(index, ty)| format!("a{index}: {}", ty.stack_type_name()));
     // eg: `stack_ptr: usize, a0: R0, a1: R1`
     let total_signature = (vec![String::from("stack_ptr: usize")])
         .into_iter()
@@ -1,6 +1,14 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
 use rust_to_wasm_compiler::{CompilerSetupError, Profile, RustToWasmCompiler};
+use toml::{Table, Value};
 use wastrumentation::compiler::{
-    CompilationError, CompilationResult, Compiles, DefaultCompilerOptions,
+    CacheKey, CompilationError, CompilationResult, Compiles, DefaultCompilerOptions,
 };
 
 use super::{
@@ -51,4 +59,173 @@ impl Compiles<Rust> for Compiler {
         }
         .map_err(|err| CompilationError::because(err.to_string()))
     }
+
+    fn cache_key(&self, compiler_options: &Self::CompilerOptions) -> Option<CacheKey> {
+        let CompilerOptions { source, profile } = compiler_options;
+        let mut key = CacheKey::default();
+        push_toolchain(&mut key)?;
+        key.push(format!("{profile:?}"));
+        match source {
+            RustSource::SourceCode(
+                wasi_support,
+                ManifestSource(manifest_source_code),
+                RustSourceCode(rust_source_code),
+            ) => {
+                key.push(format!("{wasi_support:?}"))
+                    .push(manifest_source_code)
+                    .push(rust_source_code);
+            }
+            RustSource::Manifest(wasi_support, manifest_path) => {
+                key.push(format!("{wasi_support:?}"));
+                push_crate(&mut key, manifest_path).ok()?;
+            }
+        }
+        Some(key)
+    }
+}
+
+/// Adds the versions of `rustc` & `cargo` to `key`, so a module compiled by
+/// another toolchain is never reused
+fn push_toolchain(key: &mut CacheKey) -> Option<()> {
+    for version in toolchain()? {
+        key.push(version);
+    }
+    Some(())
+}
+
+/// The versions of `rustc` & `cargo`, asked once per process
+fn toolchain() -> Option<&'static [Vec<u8>]> {
+    static TOOLCHAIN: OnceLock<Option<Vec<Vec<u8>>>> = OnceLock::new();
+    TOOLCHAIN
+        .get_or_init(|| {
+            [("rustc", "-vV"), ("cargo", "-V")]
+                .into_iter()
+                .map(|(program, version)| {
+                    let output = Command::new(program).arg(version).output().ok()?;
+                    output.status.success().then_some(output.stdout)
+                })
+                .collect()
+        })
+        .as_deref()
+}
+
+/// Adds the files of the crate at `manifest_path` to `key`, along with
+/// those of the crates it depends on by path, e.g. the rs-stdlib, and
+/// the lockfile its dependencies are resolved with
+fn push_crate(key: &mut CacheKey, manifest_path: &Path) -> io::Result<()> {
+    let manifest_path = manifest_path.canonicalize()?;
+    if let Some(lockfile) = lockfile(&manifest_path) {
+        key.push("Cargo.lock").push(fs::read(lockfile)?);
+    }
+
+    let mut crates = BTreeSet::new();
+    let mut pending = vec![manifest_path];
+    while let Some(manifest_path) = pending.pop() {
+        let crate_directory = manifest_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        if !crates.insert(crate_directory.clone()) {
+            continue;
+        }
+        let manifest_source_code = fs::read_to_string(&manifest_path)?;
+        for dependency in path_dependencies(&manifest_source_code).map_err(io::Error::other)? {
+            let dependency_manifest = crate_directory.join(dependency).join("Cargo.toml");
+            pending.push(dependency_manifest.canonicalize()?);
+        }
+    }
+
+    for crate_directory in crates {
+        let mut files = Vec::new();
+        crate_files(&crate_directory, &mut files)?;
+        files.sort();
+        for file in files {
+            let relative = file.strip_prefix(&crate_directory).unwrap_or(&file);
+            key.push(relative.to_string_lossy().as_bytes())
+                .push(fs::read(&file)?);
+        }
+    }
+    Ok(())
+}
+
+/// The `Cargo.lock` next to the manifest or, for a workspace member, the
+/// one of its workspace root, found by walking up from the manifest
+fn lockfile(manifest_path: &Path) -> Option<PathBuf> {
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lockfile| lockfile.is_file())
+}
+
+/// The paths of the dependencies of a manifest, build & target specific ones included
+fn path_dependencies(manifest_source_code: &str) -> Result<Vec<String>, toml::de::Error> {
+    let manifest: Table = manifest_source_code.parse()?;
+    let targets = manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(Value::as_table));
+    let paths = std::iter::once(&manifest)
+        .chain(targets)
+        .flat_map(|table| {
+            ["dependencies", "build-dependencies"]
+                .into_iter()
+                .filter_map(|section| table.get(section)?.as_table())
+        })
+        .flat_map(Table::values)
+        .filter_map(|dependency| dependency.get("path")?.as_str())
+        .map(String::from)
+        .collect();
+    Ok(paths)
+}
+
+/// The files of a crate, without build output or hidden directories
+fn crate_files(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            let name = entry.file_name();
+            if name != "target" && !name.to_string_lossy().starts_with('.') {
+                crate_files(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_dependencies_are_found() {
+        let manifest = r#"
+            [package]
+            name = "xpath"
+
+            [dependencies]
+            wee_alloc = "0.4.5"
+            wastrumentation-rs-stdlib = { path = "../wastrumentation-rs-stdlib", features = [
+                "std",
+            ] }
+            xpath = "1"
+            xpath-utils = { version = "1", path="../utils" }
+
+            [target.'cfg(unix)'.build-dependencies]
+            build-utils = { path = "../build-utils" }
+
+            [dev-dependencies]
+            test-utils = { path = "../test-utils" }
+        "#;
+        let mut paths = path_dependencies(manifest).unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec!["../build-utils", "../utils", "../wastrumentation-rs-stdlib"]
+        );
+    }
 }
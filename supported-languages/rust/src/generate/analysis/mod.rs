@@ -13,7 +13,12 @@ pub struct RustAnalysisSpec {
 impl From<RustAnalysisSpec> for ProcessedAnalysis<Rust> {
     fn from(value: RustAnalysisSpec) -> Self {
         let RustAnalysisSpec { ref hooks, source } = value;
-        let analysis_interface: AnalysisInterface = interface_from(hooks);
+        let analysis_interface = AnalysisInterface {
+            // The rs-stdlib keeps the source locations of every analysis
+            source_location: Some(AnalysisInterface::interface_source_location()),
+            source_file_byte: Some(AnalysisInterface::interface_source_file_byte()),
+            ..interface_from(hooks)
+        };
 
         ProcessedAnalysis {
             analysis_interface,
@@ -29,6 +34,12 @@ pub enum Hook {
     CallPost,
     CallIndirectPre,
     CallIndirectPost,
+    /// Before a `call` of an imported function. An import reached through
+    /// `call_indirect` is not reported, only by `CallIndirectPre`.
+    ImportCallPre,
+    /// After a `call` of an imported function. An import reached through
+    /// `call_indirect` is not reported, only by `CallIndirectPost`.
+    ImportCallPost,
     IfThen,
     IfThenPost,
     IfThenElse,
@@ -41,6 +52,8 @@ pub enum Hook {
     Binary,
     Drop,
     Return,
+    FunctionEntry,
+    FunctionExit,
     Const,
     Local,
     Global,
@@ -48,10 +61,16 @@ pub enum Hook {
     Load,
     MemorySize,
     MemoryGrow,
+    MemoryFill,
+    MemoryCopy,
+    MemoryInit,
+    DataDrop,
     BlockPre,
     BlockPost,
     LoopPre,
     LoopPost,
+    LoopIteration,
+    BasicBlockEnter,
     RefFunc,
     RefNull,
     RefIsNull,
@@ -66,6 +85,15 @@ pub enum Hook {
 }
 
 impl Hook {
+    /// The names of the traps the hook installs, to select its targets by
+    pub fn trap_names(&self) -> Vec<String> {
+        interface_from(&HashSet::from([*self]))
+            .exports()
+            .into_iter()
+            .map(|export| export.name.clone())
+            .collect()
+    }
+
     pub fn all_hooks() -> HashSet<Self> {
         use Hook::*;
         HashSet::from([
@@ -74,6 +102,8 @@ impl Hook {
             CallPost,
             CallIndirectPre,
             CallIndirectPost,
+            ImportCallPre,
+            ImportCallPost,
             IfThen,
             IfThenPost,
             IfThenElse,
@@ -86,6 +116,8 @@ impl Hook {
             Binary,
             Drop,
             Return,
+            FunctionEntry,
+            FunctionExit,
             Const,
             Local,
             Global,
@@ -93,10 +125,16 @@ impl Hook {
             Load,
             MemorySize,
             MemoryGrow,
+            MemoryFill,
+            MemoryCopy,
+            MemoryInit,
+            DataDrop,
             BlockPre,
             BlockPost,
             LoopPre,
             LoopPost,
+            LoopIteration,
+            BasicBlockEnter,
             RefFunc,
             RefNull,
             RefIsNull,
@@ -133,6 +171,14 @@ pub fn interface_from(hooks: &HashSet<Hook>) -> AnalysisInterface {
                 interface.post_trap_call_indirect =
                     Some(AnalysisInterface::interface_call_indirect_post())
             }
+            Hook::ImportCallPre => {
+                interface.import_call_pre = Some(AnalysisInterface::interface_import_call_pre());
+                interface.import_name_byte = Some(AnalysisInterface::interface_import_name_byte());
+            }
+            Hook::ImportCallPost => {
+                interface.import_call_post = Some(AnalysisInterface::interface_import_call_post());
+                interface.import_name_byte = Some(AnalysisInterface::interface_import_name_byte());
+            }
             Hook::IfThen => interface.if_then_trap = Some(AnalysisInterface::interface_if_then()),
             Hook::IfThenPost => {
                 interface.if_then_post_trap = Some(AnalysisInterface::interface_if_then_post())
@@ -186,6 +232,12 @@ pub fn interface_from(hooks: &HashSet<Hook>) -> AnalysisInterface {
             }
             Hook::Drop => interface.drop_trap = Some(AnalysisInterface::interface_drop()),
             Hook::Return => interface.return_trap = Some(AnalysisInterface::interface_return()),
+            Hook::FunctionEntry => {
+                interface.function_entry = Some(AnalysisInterface::interface_function_entry())
+            }
+            Hook::FunctionExit => {
+                interface.function_exit = Some(AnalysisInterface::interface_function_exit())
+            }
             Hook::Const => {
                 interface.const_i32_trap = Some(AnalysisInterface::interface_const_i32());
                 interface.const_f32_trap = Some(AnalysisInterface::interface_const_f32());
@@ -234,6 +286,36 @@ pub fn interface_from(hooks: &HashSet<Hook>) -> AnalysisInterface {
             Hook::MemoryGrow => {
                 interface.memory_grow = Some(AnalysisInterface::interface_memory_grow())
             }
+            Hook::MemoryFill => {
+                interface.memory_fill = Some(AnalysisInterface::interface_memory_fill());
+                interface.memory_fill_get_destination =
+                    Some(AnalysisInterface::interface_memory_fill_get_destination());
+                interface.memory_fill_get_value =
+                    Some(AnalysisInterface::interface_memory_fill_get_value());
+                interface.memory_fill_get_size =
+                    Some(AnalysisInterface::interface_memory_fill_get_size());
+            }
+            Hook::MemoryCopy => {
+                interface.memory_copy = Some(AnalysisInterface::interface_memory_copy());
+                interface.memory_copy_get_destination =
+                    Some(AnalysisInterface::interface_memory_copy_get_destination());
+                interface.memory_copy_get_source =
+                    Some(AnalysisInterface::interface_memory_copy_get_source());
+                interface.memory_copy_get_size =
+                    Some(AnalysisInterface::interface_memory_copy_get_size());
+            }
+            Hook::MemoryInit => {
+                interface.memory_init = Some(AnalysisInterface::interface_memory_init());
+                interface.memory_init_get_destination =
+                    Some(AnalysisInterface::interface_memory_init_get_destination());
+                interface.memory_init_get_source =
+                    Some(AnalysisInterface::interface_memory_init_get_source());
+                interface.memory_init_get_size =
+                    Some(AnalysisInterface::interface_memory_init_get_size());
+            }
+            Hook::DataDrop => {
+                interface.data_drop = Some(AnalysisInterface::interface_data_drop());
+            }
             Hook::BlockPre => {
                 interface.pre_block = Some(AnalysisInterface::interface_pre_block());
             }
@@ -246,6 +328,13 @@ pub fn interface_from(hooks: &HashSet<Hook>) -> AnalysisInterface {
             Hook::LoopPost => {
                 interface.post_loop = Some(AnalysisInterface::interface_post_loop());
             }
+            Hook::LoopIteration => {
+                interface.loop_iteration = Some(AnalysisInterface::interface_loop_iteration());
+            }
+            Hook::BasicBlockEnter => {
+                interface.basic_block_enter =
+                    Some(AnalysisInterface::interface_basic_block_enter());
+            }
             Hook::RefFunc => {
                 interface.ref_func = Some(AnalysisInterface::interface_ref_func());
             }
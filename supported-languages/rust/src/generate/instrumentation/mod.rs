@@ -62,8 +62,7 @@ impl RustSignature<'_> {
             WasmType::F32 => "f32".to_string(),
             WasmType::I64 => "i64".to_string(),
             WasmType::F64 => "f64".to_string(),
-            WasmType::Ref(RefType::ExternRef) => "extern_ref".to_string(),
-            WasmType::Ref(RefType::FuncRef) => "func_ref".to_string(),
+            WasmType::Ref(_) => "ref_handle".to_string(),
         }
     }
 }
@@ -111,7 +110,7 @@ fn generate_allocate_specialized(signature: &RustSignature) -> String {
     let signature_args_typs_ident = signature_args
         .iter()
         .enumerate()
-        .map(|(index, ty)| format!("a{index}: {ty}"))
+        .map(|(index, ty)| format!("a{index}: {}", ty.stack_type_name()))
         .collect::<Vec<String>>()
         .join(", ");
 
@@ -217,6 +216,7 @@ fn generate_load_specialized(signature: &RustSignature) -> String {
     let all_arg_loads = signature_args.iter().enumerate().map(|(index, arg_type)| {
         let mangled_name = signature.generate_load_name(SignatureSide::Argument, index);
         let accessor = RustSignature::wasmvalue_accessor_for_type(arg_type);
+        let arg_type = arg_type.stack_type_name();
         format!(
             "
 #[no_mangle]
@@ -230,6 +230,7 @@ pub extern \"C\" fn {mangled_name}(stack_ptr: usize) -> {arg_type} {{
     let all_ret_loads = signature_rets.iter().enumerate().map(|(index, ret_type)| {
         let mangled_name = signature.generate_load_name(SignatureSide::Return, index);
         let accessor = RustSignature::wasmvalue_accessor_for_type(ret_type);
+        let ret_type = ret_type.stack_type_name();
         format!(
             "
 #[no_mangle]
@@ -284,6 +285,7 @@ fn generate_store_specialized(signature: &RustSignature) -> String {
     let all_arg_stores = signature_args.iter().enumerate().map(|(index, arg_type)| {
         let mangled_name = signature.generate_store_name(SignatureSide::Argument, index);
         let constructor = RustSignature::wasmvalue_constructor_for_type(arg_type);
+        let arg_type = arg_type.stack_type_name();
         format!(
             "
 #[no_mangle]
@@ -296,6 +298,7 @@ pub extern \"C\" fn {mangled_name}(stack_ptr: usize, a{index}: {arg_type}) {{
     let all_ret_stores = signature_rets.iter().enumerate().map(|(index, ret_type)| {
         let mangled_name = signature.generate_store_name(SignatureSide::Return, index);
         let constructor = RustSignature::wasmvalue_constructor_for_type(ret_type);
+        let ret_type = ret_type.stack_type_name();
         format!(
             "
 #[no_mangle]
@@ -407,7 +410,7 @@ fn generate_store_rets_specialized(signature: &RustSignature) -> String {
     let rets_signature = signature_rets
         .iter()
         .enumerate()
-        .map(|(index, ty)| format!("a{index}: {ty}"));
+        .map(|(index, ty)| format!("a{index}: {}", ty.stack_type_name()));
 
     let total_signature = (vec![String::from("stack_ptr: usize")])
         .into_iter()
@@ -523,14 +526,8 @@ pub union WasmValue {
     pub f32: f32,
     pub i64: i64,
     pub f64: f64,
-    pub ref_type: RefType,
-}
-
-#[repr(C)]
-#[derive(Clone, Copy)]
-pub enum RefType {
-    FuncRef,
-    ExternRef,
+    // References are kept by the instrumented module, only their handle is stored
+    pub ref_handle: i32,
 }
 
 impl WasmValue {
@@ -543,9 +540,9 @@ impl WasmValue {
     #[inline(always)] #[must_use]
     pub const fn new_f64(val: f64) -> Self { WasmValue { f64: val } }
     #[inline(always)] #[must_use]
-    pub const fn new_func_ref() -> Self { WasmValue { ref_type: RefType::FuncRef } }
+    pub const fn new_func_ref(handle: i32) -> Self { WasmValue { ref_handle: handle } }
     #[inline(always)] #[must_use]
-    pub const fn new_extern_ref() -> Self { WasmValue { ref_type: RefType::ExternRef } }
+    pub const fn new_extern_ref(handle: i32) -> Self { WasmValue { ref_handle: handle } }
 }
 
 #[cfg(not(test))]
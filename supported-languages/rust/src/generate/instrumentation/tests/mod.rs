@@ -22,6 +22,10 @@ fn test_compiles() {
         return_types: vec![WasmType::I32],
         argument_types: vec![],
     });
+    hash_set.insert(Signature {
+        return_types: vec![WasmType::Ref(RefType::ExternRef)],
+        argument_types: vec![WasmType::Ref(RefType::FuncRef), WasmType::I32],
+    });
     let signatures: Vec<Signature> = hash_set.into_iter().collect();
 
     let (ManifestSource(manifest), RustSourceCode(rust_source)) = generate_lib(&signatures);
@@ -280,6 +284,29 @@ pub extern \"C\" fn load_ret3_ret_f64_f32_i32_i64_arg_i64_i32_f32_f64(stack_ptr:
     );
 }
 
+#[test]
+fn generating_load_specialized_instructions_for_references() {
+    let signature = Signature {
+        return_types: vec![WasmType::Ref(RefType::ExternRef)],
+        argument_types: vec![WasmType::Ref(RefType::FuncRef)],
+    };
+    assert_eq!(
+        generate_load_specialized(&RustSignature(&signature)),
+        "
+#[no_mangle]
+pub extern \"C\" fn load_arg0_ret_ref_extern_arg_ref_func(stack_ptr: usize) -> i32 {
+    let val = load_arg0_ret_1_arg_1(stack_ptr);
+    unsafe { val.ref_handle }
+}
+
+#[no_mangle]
+pub extern \"C\" fn load_ret0_ret_ref_extern_arg_ref_func(stack_ptr: usize) -> i32 {
+    let val = load_ret0_ret_1_arg_1(stack_ptr);
+    unsafe { val.ref_handle }
+}"
+    );
+}
+
 #[test]
 fn generating_store_generic_instructions() {
     assert_eq!(
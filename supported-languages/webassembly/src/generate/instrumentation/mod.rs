@@ -25,8 +25,8 @@ impl AsWasmType for WasmType {
             WasmType::F32 => "f32",
             WasmType::I64 => "i64",
             WasmType::F64 => "f64",
-            WasmType::Ref(wastrumentation::wasm_constructs::RefType::FuncRef) => "funcref",
-            WasmType::Ref(wastrumentation::wasm_constructs::RefType::ExternRef) => "externref",
+            // References cross the stack library as `i32` handles
+            WasmType::Ref(_) => "i32",
         }
     }
 }
@@ -48,13 +48,13 @@ impl WatSignature<'_> {
         let ret_types = self
             .return_types
             .iter()
-            .map(WasmType::wat_type)
+            .map(ToString::to_string)
             .collect::<Vec<_>>()
             .join("_");
         let arg_types = self
             .argument_types
             .iter()
-            .map(WasmType::wat_type)
+            .map(ToString::to_string)
             .collect::<Vec<_>>()
             .join("_");
 
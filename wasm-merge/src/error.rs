@@ -1,5 +1,7 @@
 use std::io::Error as ErrorIO;
 use thiserror::Error;
+use wasm_encoder::reencode::Error as ReencodeError;
+use wasmparser::BinaryReaderError;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -17,4 +19,28 @@ pub enum Error {
     MergeExecutionFailedReason(String),
     #[error("Could not read result from written output: {0}")]
     ReadFromOutputFileFailed(ErrorIO),
+    #[error("Could not parse input module `{namespace}`: {error}")]
+    ParseFailed {
+        namespace: String,
+        error: BinaryReaderError,
+    },
+    #[error("Input module `{0}` is a component, only core modules can be merged")]
+    ComponentNotSupported(String),
+    #[error("Could not re-encode input module `{namespace}`: {error}")]
+    ReencodeFailed {
+        namespace: String,
+        error: ReencodeError<IndexOutOfBounds>,
+    },
+    #[error("Export `{0}` is exported by multiple input modules")]
+    ExportConflict(String),
+    #[error("Merged module is invalid: {0}")]
+    ValidationFailed(BinaryReaderError),
+}
+
+/// An index that refers past the items of its kind in an input module
+#[derive(Error, Debug)]
+#[error("{kind} index {index} is out of bounds")]
+pub struct IndexOutOfBounds {
+    pub kind: &'static str,
+    pub index: u32,
 }
@@ -0,0 +1,642 @@
+//! Merging in-process: the input modules are parsed with
+//! `wasmparser` and re-encoded into one module with `wasm-encoder`.
+//!
+//! An import whose module is the namespace of an input module, and
+//! whose name that input module exports, is resolved to the exported
+//! item. Every index space of the merged module holds the imports that
+//! remain, followed by the items of each input module, in order. The
+//! primary module goes first, so its memory & table receive index 0.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use wasm_encoder::reencode::{self, Reencode};
+use wasm_encoder::{
+    CodeSection, CustomSection, DataCountSection, DataSection, ElementSection, ExportKind,
+    ExportSection, FunctionSection, GlobalSection, ImportSection, Instruction, MemorySection,
+    NameMap, NameSection, StartSection, TableSection, TagSection, TypeSection,
+};
+use wasmparser::{
+    BinaryReaderError, CustomSectionReader, DataSectionReader, ElementSectionReader, Encoding,
+    Export, ExternalKind, FunctionBody, FunctionSectionReader, GlobalSectionReader, Import,
+    KnownCustom, MemorySectionReader, Name, Parser, Payload, TableSectionReader, TagSectionReader,
+    TypeRef, TypeSectionReader, Validator,
+};
+
+use crate::error::{Error, IndexOutOfBounds};
+use crate::options::{DebugInfo, NoValidate, RenameExportConflicts};
+use crate::{InputModule, MergeOptions};
+
+type ReencodeError = reencode::Error<IndexOutOfBounds>;
+
+/// The index spaces that imports & exports refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Function,
+    Table,
+    Memory,
+    Global,
+    Tag,
+}
+
+impl Kind {
+    const ALL: [Kind; 5] = [
+        Kind::Function,
+        Kind::Table,
+        Kind::Memory,
+        Kind::Global,
+        Kind::Tag,
+    ];
+
+    #[allow(clippy::match_wildcard_for_single_variants)]
+    fn of_import(ty: TypeRef) -> Self {
+        match ty {
+            TypeRef::Table(_) => Kind::Table,
+            TypeRef::Memory(_) => Kind::Memory,
+            TypeRef::Global(_) => Kind::Global,
+            TypeRef::Tag(_) => Kind::Tag,
+            // Functions, exact or not
+            _ => Kind::Function,
+        }
+    }
+
+    #[allow(clippy::match_wildcard_for_single_variants)]
+    fn of_export(kind: ExternalKind) -> Self {
+        match kind {
+            ExternalKind::Table => Kind::Table,
+            ExternalKind::Memory => Kind::Memory,
+            ExternalKind::Global => Kind::Global,
+            ExternalKind::Tag => Kind::Tag,
+            // Functions, exact or not
+            _ => Kind::Function,
+        }
+    }
+
+    fn export_kind(self) -> ExportKind {
+        match self {
+            Kind::Function => ExportKind::Func,
+            Kind::Table => ExportKind::Table,
+            Kind::Memory => ExportKind::Memory,
+            Kind::Global => ExportKind::Global,
+            Kind::Tag => ExportKind::Tag,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Function => "function",
+            Kind::Table => "table",
+            Kind::Memory => "memory",
+            Kind::Global => "global",
+            Kind::Tag => "tag",
+        }
+    }
+}
+
+fn count(n: usize) -> u32 {
+    u32::try_from(n).expect("index spaces are bounded by u32")
+}
+
+/// The sections of an input module
+#[derive(Default)]
+struct Parsed<'a> {
+    namespace: &'a str,
+    types: Option<TypeSectionReader<'a>>,
+    imports: Vec<Import<'a>>,
+    /// Per kind, the positions of its imports in `imports`
+    kind_imports: [Vec<usize>; 5],
+    functions: Option<FunctionSectionReader<'a>>,
+    tables: Option<TableSectionReader<'a>>,
+    memories: Option<MemorySectionReader<'a>>,
+    tags: Option<TagSectionReader<'a>>,
+    globals: Option<GlobalSectionReader<'a>>,
+    exports: Vec<Export<'a>>,
+    start: Option<u32>,
+    elements: Option<ElementSectionReader<'a>>,
+    data_count: Option<u32>,
+    data: Option<DataSectionReader<'a>>,
+    code: Vec<FunctionBody<'a>>,
+    custom_sections: Vec<CustomSectionReader<'a>>,
+}
+
+impl<'a> Parsed<'a> {
+    fn parse(input_module: &'a InputModule<'a>) -> Result<Self, Error> {
+        let InputModule { module, namespace } = input_module;
+        let parse_error = |error| Error::ParseFailed {
+            namespace: namespace.clone(),
+            error,
+        };
+
+        let mut parsed = Parsed {
+            namespace,
+            ..Default::default()
+        };
+        for payload in Parser::new(0).parse_all(module) {
+            match payload.map_err(parse_error)? {
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    ..
+                } => return Err(Error::ComponentNotSupported(namespace.clone())),
+                Payload::TypeSection(reader) => parsed.types = Some(reader),
+                Payload::ImportSection(reader) => {
+                    parsed.imports = reader
+                        .into_iter()
+                        .collect::<Result<_, _>>()
+                        .map_err(parse_error)?;
+                }
+                Payload::FunctionSection(reader) => parsed.functions = Some(reader),
+                Payload::TableSection(reader) => parsed.tables = Some(reader),
+                Payload::MemorySection(reader) => parsed.memories = Some(reader),
+                Payload::TagSection(reader) => parsed.tags = Some(reader),
+                Payload::GlobalSection(reader) => parsed.globals = Some(reader),
+                Payload::ExportSection(reader) => {
+                    parsed.exports = reader
+                        .into_iter()
+                        .collect::<Result<_, _>>()
+                        .map_err(parse_error)?;
+                }
+                Payload::StartSection { func, .. } => parsed.start = Some(func),
+                Payload::ElementSection(reader) => parsed.elements = Some(reader),
+                Payload::DataCountSection { count, .. } => parsed.data_count = Some(count),
+                Payload::DataSection(reader) => parsed.data = Some(reader),
+                Payload::CodeSectionEntry(body) => parsed.code.push(body),
+                Payload::CustomSection(reader) => parsed.custom_sections.push(reader),
+                _ => (),
+            }
+        }
+        for (position, import) in parsed.imports.iter().enumerate() {
+            parsed.kind_imports[Kind::of_import(import.ty) as usize].push(position);
+        }
+        Ok(parsed)
+    }
+
+    /// The `index`-th import of `kind`
+    fn import(&self, kind: Kind, index: u32) -> Option<&Import<'a>> {
+        let position = self.kind_imports[kind as usize].get(index as usize)?;
+        Some(&self.imports[*position])
+    }
+
+    /// The number of imports of `kind`
+    fn imported(&self, kind: Kind) -> u32 {
+        count(self.kind_imports[kind as usize].len())
+    }
+
+    /// The number of items of `kind` the module defines
+    fn defined(&self, kind: Kind) -> u32 {
+        match kind {
+            Kind::Function => self.functions.as_ref().map_or(0, |r| r.count()),
+            Kind::Table => self.tables.as_ref().map_or(0, |r| r.count()),
+            Kind::Memory => self.memories.as_ref().map_or(0, |r| r.count()),
+            Kind::Global => self.globals.as_ref().map_or(0, |r| r.count()),
+            Kind::Tag => self.tags.as_ref().map_or(0, |r| r.count()),
+        }
+    }
+
+    fn type_count(&self) -> Result<u32, BinaryReaderError> {
+        let mut types = 0;
+        for rec_group in self.types.iter().flat_map(Clone::clone) {
+            types += rec_group?.types().count();
+        }
+        Ok(count(types))
+    }
+
+    fn export(&self, name: &str, kind: Kind) -> Option<u32> {
+        self.exports
+            .iter()
+            .find(|export| export.name == name && Kind::of_export(export.kind) == kind)
+            .map(|export| export.index)
+    }
+}
+
+/// Where an item of an input module originates from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// The `index`-th item of its kind that `module` defines
+    Defined { module: usize, index: u32 },
+    /// The `index`-th import of its kind of `module`, which remains an import
+    Imported { module: usize, index: u32 },
+}
+
+/// Follows the `index`-th item of `kind` of `module` through the imports it
+/// resolves to, up to the module defining it or the import that remains.
+fn origin(modules: &[Parsed], kind: Kind, module: usize, index: u32) -> Origin {
+    let (mut module, mut index) = (module, index);
+    let mut visited = HashSet::new();
+    loop {
+        let Some(import) = modules[module].import(kind, index) else {
+            return Origin::Defined {
+                module,
+                index: index - modules[module].imported(kind),
+            };
+        };
+        let exported = modules
+            .iter()
+            .enumerate()
+            .filter(|(_, exporter)| exporter.namespace == import.module)
+            .find_map(|(exporter, parsed)| {
+                parsed
+                    .export(import.name, kind)
+                    .map(|index| (exporter, index))
+            });
+        match exported {
+            Some(exported) if visited.insert((module, index)) => (module, index) = exported,
+            // Not exported by an input module, or cyclic
+            _ => return Origin::Imported { module, index },
+        }
+    }
+}
+
+/// The merged index of every item of every input module
+struct Layout {
+    /// Per module, the merged indices of its items, indexed by kind
+    indices: Vec<[Vec<u32>; 5]>,
+    /// The imports that remain, as module & position in its import section
+    imports: Vec<(usize, usize)>,
+    /// Per module, the merged index of its first type, element & data segment
+    types: Vec<u32>,
+    elements: Vec<u32>,
+    data: Vec<u32>,
+    type_count: u32,
+    function_count: u32,
+    data_count: u32,
+}
+
+impl Layout {
+    fn new(modules: &[Parsed]) -> Result<Self, Error> {
+        // The imports that remain come first, numbered per kind in order
+        let mut imports = Vec::new();
+        let mut import_indices: HashMap<(Kind, usize, u32), u32> = HashMap::new();
+        let mut next = [0_u32; 5];
+        for (module, parsed) in modules.iter().enumerate() {
+            let mut kind_indices = [0_u32; 5];
+            for (position, import) in parsed.imports.iter().enumerate() {
+                let kind = Kind::of_import(import.ty);
+                let index = kind_indices[kind as usize];
+                kind_indices[kind as usize] += 1;
+                if origin(modules, kind, module, index) == (Origin::Imported { module, index }) {
+                    import_indices.insert((kind, module, index), next[kind as usize]);
+                    next[kind as usize] += 1;
+                    imports.push((module, position));
+                }
+            }
+        }
+
+        // Followed by the items every module defines
+        let mut offsets = Vec::with_capacity(modules.len());
+        for parsed in modules {
+            offsets.push(next);
+            for kind in Kind::ALL {
+                next[kind as usize] += parsed.defined(kind);
+            }
+        }
+
+        let indices = modules
+            .iter()
+            .enumerate()
+            .map(|(module, parsed)| {
+                Kind::ALL.map(|kind| {
+                    let items = parsed.imported(kind) + parsed.defined(kind);
+                    (0..items)
+                        .map(|index| match origin(modules, kind, module, index) {
+                            Origin::Defined { module, index } => {
+                                offsets[module][kind as usize] + index
+                            }
+                            Origin::Imported { module, index } => {
+                                import_indices[&(kind, module, index)]
+                            }
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+
+        let mut types = Vec::with_capacity(modules.len());
+        let mut elements = Vec::with_capacity(modules.len());
+        let mut data = Vec::with_capacity(modules.len());
+        let (mut type_count, mut element_count, mut data_count) = (0, 0, 0);
+        for parsed in modules {
+            types.push(type_count);
+            elements.push(element_count);
+            data.push(data_count);
+            type_count += parsed.type_count().map_err(|error| Error::ParseFailed {
+                namespace: parsed.namespace.to_string(),
+                error,
+            })?;
+            element_count += parsed.elements.as_ref().map_or(0, |r| r.count());
+            data_count += parsed.data.as_ref().map_or(0, |r| r.count());
+        }
+
+        Ok(Self {
+            indices,
+            imports,
+            types,
+            elements,
+            data,
+            type_count,
+            function_count: next[Kind::Function as usize],
+            data_count,
+        })
+    }
+
+    fn remap(&self, module: usize) -> Remap<'_> {
+        Remap {
+            indices: &self.indices[module],
+            types: self.types[module],
+            elements: self.elements[module],
+            data: self.data[module],
+        }
+    }
+}
+
+/// Re-encodes the items of an input module into the merged index spaces
+struct Remap<'a> {
+    indices: &'a [Vec<u32>; 5],
+    types: u32,
+    elements: u32,
+    data: u32,
+}
+
+impl Remap<'_> {
+    fn index(&self, kind: Kind, index: u32) -> Result<u32, ReencodeError> {
+        self.indices[kind as usize]
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| {
+                reencode::Error::UserError(IndexOutOfBounds {
+                    kind: kind.name(),
+                    index,
+                })
+            })
+    }
+}
+
+impl Reencode for Remap<'_> {
+    type Error = IndexOutOfBounds;
+
+    fn type_index(&mut self, ty: u32) -> Result<u32, ReencodeError> {
+        Ok(self.types + ty)
+    }
+
+    fn function_index(&mut self, func: u32) -> Result<u32, ReencodeError> {
+        self.index(Kind::Function, func)
+    }
+
+    fn table_index(&mut self, table: u32) -> Result<u32, ReencodeError> {
+        self.index(Kind::Table, table)
+    }
+
+    fn memory_index(&mut self, memory: u32) -> Result<u32, ReencodeError> {
+        self.index(Kind::Memory, memory)
+    }
+
+    fn global_index(&mut self, global: u32) -> Result<u32, ReencodeError> {
+        self.index(Kind::Global, global)
+    }
+
+    fn tag_index(&mut self, tag: u32) -> Result<u32, ReencodeError> {
+        self.index(Kind::Tag, tag)
+    }
+
+    fn element_index(&mut self, element: u32) -> Result<u32, ReencodeError> {
+        Ok(self.elements + element)
+    }
+
+    fn data_index(&mut self, data: u32) -> Result<u32, ReencodeError> {
+        Ok(self.data + data)
+    }
+}
+
+/// The sections of the merged module
+#[derive(Default)]
+struct Sections {
+    types: TypeSection,
+    imports: ImportSection,
+    functions: FunctionSection,
+    tables: TableSection,
+    memories: MemorySection,
+    tags: TagSection,
+    globals: GlobalSection,
+    exports: ExportSection,
+    start: Option<StartSection>,
+    elements: ElementSection,
+    data_count: Option<DataCountSection>,
+    code: CodeSection,
+    data: DataSection,
+    names: BTreeMap<u32, String>,
+    custom_sections: Vec<CustomSection<'static>>,
+}
+
+impl Sections {
+    /// Appends the items `parsed` defines
+    fn append(&mut self, parsed: &Parsed, remap: &mut Remap) -> Result<(), ReencodeError> {
+        if let Some(types) = &parsed.types {
+            remap.parse_type_section(&mut self.types, types.clone())?;
+        }
+        if let Some(functions) = &parsed.functions {
+            remap.parse_function_section(&mut self.functions, functions.clone())?;
+        }
+        if let Some(tables) = &parsed.tables {
+            remap.parse_table_section(&mut self.tables, tables.clone())?;
+        }
+        if let Some(memories) = &parsed.memories {
+            remap.parse_memory_section(&mut self.memories, memories.clone())?;
+        }
+        if let Some(tags) = &parsed.tags {
+            remap.parse_tag_section(&mut self.tags, tags.clone())?;
+        }
+        if let Some(globals) = &parsed.globals {
+            remap.parse_global_section(&mut self.globals, globals.clone())?;
+        }
+        if let Some(elements) = &parsed.elements {
+            remap.parse_element_section(&mut self.elements, elements.clone())?;
+        }
+        for body in &parsed.code {
+            remap.parse_function_body(&mut self.code, body.clone())?;
+        }
+        if let Some(data) = &parsed.data {
+            remap.parse_data_section(&mut self.data, data.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Appends the function names & the custom sections of `parsed`, the
+    /// DWARF sections are dropped as the code offsets no longer hold
+    fn append_custom_sections(
+        &mut self,
+        parsed: &Parsed,
+        remap: &mut Remap,
+    ) -> Result<(), ReencodeError> {
+        for custom_section in &parsed.custom_sections {
+            match custom_section.as_known() {
+                KnownCustom::Name(names) => {
+                    for name in names {
+                        let Name::Function(function_names) = name? else {
+                            continue;
+                        };
+                        for naming in function_names {
+                            let naming = naming?;
+                            // A name of the module defining the function takes precedence
+                            self.names
+                                .entry(remap.function_index(naming.index)?)
+                                .or_insert_with(|| naming.name.to_string());
+                        }
+                    }
+                }
+                _ if custom_section.name().starts_with(".debug_") => (),
+                _ => self.custom_sections.push(CustomSection {
+                    name: custom_section.name().to_string().into(),
+                    data: custom_section.data().to_vec().into(),
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut module = wasm_encoder::Module::new();
+        module
+            .section(&self.types)
+            .section(&self.imports)
+            .section(&self.functions)
+            .section(&self.tables)
+            .section(&self.memories);
+        if !self.tags.is_empty() {
+            module.section(&self.tags);
+        }
+        module.section(&self.globals).section(&self.exports);
+        if let Some(start) = &self.start {
+            module.section(start);
+        }
+        module.section(&self.elements);
+        if let Some(data_count) = &self.data_count {
+            module.section(data_count);
+        }
+        module.section(&self.code).section(&self.data);
+        if !self.names.is_empty() {
+            let mut function_names = NameMap::new();
+            for (index, name) in &self.names {
+                function_names.append(*index, name);
+            }
+            let mut names = NameSection::new();
+            names.functions(&function_names);
+            module.section(&names);
+        }
+        for custom_section in &self.custom_sections {
+            module.section(custom_section);
+        }
+        module.finish()
+    }
+}
+
+/// The name `name` is exported under, given the names exported so far
+fn export_name(
+    name: &str,
+    exported: &HashSet<String>,
+    rename_export_conflicts: &RenameExportConflicts,
+) -> Result<String, Error> {
+    if !exported.contains(name) {
+        return Ok(name.to_string());
+    }
+    match rename_export_conflicts {
+        RenameExportConflicts::Disable => Err(Error::ExportConflict(name.to_string())),
+        RenameExportConflicts::Enable => Ok((1..)
+            .map(|n| format!("{name}_{n}"))
+            .find(|renamed| !exported.contains(renamed))
+            .expect("some suffix is free")),
+    }
+}
+
+pub(crate) fn merge(merge_options: &MergeOptions) -> Result<Vec<u8>, Error> {
+    let MergeOptions {
+        primary,
+        input_modules,
+        no_validation,
+        rename_export_conflicts,
+        debug_info,
+        ..
+    } = merge_options;
+
+    let modules = primary
+        .iter()
+        .chain(input_modules)
+        .map(Parsed::parse)
+        .collect::<Result<Vec<Parsed>, Error>>()?;
+    let layout = Layout::new(&modules)?;
+
+    let mut sections = Sections::default();
+    let mut exported = HashSet::new();
+    let mut starts = Vec::new();
+    for (module, parsed) in modules.iter().enumerate() {
+        let reencode_error = |error| Error::ReencodeFailed {
+            namespace: parsed.namespace.to_string(),
+            error,
+        };
+        let mut remap = layout.remap(module);
+        sections
+            .append(parsed, &mut remap)
+            .map_err(reencode_error)?;
+        if let DebugInfo::Enable = debug_info {
+            sections
+                .append_custom_sections(parsed, &mut remap)
+                .map_err(reencode_error)?;
+        }
+        for export in &parsed.exports {
+            let kind = Kind::of_export(export.kind);
+            let index = remap.index(kind, export.index).map_err(reencode_error)?;
+            let name = export_name(export.name, &exported, rename_export_conflicts)?;
+            sections.exports.export(&name, kind.export_kind(), index);
+            exported.insert(name);
+        }
+        if let Some(start) = parsed.start {
+            starts.push(remap.index(Kind::Function, start).map_err(reencode_error)?);
+        }
+    }
+
+    for (module, position) in &layout.imports {
+        let import = &modules[*module].imports[*position];
+        let entity_type = layout
+            .remap(*module)
+            .entity_type(import.ty)
+            .map_err(|error| Error::ReencodeFailed {
+                namespace: modules[*module].namespace.to_string(),
+                error,
+            })?;
+        sections
+            .imports
+            .import(import.module, import.name, entity_type);
+    }
+
+    // Several start functions are called in module order, from a new one
+    sections.start = match starts.as_slice() {
+        [] => None,
+        [start] => Some(StartSection {
+            function_index: *start,
+        }),
+        _ => {
+            sections.types.ty().function([], []);
+            sections.functions.function(layout.type_count);
+            let mut start = wasm_encoder::Function::new([]);
+            for function in &starts {
+                start.instruction(&Instruction::Call(*function));
+            }
+            start.instruction(&Instruction::End);
+            sections.code.function(&start);
+            Some(StartSection {
+                function_index: layout.function_count,
+            })
+        }
+    };
+
+    if modules.iter().any(|parsed| parsed.data_count.is_some()) {
+        sections.data_count = Some(DataCountSection {
+            count: layout.data_count,
+        });
+    }
+
+    let merged = sections.finish();
+    if let NoValidate::Disable = no_validation {
+        Validator::new()
+            .validate_all(&merged)
+            .map_err(Error::ValidationFailed)?;
+    }
+    Ok(merged)
+}
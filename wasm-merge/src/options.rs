@@ -1,3 +1,4 @@
+#[cfg(feature = "binaryen")]
 pub(crate) trait AsOption {
     fn as_option(&self) -> &str;
 }
@@ -11,6 +12,7 @@ macro_rules! option {
             Disable,
         }
 
+        #[cfg(feature = "binaryen")]
         impl AsOption for $name {
             fn as_option(&self) -> &str {
                 match self {
@@ -26,6 +28,8 @@ macro_rules! option {
 option!(NoValidate, "--no-validation");
 // Rename exports to avoid conflicts (rather than error)
 option!(RenameExportConflicts, "--rename-export-conflicts");
+// Preserve the name section & debug info
+option!(DebugInfo, "--debuginfo");
 
 // FEATURES
 // sign extension operations
@@ -80,9 +80,8 @@ fn test_merge() {
     assert_eq!(odd.call(&mut store, 12346).unwrap(), 0);
 }
 
-#[test]
-fn test_merge_fail() {
-    let merge_options = MergeOptions {
+fn invalid_modules() -> MergeOptions<'static> {
+    MergeOptions {
         primary: Some(InputModule {
             module: &[99, 88, 77, 66],
             namespace: String::from("foo"),
@@ -94,10 +93,103 @@ fn test_merge_fail() {
         multimemory: options::Multimemory::Enable,
         bulk_memory: options::BulkMemory::Enable,
         ..Default::default()
+    }
+}
+
+#[test]
+fn test_merge_fail() {
+    let merge_error = invalid_modules().merge().unwrap_err();
+    assert!(matches!(
+        merge_error,
+        error::Error::ParseFailed { namespace, .. } if namespace == "foo"
+    ));
+}
+
+#[cfg(feature = "binaryen")]
+#[test]
+fn test_merge_fail_binaryen() {
+    let merge_error = invalid_modules().merge_with_binaryen().unwrap_err();
+    assert!(merge_error.to_string().contains("Fatal"));
+}
+
+const WAT_PRIMARY: &str = r#"
+    (module
+        (import "secondary" "answer" (func $answer (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "\2a")
+        (func (export "main") (result i32)
+         call $answer
+         i32.const 0
+         i32.load8_u
+         i32.add))"#;
+
+const WAT_SECONDARY: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (data (i32.const 0) "\01")
+        (func (export "answer") (result i32)
+         i32.const 0
+         i32.load8_u))"#;
+
+#[test]
+fn test_merge_primary() {
+    let wat_primary = parse_str(WAT_PRIMARY).unwrap();
+    let wat_secondary = parse_str(WAT_SECONDARY).unwrap();
+    let merge_options = MergeOptions {
+        primary: Some(InputModule {
+            module: &wat_primary,
+            namespace: String::from("primary"),
+        }),
+        input_modules: vec![InputModule {
+            module: &wat_secondary,
+            namespace: String::from("secondary"),
+        }],
+        rename_export_conflicts: options::RenameExportConflicts::Enable,
+        multimemory: options::Multimemory::Enable,
+        ..Default::default()
+    };
+
+    let merged_wasm = merge_options.merge().unwrap();
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged_wasm).unwrap();
+    assert_eq!(module.imports().len(), 0);
+    let instance = Instance::new(&mut store, &module, &[]).unwrap();
+
+    // Each module reads its own memory
+    let main = instance
+        .get_typed_func::<(), i32>(&mut store, "main")
+        .unwrap();
+    assert_eq!(main.call(&mut store, ()).unwrap(), 43);
+
+    // The memory of the primary module keeps its export, the other one is renamed
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.data(&store)[0], 42);
+    let memory = instance.get_memory(&mut store, "memory_1").unwrap();
+    assert_eq!(memory.data(&store)[0], 1);
+}
+
+#[test]
+fn test_merge_export_conflict() {
+    let wat_primary = parse_str(WAT_PRIMARY).unwrap();
+    let wat_secondary = parse_str(WAT_SECONDARY).unwrap();
+    let merge_options = MergeOptions {
+        primary: Some(InputModule {
+            module: &wat_primary,
+            namespace: String::from("primary"),
+        }),
+        input_modules: vec![InputModule {
+            module: &wat_secondary,
+            namespace: String::from("secondary"),
+        }],
+        ..Default::default()
     };
 
     let merge_error = merge_options.merge().unwrap_err();
-    assert!(merge_error.to_string().contains("Fatal"));
+    assert!(matches!(
+        merge_error,
+        error::Error::ExportConflict(name) if name == "memory"
+    ));
 }
 
 #[test]
@@ -131,6 +131,28 @@ advice! { call_indirect post (
     }
 }
 
+advice! { import_call pre (
+        target_func: ImportedFunction,
+        args: MutDynArgs,
+        location: Location,
+    ) {
+        let _ = target_func;
+        let _ = args;
+        let _ = location;
+    }
+}
+
+advice! { import_call post (
+        target_func: ImportedFunction,
+        ress: MutDynResults,
+        location: Location,
+    ) {
+        let _ = target_func;
+        let _ = ress;
+        let _ = location;
+    }
+}
+
 advice! { unary(
         operator: UnaryOperator,
         operand: WasmValue,
@@ -166,6 +188,20 @@ advice! { return_ (
     }
 }
 
+advice! { function_entry (
+        location: Location,
+    ) {
+        let _ = location;
+    }
+}
+
+advice! { function_exit (
+        location: Location,
+    ) {
+        let _ = location;
+    }
+}
+
 advice! { const_(
         value: WasmValue,
         location: Location,
@@ -205,10 +241,12 @@ advice! { load(
         store_index: LoadIndex,
         offset: LoadOffset,
         operation: LoadOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         location: Location,
     ) {
         let _ = location;
-        operation.perform(&store_index, &offset)
+        operation.perform(&store_index, &offset, &memory, &alignment)
     }
 }
 
@@ -217,10 +255,12 @@ advice! { store(
         value: WasmValue,
         offset: StoreOffset,
         operation: StoreOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         location: Location,
     ) {
         let _ = location;
-        operation.perform(&store_index, &value, &offset);
+        operation.perform(&store_index, &value, &offset, &memory, &alignment);
     }
 }
 
@@ -245,6 +285,50 @@ advice! { memory_grow (
     }
 }
 
+advice! { memory_fill (
+        destination: WasmValue,
+        value: WasmValue,
+        size: WasmValue,
+        location: Location,
+    ) {
+        let _ = location;
+        (destination, value, size)
+    }
+}
+
+advice! { memory_copy (
+        destination: WasmValue,
+        source: WasmValue,
+        size: WasmValue,
+        location: Location,
+    ) {
+        let _ = location;
+        (destination, source, size)
+    }
+}
+
+advice! { memory_init (
+        destination: WasmValue,
+        source: WasmValue,
+        size: WasmValue,
+        data_index: DataIndex,
+        location: Location,
+    ) {
+        let _ = data_index;
+        let _ = location;
+        (destination, source, size)
+    }
+}
+
+advice! { data_drop (
+        data_index: DataIndex,
+        location: Location,
+    ) {
+        let _ = data_index;
+        let _ = location;
+    }
+}
+
 advice! { block pre (
         block_input_count: BlockInputCount,
         block_arity: BlockArity,
@@ -281,6 +365,24 @@ advice! { loop_ post (
     }
 }
 
+advice! { loop_ iteration (
+        iteration: LoopIterationCount,
+        location: Location,
+    ) {
+        let _ = iteration;
+        let _ = location;
+    }
+}
+
+advice! { basic_block enter (
+        block: BasicBlockId,
+        location: Location,
+    ) {
+        let _ = block;
+        let _ = location;
+    }
+}
+
 advice! { ref_func (
         func_index: WasmValue,
         location: Location,
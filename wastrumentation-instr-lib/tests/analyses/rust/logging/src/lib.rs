@@ -119,6 +119,24 @@ advice! { call_indirect post (
     }
 }
 
+advice! { import_call pre (
+        target_func: ImportedFunction,
+        args: MutDynArgs,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] import_call pre {target_func:#?}({args:#?}), location: {location:?}");
+    }
+}
+
+advice! { import_call post (
+        target_func: ImportedFunction,
+        ress: MutDynResults,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] import_call post {target_func:#?} = {ress:#?}, location: {location:?}");
+    }
+}
+
 advice! { unary (
         operator: UnaryOperator,
         operand: WasmValue,
@@ -154,6 +172,20 @@ advice! { return_ (
     }
 }
 
+advice! { function_entry (
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] Function entry called! location: {location:?}");
+    }
+}
+
+advice! { function_exit (
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] Function exit called! location: {location:?}");
+    }
+}
+
 advice! { const_ (
         value: WasmValue,
         location: Location,
@@ -189,9 +221,11 @@ advice! { load (
         store_index: LoadIndex,
         offset: LoadOffset,
         operation: LoadOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         location: Location,
     ) {
-        let value = operation.perform(&store_index, &offset);
+        let value = operation.perform(&store_index, &offset, &memory, &alignment);
         println!("[ANALYSIS:] load generic {operation:#?} @ (CONST {offset:#?} + {store_index:#?}) -> {value:#?}, location: {location:?}");
         value
     }
@@ -202,10 +236,12 @@ advice! { store (
         value: WasmValue,
         offset: StoreOffset,
         operation: StoreOperation,
+        memory: MemoryIndex,
+        alignment: MemoryAlignment,
         location: Location,
     ) {
         println!("[ANALYSIS:] store generic {operation:#?} @ (CONST {offset:#?} + {store_index:#?}) <- {value:#?}, location: {location:?}");
-        operation.perform(&store_index, &value, &offset);
+        operation.perform(&store_index, &value, &offset, &memory, &alignment);
     }
 }
 
@@ -229,6 +265,48 @@ advice! { memory_grow (
     }
 }
 
+advice! { memory_fill (
+        destination: WasmValue,
+        value: WasmValue,
+        size: WasmValue,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] memory_fill {size:#?} bytes with {value:#?} at {destination:#?}, location: {location:?}");
+        (destination, value, size)
+    }
+}
+
+advice! { memory_copy (
+        destination: WasmValue,
+        source: WasmValue,
+        size: WasmValue,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] memory_copy {size:#?} bytes from {source:#?} to {destination:#?}, location: {location:?}");
+        (destination, source, size)
+    }
+}
+
+advice! { memory_init (
+        destination: WasmValue,
+        source: WasmValue,
+        size: WasmValue,
+        data_index: DataIndex,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] memory_init {size:#?} bytes from segment {data_index:#?}[{source:#?}] to {destination:#?}, location: {location:?}");
+        (destination, source, size)
+    }
+}
+
+advice! { data_drop (
+        data_index: DataIndex,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] data_drop {data_index:#?}, location: {location:?}");
+    }
+}
+
 advice! { block pre (
         block_input_count: BlockInputCount,
         block_arity: BlockArity,
@@ -261,6 +339,22 @@ advice! { loop_ post (
     }
 }
 
+advice! { loop_ iteration (
+        iteration: LoopIterationCount,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] loop_ iteration {iteration:?}, location: {location:?}");
+    }
+}
+
+advice! { basic_block enter (
+        block: BasicBlockId,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] basic_block enter {block:?}, location: {location:?}");
+    }
+}
+
 advice! { ref_func (
         func_index: WasmValue,
         location: Location,
@@ -29,6 +29,8 @@ enum WasmValueEq {
     F32(OrderedFloat<f32>),
     I64(i64),
     F64(OrderedFloat<f64>),
+    FuncRef(i32),
+    ExternRef(i32),
 }
 
 impl From<&WasmValue> for WasmValueEq {
@@ -38,6 +40,8 @@ impl From<&WasmValue> for WasmValueEq {
             WasmValue::F32(v) => WasmValueEq::F32((*v).into()),
             WasmValue::I64(v) => WasmValueEq::I64(*v),
             WasmValue::F64(v) => WasmValueEq::F64((*v).into()),
+            WasmValue::FuncRef(v) => WasmValueEq::FuncRef(*v),
+            WasmValue::ExternRef(v) => WasmValueEq::ExternRef(*v),
         }
     }
 }
@@ -49,6 +53,8 @@ impl From<&WasmValueEq> for WasmValue {
             WasmValueEq::F32(OrderedFloat(v)) => WasmValue::F32(*v),
             WasmValueEq::I64(v) => WasmValue::I64(*v),
             WasmValueEq::F64(OrderedFloat(v)) => WasmValue::F64(*v),
+            WasmValueEq::FuncRef(v) => WasmValue::FuncRef(*v),
+            WasmValueEq::ExternRef(v) => WasmValue::ExternRef(*v),
         }
     }
 }
@@ -66,6 +72,8 @@ impl CallExpectation {
                 WasmValue::F32(_) => assert!(matches!(actual_arg, WasmType::F32)),
                 WasmValue::I64(_) => assert!(matches!(actual_arg, WasmType::I64)),
                 WasmValue::F64(_) => assert!(matches!(actual_arg, WasmType::F64)),
+                WasmValue::FuncRef(_) => assert!(matches!(actual_arg, WasmType::FuncRef)),
+                WasmValue::ExternRef(_) => assert!(matches!(actual_arg, WasmType::ExternRef)),
             }
             if *expected_arg != (args.get_arg(index.try_into().unwrap())) {
                 panic!()
@@ -79,6 +87,8 @@ impl CallExpectation {
                 WasmValue::F32(_) => assert!(matches!(actual_res, WasmType::F32)),
                 WasmValue::I64(_) => assert!(matches!(actual_res, WasmType::I64)),
                 WasmValue::F64(_) => assert!(matches!(actual_res, WasmType::F64)),
+                WasmValue::FuncRef(_) => assert!(matches!(actual_res, WasmType::FuncRef)),
+                WasmValue::ExternRef(_) => assert!(matches!(actual_res, WasmType::ExternRef)),
             }
             if *expected_res != (ress.get_res(index.try_into().unwrap())) {
                 panic!()
@@ -0,0 +1,25 @@
+use wastrumentation_rs_stdlib::*;
+
+/////
+// START ADVICE SPECIFICATION //
+//                         /////
+
+advice! { binary (
+        operator: BinaryOperator,
+        l_operand: WasmValue,
+        r_operand: WasmValue,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] binary {:?}, location: {location:?}", location.source());
+        operator.apply(l_operand, r_operand)
+    }
+}
+
+advice! { const_ (
+        value: WasmValue,
+        location: Location,
+    ) {
+        println!("[ANALYSIS:] const_ {:?}, location: {location:?}", location.source());
+        value
+    }
+}
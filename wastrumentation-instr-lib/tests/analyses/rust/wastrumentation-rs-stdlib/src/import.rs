@@ -0,0 +1,88 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::{addr_of, addr_of_mut};
+
+use crate::FunctionIndex;
+
+// Serialized name kinds, streamed in by the instrumented module
+const NAME_KIND_MODULE: i32 = 0;
+const NAME_KIND_FIELD: i32 = 1;
+
+struct ImportNames {
+    function_index: i32,
+    module: Vec<u8>,
+    field: Vec<u8>,
+}
+
+/// The string table of import names, filled in before the first import call is reported
+static mut IMPORT_NAMES: Vec<ImportNames> = Vec::new();
+
+#[no_mangle]
+extern "C" fn trap_import_name_byte(function_index: i32, kind: i32, byte: i32) {
+    let import_names = unsafe { &mut *addr_of_mut!(IMPORT_NAMES) };
+    let position = match import_names
+        .iter()
+        .position(|names| names.function_index == function_index)
+    {
+        Some(position) => position,
+        None => {
+            import_names.push(ImportNames {
+                function_index,
+                module: Vec::new(),
+                field: Vec::new(),
+            });
+            import_names.len() - 1
+        }
+    };
+    let names = &mut import_names[position];
+    match kind {
+        NAME_KIND_MODULE => names.module.push(byte as u8),
+        NAME_KIND_FIELD => names.field.push(byte as u8),
+        _ => panic!(),
+    }
+}
+
+/// An imported function, the target of an import call
+#[derive(PartialEq, Clone, Copy)]
+pub struct ImportedFunction(i32);
+
+impl ImportedFunction {
+    pub fn new(function_index: i32) -> Self {
+        Self(function_index)
+    }
+
+    pub fn function_index(&self) -> FunctionIndex {
+        let Self(function_index) = self;
+        FunctionIndex(*function_index)
+    }
+
+    /// The module name of the import, e.g. `wasi_snapshot_preview1`
+    pub fn module(&self) -> String {
+        self.name_of(|names| &names.module)
+    }
+
+    /// The field name of the import, e.g. `fd_write`
+    pub fn name(&self) -> String {
+        self.name_of(|names| &names.field)
+    }
+
+    fn name_of(&self, select: impl Fn(&ImportNames) -> &Vec<u8>) -> String {
+        let Self(function_index) = self;
+        let import_names = unsafe { &*addr_of!(IMPORT_NAMES) };
+        import_names
+            .iter()
+            .find(|names| names.function_index == *function_index)
+            .map(|names| String::from_utf8_lossy(select(names)).into_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl core::fmt::Debug for ImportedFunction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ImportedFunction")
+            .field("function_index", &self.function_index().value())
+            .field("module", &self.module())
+            .field("name", &self.name())
+            .finish()
+    }
+}
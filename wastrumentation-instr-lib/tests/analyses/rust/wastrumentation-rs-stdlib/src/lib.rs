@@ -12,10 +12,16 @@ pub use unary::UnaryOperator;
 pub mod binary;
 pub use binary::BinaryOperator;
 
+mod import;
+pub use import::ImportedFunction;
+
+mod source;
+pub use source::SourceLocation;
+
 mod memory;
 pub use memory::{
-    base_memory_size, Deserialize, LoadIndex, LoadOffset, LoadOperation, MemoryIndex, StoreIndex,
-    StoreOffset, StoreOperation,
+    base_memory_size, Deserialize, LoadIndex, LoadOffset, LoadOperation, MemoryAlignment,
+    MemoryIndex, StoreIndex, StoreOffset, StoreOperation,
 };
 
 extern crate alloc;
@@ -35,30 +41,60 @@ extern "C" {
     // Base apply
     fn call_base(f_apply: i32, sigv: i32);
     // Base load
-    fn instrumented_base_load_i32(ptr: i32, offset: i32) -> i32;
-    fn instrumented_base_load_i64(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_f32(ptr: i32, offset: i32) -> f32;
-    fn instrumented_base_load_f64(ptr: i32, offset: i32) -> f64;
-    fn instrumented_base_load_i32_8S(ptr: i32, offset: i32) -> i32;
-    fn instrumented_base_load_i32_8U(ptr: i32, offset: i32) -> i32;
-    fn instrumented_base_load_i32_16S(ptr: i32, offset: i32) -> i32;
-    fn instrumented_base_load_i32_16U(ptr: i32, offset: i32) -> i32;
-    fn instrumented_base_load_i64_8S(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_i64_8U(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_i64_16S(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_i64_16U(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_i64_32S(ptr: i32, offset: i32) -> i64;
-    fn instrumented_base_load_i64_32U(ptr: i32, offset: i32) -> i64;
+    fn instrumented_base_load_i32(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i32;
+    fn instrumented_base_load_i64(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_f32(ptr: i32, offset: i32, memory: i32, alignment: i32) -> f32;
+    fn instrumented_base_load_f64(ptr: i32, offset: i32, memory: i32, alignment: i32) -> f64;
+    fn instrumented_base_load_i32_8S(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i32;
+    fn instrumented_base_load_i32_8U(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i32;
+    fn instrumented_base_load_i32_16S(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i32;
+    fn instrumented_base_load_i32_16U(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i32;
+    fn instrumented_base_load_i64_8S(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_i64_8U(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_i64_16S(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_i64_16U(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_i64_32S(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
+    fn instrumented_base_load_i64_32U(ptr: i32, offset: i32, memory: i32, alignment: i32) -> i64;
     // Base store
-    fn instrumented_base_store_i32(ptr: i32, value: i32, offset: i32);
-    fn instrumented_base_store_i64(ptr: i32, value: i64, offset: i32);
-    fn instrumented_base_store_f32(ptr: i32, value: f32, offset: i32);
-    fn instrumented_base_store_f64(ptr: i32, value: f64, offset: i32);
-    fn instrumented_base_store_i32_8(ptr: i32, value: i32, offset: i32);
-    fn instrumented_base_store_i32_16(ptr: i32, value: i32, offset: i32);
-    fn instrumented_base_store_i64_8(ptr: i32, value: i64, offset: i32);
-    fn instrumented_base_store_i64_16(ptr: i32, value: i64, offset: i32);
-    fn instrumented_base_store_i64_32(ptr: i32, value: i64, offset: i32);
+    fn instrumented_base_store_i32(ptr: i32, value: i32, offset: i32, memory: i32, alignment: i32);
+    fn instrumented_base_store_i64(ptr: i32, value: i64, offset: i32, memory: i32, alignment: i32);
+    fn instrumented_base_store_f32(ptr: i32, value: f32, offset: i32, memory: i32, alignment: i32);
+    fn instrumented_base_store_f64(ptr: i32, value: f64, offset: i32, memory: i32, alignment: i32);
+    fn instrumented_base_store_i32_8(
+        ptr: i32,
+        value: i32,
+        offset: i32,
+        memory: i32,
+        alignment: i32,
+    );
+    fn instrumented_base_store_i32_16(
+        ptr: i32,
+        value: i32,
+        offset: i32,
+        memory: i32,
+        alignment: i32,
+    );
+    fn instrumented_base_store_i64_8(
+        ptr: i32,
+        value: i64,
+        offset: i32,
+        memory: i32,
+        alignment: i32,
+    );
+    fn instrumented_base_store_i64_16(
+        ptr: i32,
+        value: i64,
+        offset: i32,
+        memory: i32,
+        alignment: i32,
+    );
+    fn instrumented_base_store_i64_32(
+        ptr: i32,
+        value: i64,
+        offset: i32,
+        memory: i32,
+        alignment: i32,
+    );
     // Base memory grow
     fn instrumented_memory_grow(amount: i32, idx: i32) -> i32;
     fn instrumented_memory_size(idx: i32) -> i32;
@@ -100,7 +136,7 @@ pub struct Location {
 }
 
 impl Location {
-    pub fn new(instr_index: i64, funct_index: i64) -> Self {
+    pub fn new(funct_index: i64, instr_index: i64) -> Self {
         Self {
             instr_index,
             funct_index,
@@ -113,12 +149,20 @@ impl Location {
     pub fn function_index(&self) -> i64 {
         self.funct_index
     }
+
+    /// The source location of the site, when instrumented with source locations
+    /// from an input program with DWARF
+    pub fn source(&self) -> Option<SourceLocation> {
+        SourceLocation::of(self.funct_index, self.instr_index)
+    }
 }
 
 const TYPE_I32: i32 = 0;
 const TYPE_F32: i32 = 1;
 const TYPE_I64: i32 = 2;
 const TYPE_F64: i32 = 3;
+const TYPE_FUNC_REF: i32 = 4;
+const TYPE_EXTERN_REF: i32 = 5;
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum WasmType {
@@ -126,6 +170,8 @@ pub enum WasmType {
     F32,
     I64,
     F64,
+    FuncRef,
+    ExternRef,
 }
 
 // TODO: can I wrap this i32 into MaterializedWasmType to ensure type safety?
@@ -136,6 +182,8 @@ impl From<&i32> for WasmType {
             TYPE_F32 => Self::F32,
             TYPE_I64 => Self::I64,
             TYPE_F64 => Self::F64,
+            TYPE_FUNC_REF => Self::FuncRef,
+            TYPE_EXTERN_REF => Self::ExternRef,
             _ => panic!(),
         }
     }
@@ -148,6 +196,7 @@ impl WasmType {
             WasmType::F32 => size_of::<f32>(),
             WasmType::I64 => size_of::<i64>(),
             WasmType::F64 => size_of::<f64>(),
+            WasmType::FuncRef | WasmType::ExternRef => size_of::<i32>(),
         }
     }
 
@@ -174,6 +223,14 @@ impl WasmType {
                 let res = wastrumentation_stack_load_f64(ptr, offset);
                 WasmValue::F64(res)
             },
+            WasmType::FuncRef => unsafe {
+                let res = wastrumentation_stack_load_i32(ptr, offset);
+                WasmValue::FuncRef(res)
+            },
+            WasmType::ExternRef => unsafe {
+                let res = wastrumentation_stack_load_i32(ptr, offset);
+                WasmValue::ExternRef(res)
+            },
         }
     }
 }
@@ -184,6 +241,10 @@ pub enum WasmValue {
     F32(f32),
     I64(i64),
     F64(f64),
+    /// Handle to a reference kept by the instrumented module, valid during the call
+    FuncRef(i32),
+    /// Handle to a reference kept by the instrumented module, valid during the call
+    ExternRef(i32),
 }
 
 impl PartialEq for WasmValue {
@@ -193,6 +254,8 @@ impl PartialEq for WasmValue {
             (Self::F32(l), Self::F32(r)) => l.to_be_bytes() == r.to_be_bytes(),
             (Self::I64(l), Self::I64(r)) => l == r,
             (Self::F64(l), Self::F64(r)) => l.to_be_bytes() == r.to_be_bytes(),
+            (Self::FuncRef(l), Self::FuncRef(r)) => l == r,
+            (Self::ExternRef(l), Self::ExternRef(r)) => l == r,
             _ => false,
         }
     }
@@ -238,6 +301,9 @@ impl WasmValue {
             WasmValue::F64(value) => unsafe {
                 wastrumentation_stack_store_f64(ptr, *value, offset)
             },
+            WasmValue::FuncRef(handle) | WasmValue::ExternRef(handle) => unsafe {
+                wastrumentation_stack_store_i32(ptr, *handle, offset)
+            },
         }
     }
 
@@ -269,12 +335,28 @@ impl WasmValue {
         }
     }
 
+    pub fn as_func_ref(&self) -> i32 {
+        match self {
+            Self::FuncRef(handle) => *handle,
+            _ => panic!("Attempt to convert {self:?} to funcref"),
+        }
+    }
+
+    pub fn as_extern_ref(&self) -> i32 {
+        match self {
+            Self::ExternRef(handle) => *handle,
+            _ => panic!("Attempt to convert {self:?} to externref"),
+        }
+    }
+
     pub fn type_(&self) -> WasmType {
         match self {
             WasmValue::I32(_) => WasmType::I32,
             WasmValue::F32(_) => WasmType::F32,
             WasmValue::I64(_) => WasmType::I64,
             WasmValue::F64(_) => WasmType::F64,
+            WasmValue::FuncRef(_) => WasmType::FuncRef,
+            WasmValue::ExternRef(_) => WasmType::ExternRef,
         }
     }
 
@@ -293,6 +375,10 @@ impl WasmValue {
             WasmType::I64 => Self::I64(0),
             WasmType::F32 => Self::F32(0.0),
             WasmType::F64 => Self::F64(0.0),
+            // The default is a null reference, which has no handle
+            WasmType::FuncRef | WasmType::ExternRef => {
+                panic!("Attempt to create a default value for {type_:?}")
+            }
         }
     }
 
@@ -303,6 +389,7 @@ impl WasmValue {
             WasmValue::F32(v) => v.to_le_bytes().to_vec(),
             WasmValue::I64(v) => v.to_le_bytes().to_vec(),
             WasmValue::F64(v) => v.to_le_bytes().to_vec(),
+            WasmValue::FuncRef(v) | WasmValue::ExternRef(v) => v.to_le_bytes().to_vec(),
         }
         .into_iter()
         .map(|v| format!("{v}"))
@@ -576,9 +663,12 @@ generate_wrapper!(BlockArity             wrapping i32 accessed-using .value());
 generate_wrapper!(BlockInputCount        wrapping i32 accessed-using .value());
 generate_wrapper!(LoopArity              wrapping i32 accessed-using .value());
 generate_wrapper!(LoopInputCount         wrapping i32 accessed-using .value());
+generate_wrapper!(LoopIterationCount     wrapping i64 accessed-using .value());
+generate_wrapper!(BasicBlockId           wrapping i32 accessed-using .value());
 generate_wrapper!(GlobalIndex            wrapping i64 accessed-using .value());
 generate_wrapper!(TableIndex             wrapping i32 accessed-using .value());
 generate_wrapper!(ElementIndex           wrapping i32 accessed-using .value());
+generate_wrapper!(DataIndex              wrapping i32 accessed-using .value());
 
 impl SerializedContinuation for PathContinuation {
     fn low_level_continuation(&self) -> &i32 {
@@ -652,6 +742,54 @@ macro_rules! advice {
             $body
         }
     };
+    (import_call pre
+        (
+            $func_ident: ident: ImportedFunction,
+            $args_ident: ident: MutDynArgs,
+            $location_ident: ident: Location $(,)?
+        ) $body:block
+    ) => {
+        #[no_mangle]
+        pub extern "C"
+        fn trap_import_call_pre (
+            f_tgt: i32,
+            argc: i32,
+            resc: i32,
+            sigv: i32,
+            sigtypv: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $func_ident = ImportedFunction::new(f_tgt);
+            let mut $args_ident = MutDynArgs::new(argc, resc, sigv, sigtypv);
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
+    (import_call post
+        (
+            $func_ident: ident: ImportedFunction,
+            $ress_ident: ident: MutDynResults,
+            $location_ident: ident: Location $(,)?
+        ) $body:block
+    ) => {
+        #[no_mangle]
+        pub extern "C"
+        fn trap_import_call_post (
+            f_tgt: i32,
+            argc: i32,
+            resc: i32,
+            sigv: i32,
+            sigtypv: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $func_ident = ImportedFunction::new(f_tgt);
+            let mut $ress_ident = MutDynResults::new(argc, resc, sigv, sigtypv);
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
     (call_indirect pre
         (
             $func_table_index_ident: ident: FunctionTableIndex,
@@ -1021,6 +1159,30 @@ macro_rules! advice {
             $body
         }
     };
+    (function_entry (
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        #[no_mangle]
+        extern "C" fn trap_function_entry(
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
+    (function_exit (
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        #[no_mangle]
+        extern "C" fn trap_function_exit(
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
     (const_
         (
             $value: ident: WasmValue,
@@ -1182,17 +1344,23 @@ macro_rules! advice {
         $load_index: ident: LoadIndex,
         $offset: ident: LoadOffset,
         $operation: ident: LoadOperation,
+        $memory: ident: MemoryIndex,
+        $alignment: ident: MemoryAlignment,
         $location_ident: ident: Location $(,)?
     ) $body:block) => {
         fn generic_load_trap(
             load_index: LoadIndex,
             offset: LoadOffset,
             operation: LoadOperation,
+            memory: MemoryIndex,
+            alignment: MemoryAlignment,
             location: Location,
         ) -> WasmValue {
             let $load_index = load_index;
             let $offset = offset;
             let $operation = operation;
+            let $memory = memory;
+            let $alignment = alignment;
             let $location_ident = location;
             $body
         }
@@ -1214,14 +1382,18 @@ macro_rules! advice {
             load_idx: i32,
             offset: i64,
             operation: i32,
+            memory: i64,
+            alignment: i32,
             funct_index: i64,
             instr_index: i64,
         ) -> $load_type {
             let load_index = LoadIndex(load_idx);
             let offset = LoadOffset(offset);
             let operation = LoadOperation::deserialize(&operation);
+            let memory = MemoryIndex(memory);
+            let alignment = MemoryAlignment(alignment);
             let location = Location::new(funct_index, instr_index);
-            let outcome = $generic_load_trap(load_index, offset, operation, location);
+            let outcome = $generic_load_trap(load_index, offset, operation, memory, alignment, location);
             let WasmValue::$load_type_wasm_value(outcome) = outcome else {
                 panic!(concat!("Attempted to convert {:?} to ", stringify!($value_type_wasm_value)), outcome);
             };
@@ -1234,6 +1406,8 @@ macro_rules! advice {
         $value: ident: WasmValue,
         $offset: ident: StoreOffset,
         $operation: ident: StoreOperation,
+        $memory: ident: MemoryIndex,
+        $alignment: ident: MemoryAlignment,
         $location_ident: ident: Location $(,)?
     ) $body:block) => {
         fn generic_store_trap(
@@ -1241,12 +1415,16 @@ macro_rules! advice {
             value: WasmValue,
             offset: StoreOffset,
             operation: StoreOperation,
+            memory: MemoryIndex,
+            alignment: MemoryAlignment,
             location: Location,
         ) {
             let $store_index = store_index;
             let $value = value;
             let $offset = offset;
             let $operation = operation;
+            let $memory = memory;
+            let $alignment = alignment;
             let $location_ident = location;
             $body
         }
@@ -1270,6 +1448,8 @@ macro_rules! advice {
             value: $store_type,
             offset: i64,
             operation: i32,
+            memory: i64,
+            alignment: i32,
             funct_index: i64,
             instr_index: i64,
         ) {
@@ -1277,8 +1457,10 @@ macro_rules! advice {
             let value = WasmValue::$store_type_wasm_value(value);
             let offset = StoreOffset(offset);
             let operation = StoreOperation::deserialize(&operation);
+            let memory = MemoryIndex(memory);
+            let alignment = MemoryAlignment(alignment);
             let location = Location::new(funct_index, instr_index);
-            $generic_store_trap(store_index, value, offset, operation, location);
+            $generic_store_trap(store_index, value, offset, operation, memory, alignment, location);
         }
     };
     (memory_size
@@ -1325,6 +1507,159 @@ macro_rules! advice {
             delta_or_neg_1.as_i32()
         }
     };
+    (memory_fill (
+        $destination: ident: WasmValue,
+        $value: ident: WasmValue,
+        $size: ident: WasmValue,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        static mut RETURN_MEMORY_FILL_DST: i32 = 0;
+        static mut RETURN_MEMORY_FILL_VAL: i32 = 0;
+        static mut RETURN_MEMORY_FILL_SIZE: i32 = 0;
+
+        #[no_mangle]
+        extern "C" fn trap_memory_fill(
+            destination: i32,
+            value: i32,
+            size: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $destination = WasmValue::I32(destination);
+            let $value = WasmValue::I32(value);
+            let $size = WasmValue::I32(size);
+            let $location_ident = Location::new(funct_index, instr_index);
+            let (new_destination, new_value, new_size): (WasmValue, WasmValue, WasmValue) = $body;
+            unsafe {
+                RETURN_MEMORY_FILL_DST = new_destination.as_i32();
+                RETURN_MEMORY_FILL_VAL = new_value.as_i32();
+                RETURN_MEMORY_FILL_SIZE = new_size.as_i32();
+            }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_fill_get_dst() -> i32 {
+            unsafe { RETURN_MEMORY_FILL_DST }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_fill_get_val() -> i32 {
+            unsafe { RETURN_MEMORY_FILL_VAL }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_fill_get_size() -> i32 {
+            unsafe { RETURN_MEMORY_FILL_SIZE }
+        }
+    };
+    (memory_copy (
+        $destination: ident: WasmValue,
+        $source: ident: WasmValue,
+        $size: ident: WasmValue,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        static mut RETURN_MEMORY_COPY_DST: i32 = 0;
+        static mut RETURN_MEMORY_COPY_SRC: i32 = 0;
+        static mut RETURN_MEMORY_COPY_SIZE: i32 = 0;
+
+        #[no_mangle]
+        extern "C" fn trap_memory_copy(
+            destination: i32,
+            source: i32,
+            size: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $destination = WasmValue::I32(destination);
+            let $source = WasmValue::I32(source);
+            let $size = WasmValue::I32(size);
+            let $location_ident = Location::new(funct_index, instr_index);
+            let (new_destination, new_source, new_size): (WasmValue, WasmValue, WasmValue) = $body;
+            unsafe {
+                RETURN_MEMORY_COPY_DST = new_destination.as_i32();
+                RETURN_MEMORY_COPY_SRC = new_source.as_i32();
+                RETURN_MEMORY_COPY_SIZE = new_size.as_i32();
+            }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_copy_get_dst() -> i32 {
+            unsafe { RETURN_MEMORY_COPY_DST }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_copy_get_src() -> i32 {
+            unsafe { RETURN_MEMORY_COPY_SRC }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_copy_get_size() -> i32 {
+            unsafe { RETURN_MEMORY_COPY_SIZE }
+        }
+    };
+    (memory_init (
+        $destination: ident: WasmValue,
+        $source: ident: WasmValue,
+        $size: ident: WasmValue,
+        $data_index: ident: DataIndex,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        static mut RETURN_MEMORY_INIT_DST: i32 = 0;
+        static mut RETURN_MEMORY_INIT_SRC: i32 = 0;
+        static mut RETURN_MEMORY_INIT_SIZE: i32 = 0;
+
+        #[no_mangle]
+        extern "C" fn trap_memory_init(
+            destination: i32,
+            source: i32,
+            size: i32,
+            data_index: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $destination = WasmValue::I32(destination);
+            let $source = WasmValue::I32(source);
+            let $size = WasmValue::I32(size);
+            let $data_index = DataIndex(data_index);
+            let $location_ident = Location::new(funct_index, instr_index);
+            let (new_destination, new_source, new_size): (WasmValue, WasmValue, WasmValue) = $body;
+            unsafe {
+                RETURN_MEMORY_INIT_DST = new_destination.as_i32();
+                RETURN_MEMORY_INIT_SRC = new_source.as_i32();
+                RETURN_MEMORY_INIT_SIZE = new_size.as_i32();
+            }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_init_get_dst() -> i32 {
+            unsafe { RETURN_MEMORY_INIT_DST }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_init_get_src() -> i32 {
+            unsafe { RETURN_MEMORY_INIT_SRC }
+        }
+
+        #[no_mangle]
+        extern "C" fn trap_memory_init_get_size() -> i32 {
+            unsafe { RETURN_MEMORY_INIT_SIZE }
+        }
+    };
+    (data_drop (
+        $data_index: ident: DataIndex,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        #[no_mangle]
+        extern "C" fn trap_data_drop(
+            data_index: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $data_index = DataIndex(data_index);
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body;
+        }
+    };
     (block pre (
         $block_input_c: ident: BlockInputCount,
         $block_arity: ident: BlockArity,
@@ -1385,6 +1720,36 @@ macro_rules! advice {
             $body
         }
     };
+    (loop_ iteration (
+        $iteration: ident: LoopIterationCount,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        #[no_mangle]
+        extern "C" fn trap_loop_iteration(
+            iteration: i64,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $iteration = LoopIterationCount(iteration);
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
+    (basic_block enter (
+        $block: ident: BasicBlockId,
+        $location_ident: ident: Location $(,)?
+    ) $body:block) => {
+        #[no_mangle]
+        extern "C" fn trap_basic_block_enter(
+            block: i32,
+            funct_index: i64,
+            instr_index: i64,
+        ) {
+            let $block = BasicBlockId(block);
+            let $location_ident = Location::new(funct_index, instr_index);
+            $body
+        }
+    };
     /////////////////////
     // REFERENCE TYPES //
     /////////////////////
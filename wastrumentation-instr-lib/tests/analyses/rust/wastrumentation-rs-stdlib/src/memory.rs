@@ -34,6 +34,14 @@ generate_wrapper!(StoreIndex  wrapping i32 accessed-using .value());
 generate_wrapper!(StoreOffset wrapping i64 accessed-using .value());
 generate_wrapper!(LoadIndex   wrapping i32 accessed-using .value());
 generate_wrapper!(MemoryIndex wrapping i64 accessed-using .value());
+generate_wrapper!(MemoryAlignment wrapping i32 accessed-using .exponent());
+
+impl MemoryAlignment {
+    /// The alignment hint in bytes, i.e. `2^exponent`
+    pub fn bytes(&self) -> usize {
+        1 << self.exponent()
+    }
+}
 
 pub fn base_memory_size(index: i32) -> i32 {
     unsafe { crate::instrumented_memory_size(index) }
@@ -100,7 +108,14 @@ impl Deserialize for StoreOperation {
 }
 
 impl StoreOperation {
-    pub fn perform(&self, store_index: &StoreIndex, value: &WasmValue, offset: &StoreOffset) {
+    pub fn perform(
+        &self,
+        store_index: &StoreIndex,
+        value: &WasmValue,
+        offset: &StoreOffset,
+        memory: &MemoryIndex,
+        alignment: &MemoryAlignment,
+    ) {
         // Regular
         use StoreOperation::{F32Store, F64Store, I32Store, I64Store};
         // I32 Load
@@ -110,20 +125,40 @@ impl StoreOperation {
 
         let ptr = store_index.value();
         let offset = offset.value().try_into().unwrap();
+        let memory = memory.value().try_into().unwrap();
+        let alignment = alignment.exponent();
 
         match self {
             // Regular
-            F32Store => unsafe { instrumented_base_store_f32(ptr, value.as_f32(), offset) },
-            F64Store => unsafe { instrumented_base_store_f64(ptr, value.as_f64(), offset) },
-            I32Store => unsafe { instrumented_base_store_i32(ptr, value.as_i32(), offset) },
-            I64Store => unsafe { instrumented_base_store_i64(ptr, value.as_i64(), offset) },
+            F32Store => unsafe {
+                instrumented_base_store_f32(ptr, value.as_f32(), offset, memory, alignment)
+            },
+            F64Store => unsafe {
+                instrumented_base_store_f64(ptr, value.as_f64(), offset, memory, alignment)
+            },
+            I32Store => unsafe {
+                instrumented_base_store_i32(ptr, value.as_i32(), offset, memory, alignment)
+            },
+            I64Store => unsafe {
+                instrumented_base_store_i64(ptr, value.as_i64(), offset, memory, alignment)
+            },
             // I32 Load
-            I32Store16 => unsafe { instrumented_base_store_i32_16(ptr, value.as_i32(), offset) },
-            I32Store8 => unsafe { instrumented_base_store_i32_8(ptr, value.as_i32(), offset) },
+            I32Store16 => unsafe {
+                instrumented_base_store_i32_16(ptr, value.as_i32(), offset, memory, alignment)
+            },
+            I32Store8 => unsafe {
+                instrumented_base_store_i32_8(ptr, value.as_i32(), offset, memory, alignment)
+            },
             // I64 Load
-            I64Store16 => unsafe { instrumented_base_store_i64_16(ptr, value.as_i64(), offset) },
-            I64Store32 => unsafe { instrumented_base_store_i64_32(ptr, value.as_i64(), offset) },
-            I64Store8 => unsafe { instrumented_base_store_i64_8(ptr, value.as_i64(), offset) },
+            I64Store16 => unsafe {
+                instrumented_base_store_i64_16(ptr, value.as_i64(), offset, memory, alignment)
+            },
+            I64Store32 => unsafe {
+                instrumented_base_store_i64_32(ptr, value.as_i64(), offset, memory, alignment)
+            },
+            I64Store8 => unsafe {
+                instrumented_base_store_i64_8(ptr, value.as_i64(), offset, memory, alignment)
+            },
         }
     }
 
@@ -153,7 +188,13 @@ impl StoreOperation {
 }
 
 impl LoadOperation {
-    pub fn perform(&self, load_index: &LoadIndex, offset: &LoadOffset) -> WasmValue {
+    pub fn perform(
+        &self,
+        load_index: &LoadIndex,
+        offset: &LoadOffset,
+        memory: &MemoryIndex,
+        alignment: &MemoryAlignment,
+    ) -> WasmValue {
         // Regular
         use LoadOperation::{F32Load, F64Load, I32Load, I64Load};
         // I32 Load
@@ -163,25 +204,47 @@ impl LoadOperation {
 
         let ptr = load_index.value();
         let offset = offset.value().try_into().unwrap();
+        let memory = memory.value().try_into().unwrap();
+        let alignment = alignment.exponent();
 
         match self {
             // Regular
-            F32Load => unsafe { instrumented_base_load_f32(ptr, offset).into() },
-            F64Load => unsafe { instrumented_base_load_f64(ptr, offset).into() },
-            I32Load => unsafe { instrumented_base_load_i32(ptr, offset).into() },
-            I64Load => unsafe { instrumented_base_load_i64(ptr, offset).into() },
+            F32Load => unsafe { instrumented_base_load_f32(ptr, offset, memory, alignment).into() },
+            F64Load => unsafe { instrumented_base_load_f64(ptr, offset, memory, alignment).into() },
+            I32Load => unsafe { instrumented_base_load_i32(ptr, offset, memory, alignment).into() },
+            I64Load => unsafe { instrumented_base_load_i64(ptr, offset, memory, alignment).into() },
             // I32 Load
-            I32Load16S => unsafe { instrumented_base_load_i32_16S(ptr, offset).into() },
-            I32Load16U => unsafe { instrumented_base_load_i32_16U(ptr, offset).into() },
-            I32Load8S => unsafe { instrumented_base_load_i32_8S(ptr, offset).into() },
-            I32Load8U => unsafe { instrumented_base_load_i32_8U(ptr, offset).into() },
+            I32Load16S => unsafe {
+                instrumented_base_load_i32_16S(ptr, offset, memory, alignment).into()
+            },
+            I32Load16U => unsafe {
+                instrumented_base_load_i32_16U(ptr, offset, memory, alignment).into()
+            },
+            I32Load8S => unsafe {
+                instrumented_base_load_i32_8S(ptr, offset, memory, alignment).into()
+            },
+            I32Load8U => unsafe {
+                instrumented_base_load_i32_8U(ptr, offset, memory, alignment).into()
+            },
             // I64 Load
-            I64Load16S => unsafe { instrumented_base_load_i64_16S(ptr, offset).into() },
-            I64Load16U => unsafe { instrumented_base_load_i64_16U(ptr, offset).into() },
-            I64Load32S => unsafe { instrumented_base_load_i64_32S(ptr, offset).into() },
-            I64Load32U => unsafe { instrumented_base_load_i64_32U(ptr, offset).into() },
-            I64Load8S => unsafe { instrumented_base_load_i64_8S(ptr, offset).into() },
-            I64Load8U => unsafe { instrumented_base_load_i64_8U(ptr, offset).into() },
+            I64Load16S => unsafe {
+                instrumented_base_load_i64_16S(ptr, offset, memory, alignment).into()
+            },
+            I64Load16U => unsafe {
+                instrumented_base_load_i64_16U(ptr, offset, memory, alignment).into()
+            },
+            I64Load32S => unsafe {
+                instrumented_base_load_i64_32S(ptr, offset, memory, alignment).into()
+            },
+            I64Load32U => unsafe {
+                instrumented_base_load_i64_32U(ptr, offset, memory, alignment).into()
+            },
+            I64Load8S => unsafe {
+                instrumented_base_load_i64_8S(ptr, offset, memory, alignment).into()
+            },
+            I64Load8U => unsafe {
+                instrumented_base_load_i64_8U(ptr, offset, memory, alignment).into()
+            },
         }
     }
 
@@ -0,0 +1,67 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::{addr_of, addr_of_mut};
+
+/// The paths of the source files, by file index
+static mut SOURCE_FILES: Vec<Vec<u8>> = Vec::new();
+
+/// The file index, line & column of every instrumented site, by function & instruction index
+static mut SOURCE_LOCATIONS: BTreeMap<(i64, i64), (i32, i32, i32)> = BTreeMap::new();
+
+#[no_mangle]
+extern "C" fn trap_source_file_byte(file: i32, byte: i32) {
+    let source_files = unsafe { &mut *addr_of_mut!(SOURCE_FILES) };
+    let file = file as usize;
+    if source_files.len() <= file {
+        source_files.resize(file + 1, Vec::new());
+    }
+    source_files[file].push(byte as u8);
+}
+
+#[no_mangle]
+extern "C" fn trap_source_location(
+    file: i32,
+    line: i32,
+    column: i32,
+    funct_index: i64,
+    instr_index: i64,
+) {
+    let source_locations = unsafe { &mut *addr_of_mut!(SOURCE_LOCATIONS) };
+    source_locations.insert((funct_index, instr_index), (file, line, column));
+}
+
+/// Where in the source an instrumented site originates from, read from the DWARF of the input program
+#[derive(PartialEq, Debug, Clone)]
+pub struct SourceLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    pub(crate) fn of(funct_index: i64, instr_index: i64) -> Option<Self> {
+        let source_locations = unsafe { &*addr_of!(SOURCE_LOCATIONS) };
+        let source_files = unsafe { &*addr_of!(SOURCE_FILES) };
+        let (file, line, column) = source_locations.get(&(funct_index, instr_index))?;
+        Some(Self {
+            file: String::from_utf8_lossy(source_files.get(*file as usize)?).into_owned(),
+            line: *line as u32,
+            column: *column as u32,
+        })
+    }
+
+    /// The path of the source file, e.g. `/src/main.c`
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Zero when the site spans a whole line
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
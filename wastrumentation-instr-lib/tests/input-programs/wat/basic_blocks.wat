@@ -0,0 +1,26 @@
+(module
+  ;; one block: [0, 4)
+  (func $double (param i32) (result i32)
+    local.get 0
+    i32.const 2
+    i32.mul)
+
+  ;; four blocks: [0, 1) [1, 4) [4, 14) [14, 16)
+  (func $main (export "main") (result i32)
+    (local $i i32)
+    (local $sum i32)
+    (loop $accumulate
+      local.get $sum
+      local.get $i
+      call $double
+      i32.add
+      local.set $sum
+      local.get $i
+      i32.const 1
+      i32.add
+      local.tee $i
+      i32.const 4
+      i32.lt_u
+      br_if $accumulate)
+    ;; 2 * (0 + 1 + 2 + 3)
+    local.get $sum))
@@ -0,0 +1,38 @@
+(module
+  (memory $memory_0 1)
+  (data $data_0 "hello")
+
+  (func (export "main") (result i32)
+
+    ;; TEST: memory.init
+    ;; copy "hello" from the passive data segment to address 0
+    i32.const 0  ;; destination
+    i32.const 0  ;; source offset in data segment
+    i32.const 5  ;; size
+    memory.init $data_0
+
+    ;; TEST: data.drop
+    data.drop $data_0
+
+    ;; TEST: memory.copy
+    ;; copy "hello" from address 0 to address 16
+    i32.const 16 ;; destination
+    i32.const 0  ;; source
+    i32.const 5  ;; size
+    memory.copy
+
+    ;; TEST: memory.fill
+    ;; fill 4 bytes at address 32 with value 7
+    i32.const 32 ;; destination
+    i32.const 7  ;; value
+    i32.const 4  ;; size
+    memory.fill
+
+    ;; 'h' (104) + 7 = 111
+    i32.const 16
+    i32.load8_u
+    i32.const 35
+    i32.load8_u
+    i32.add
+  )
+)
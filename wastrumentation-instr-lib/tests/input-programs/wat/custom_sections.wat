@@ -0,0 +1,13 @@
+(module
+  (func $crash (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.add
+    drop
+    unreachable)
+
+  (func $main (export "main") (result i32)
+    i32.const 41
+    call $crash)
+
+  (@custom "wastrumentation" "kept"))
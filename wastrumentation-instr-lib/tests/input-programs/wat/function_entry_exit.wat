@@ -0,0 +1,60 @@
+(module
+  ;; Leaves through fall-through
+  (func $fall_through (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.add)
+
+  ;; Leaves through `return`, nested in a block
+  (func $early_return (param i32) (result i32)
+    (block
+      local.get 0
+      i32.eqz
+      br_if 0
+      local.get 0
+      i32.const 10
+      i32.mul
+      return)
+    i32.const 7)
+
+  ;; Leaves through `br` to the function label
+  (func $branch_out (param i32) (result i32)
+    (loop
+      local.get 0
+      i32.const 2
+      i32.add
+      br 1)
+    unreachable)
+
+  ;; Leaves through `br_if` and `br_table` to the function label
+  (func $branch_table_out (param i32) (result i32)
+    i32.const 5
+    local.get 0
+    br_if 0
+    drop
+    i32.const 3
+    local.get 0
+    br_table 0 0)
+
+  ;; Empty body
+  (func $empty)
+
+  (func $main (export "main") (result i32)
+    call $empty
+    i32.const 1
+    call $fall_through      ;; 2
+    i32.const 4
+    call $early_return      ;; 40
+    i32.add
+    i32.const 0
+    call $early_return      ;; 7
+    i32.add
+    i32.const 1
+    call $branch_out        ;; 3
+    i32.add
+    i32.const 1
+    call $branch_table_out  ;; 5
+    i32.add
+    i32.const 0
+    call $branch_table_out  ;; 3
+    i32.add))
@@ -0,0 +1,14 @@
+(module
+  (import "env" "add" (func $add (param i32 i32) (result i32)))
+  (import "env" "scale" (func $scale (param f64) (result f64)))
+  (import "env" "tick" (func $tick))
+
+  (func $main (export "main") (result i32)
+    call $tick
+    i32.const 40
+    i32.const 2
+    call $add              ;; 42
+    f64.const 2.5
+    call $scale            ;; 5
+    i32.trunc_f64_s
+    i32.add))
@@ -0,0 +1,33 @@
+(module
+  (func $main (export "main") (result i32)
+    (local $i i32)
+    (local $j i32)
+    (local $sum i32)
+    ;; outer loop, left through `br_if`
+    (loop $outer
+      i32.const 0
+      local.set $j
+      ;; inner loop with a parameter, left through fall-through
+      i32.const 0
+      (loop $inner (param i32) (result i32)
+        local.get $j
+        i32.add
+        local.get $j
+        i32.const 1
+        i32.add
+        local.tee $j
+        i32.const 4
+        i32.lt_u
+        br_if $inner)
+      local.get $sum
+      i32.add
+      local.set $sum
+      local.get $i
+      i32.const 1
+      i32.add
+      local.tee $i
+      i32.const 5
+      i32.lt_u
+      br_if $outer)
+    ;; 5 * (0 + 1 + 2 + 3)
+    local.get $sum))
@@ -0,0 +1,34 @@
+(module
+  (memory $memory_0 1)
+  (memory $memory_1 1)
+  (memory $memory_2 1)
+
+  (func (export "main") (result i32)
+
+    ;; TEST: store into a non-zero memory
+    i32.const 8
+    i32.const 42
+    i32.store $memory_2 align=2
+
+    ;; TEST: store with a non-natural alignment
+    i32.const 8
+    i32.const 7
+    i32.store16 $memory_1 align=1
+
+    ;; TEST: the store to memory 2 must not have touched memory 0
+    i32.const 8
+    i32.load $memory_0
+
+    ;; TEST: loads from non-zero memories
+    i32.const 8
+    i32.load $memory_2 align=2
+    i32.add
+    i32.const 8
+    i32.load16_u $memory_1 align=1
+    i32.add
+    i32.const 100
+    i32.add
+
+    ;; 0 + 42 + 7 + 100 = 149
+  )
+)
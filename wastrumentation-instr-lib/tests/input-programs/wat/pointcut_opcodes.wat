@@ -0,0 +1,14 @@
+(module
+  (memory 1)
+  (func $main (export "main") (result i32)
+    i32.const 0
+    i32.const 7
+    i32.store8
+    i32.const 0
+    i32.load8_u
+    i64.extend_i32_u
+    i64.const 3
+    i64.div_u
+    i32.wrap_i64
+    i32.const 5
+    i32.rem_s))
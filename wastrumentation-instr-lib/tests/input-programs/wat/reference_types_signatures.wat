@@ -0,0 +1,36 @@
+(module
+  (table $table 1 funcref)
+  (elem (table $table) (i32.const 0) func $forty_two)
+
+  (func $forty_two (result i32)
+    i32.const 42)
+
+  ;; TEST: funcref parameter and result
+  (func $identity (param funcref) (result funcref)
+    local.get 0)
+
+  ;; TEST: funcref parameter mixed with numeric values
+  (func $call_through (param i32 funcref) (result i32)
+    local.get 0
+    local.get 1
+    call $identity
+    table.set $table
+    local.get 0
+    call_indirect $table (result i32))
+
+  ;; TEST: externref parameter and result
+  (func $pass_extern (param externref) (result externref)
+    local.get 0)
+
+  (func (export "main") (result i32)
+    i32.const 0
+    ref.func $forty_two
+    call $call_through
+    ref.null extern
+    call $pass_extern
+    ref.is_null
+    i32.add
+
+    ;; 42 + 1 = 43
+  )
+)
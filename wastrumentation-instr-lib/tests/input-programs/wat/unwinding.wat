@@ -0,0 +1,54 @@
+(module
+  (func $find (param $limit i32) (result i32)
+    (local $i i32)
+    (block $done
+      (loop $search
+        (block $skip
+          local.get $i
+          local.get $limit
+          i32.eq
+          ;; `return` leaves $skip, $search and $done
+          (if
+            (then
+              local.get $i
+              i32.const 10
+              i32.mul
+              return))
+          local.get $i
+          i32.const 1
+          i32.add
+          local.tee $i
+          i32.const 100
+          i32.gt_u
+          ;; `br_if` leaves $skip, $search and $done
+          br_if $done
+          ;; `br` leaves $skip and continues $search
+          br $search)))
+    i32.const -1)
+
+  (func $pick (param $x i32) (result i32)
+    (block $two
+      (block $one
+        (block $zero
+          local.get $x
+          ;; every `br_table` target leaves a different number of blocks
+          br_table $zero $one $two)
+        i32.const 100
+        return)
+      i32.const 200
+      return)
+    i32.const 300)
+
+  (func $main (export "main") (result i32)
+    ;; 30 + 100 + 200 + 300
+    i32.const 3
+    call $find
+    i32.const 0
+    call $pick
+    i32.add
+    i32.const 1
+    call $pick
+    i32.add
+    i32.const 5
+    call $pick
+    i32.add))
@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{absolute, Path};
+use std::time::{Duration, Instant};
+
+use rust_to_wasm_compiler::WasiSupport;
+
+// Wastrumentation imports
+use wastrumentation::{compiler::Compiles, Configuration, Wastrumenter};
+use wastrumentation_lang_rust::compile::{compiler::Compiler, options::RustSource};
+use wastrumentation_lang_rust::generate::analysis::{Hook, RustAnalysisSpec};
+
+const PATH_INPUT_ANALYSIS: &str = "./tests/analyses/rust/logging/Cargo.toml";
+
+/*
+Execution on release:
+    cargo test --release --package wastrumentation-instr-lib --test instrumentation_time_wasmr3 -- test_instrumentation_time --exact --ignored --show-output
+*/
+
+// The input programs are not part of the repository, cfr. `memoization_test_wasmr3.rs`.
+// Every program is instrumented with the hooks sharing a traversal, as is the
+// default, and with every hook traversing on its own, as before they shared it.
+// The timings are printed & recorded in `instrumentation_time_wasmr3.csv` in
+// the temporary directory of the target directory.
+#[ignore]
+#[test]
+fn test_instrumentation_time() {
+    let record = Path::new(env!("CARGO_TARGET_TMPDIR")).join("instrumentation_time_wasmr3.csv");
+    let mut record = File::create(record).unwrap();
+    writeln!(
+        record,
+        "program,bytes,shared_traversal_ms,separate_traversals_ms"
+    )
+    .unwrap();
+
+    // Compiling the analysis & merging are part of every run, measured on an empty module
+    let empty_module = wat::parse_str("(module)").unwrap();
+    let baseline = instrumentation_time(&empty_module, false);
+    println!("Baseline (empty module): {baseline:.2?}");
+    writeln!(
+        record,
+        "baseline,{},{},",
+        empty_module.len(),
+        baseline.as_millis()
+    )
+    .unwrap();
+
+    for name in [
+        "factorial",
+        "game-of-life",
+        "rtexviewer",
+        "jqkungfu",
+        "parquet",
+        "rtexpacker",
+        "ffmpeg",
+        "pathfinding",
+        "sandspiel",
+        "commanderkeen",
+        "riconpacker",
+        "guiicons",
+        "mandelbrot",
+        "boa",
+        "multiplyDouble",
+        "fib",
+        "multiplyInt",
+        "pacalc",
+        "funky-kart",
+        "figma-startpage",
+        "hydro",
+        "jsc",
+        "rguilayout",
+        "bullet",
+        "sqlgui",
+        "rfxgen",
+        "rguistyler",
+    ] {
+        // Read input program
+        let mut input_program: Vec<u8> = Vec::new();
+        File::open(absolute(format!("../benchmarking-node/wasmr3-python/working-dir/wasm-r3/benchmarks/{name}/{name}.wasm")).unwrap())
+            .unwrap()
+            .read_to_end(&mut input_program)
+            .unwrap();
+
+        let shared = instrumentation_time(&input_program, false);
+        let separate = instrumentation_time(&input_program, true);
+        println!(
+            "{name} ({} bytes): {shared:.2?} ({:.2?} over baseline), {separate:.2?} in separate traversals",
+            input_program.len(),
+            shared.saturating_sub(baseline),
+        );
+        writeln!(
+            record,
+            "{name},{},{},{}",
+            input_program.len(),
+            shared.as_millis(),
+            separate.as_millis()
+        )
+        .unwrap();
+    }
+}
+
+/// Instruments `input_program` with every hook enabled
+fn instrumentation_time(input_program: &[u8], separate_passes: bool) -> Duration {
+    let analysis_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+    let instrumentation_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+
+    let source = RustSource::Manifest(WasiSupport::Enabled, absolute(PATH_INPUT_ANALYSIS).unwrap());
+    let analysis = RustAnalysisSpec {
+        source,
+        hooks: Hook::all_hooks(),
+    }
+    .into();
+
+    let configuration = Configuration {
+        separate_passes,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into())
+        .wastrument(input_program, analysis, &configuration)
+        .expect("Wastrumentation should succeed");
+    start.elapsed()
+}
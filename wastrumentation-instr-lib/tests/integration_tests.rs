@@ -124,6 +124,7 @@ fn test_analysis_denan() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Analysis),
+        ..Default::default()
     };
 
     let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
@@ -197,6 +198,7 @@ fn test_analysis_forward_guiicons() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
     };
 
     // Read input program
@@ -246,6 +248,7 @@ fn test_analysis_safe_heap() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Analysis),
+        ..Default::default()
     };
 
     let input_program = SOURCE.to_input_program();
@@ -368,6 +371,7 @@ fn test_analysis_memory_introspection() {
         // the reported `base_memory_size(0)` in the analysis
         // must target the input program!
         primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
     };
 
     let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
@@ -448,6 +452,7 @@ fn test_analysis_forward_pacalc() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
     };
 
     // Read input program
@@ -615,6 +620,7 @@ fn test_analysis_logging() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Analysis),
+        ..Default::default()
     };
 
     let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
@@ -1,8 +1,29 @@
+// Shared by several test crates, each using part of it
+#![allow(dead_code)]
+
+// Rust STD
+use std::collections::HashSet;
+use std::path::absolute;
+
 // Wastrumentation imports
 use rust_to_wasm_compiler::{Profile, WasiSupport};
+use wastrumentation::analysis::ProcessedAnalysis;
 use wastrumentation::compiler::Compiles;
+use wastrumentation::error::Error;
+use wastrumentation::{Configuration, SideTables, Wastrumenter};
 use wastrumentation_lang_rust::compile::compiler::Compiler;
 use wastrumentation_lang_rust::compile::options::*;
+use wastrumentation_lang_rust::compile::Rust;
+use wastrumentation_lang_rust::generate::analysis::{Hook, RustAnalysisSpec};
+
+// Wasmtime imports
+use wasmtime::{Config, Engine, Extern, Linker, Module, Store, WasmResults};
+use wasmtime_wasi::p2::pipe::MemoryOutputPipe;
+use wasmtime_wasi::{p1::WasiP1Ctx, WasiCtxBuilder};
+
+/// Prints every event it observes, each starting with `[ANALYSIS:] `
+const PATH_LOGGING_ANALYSIS: &str = "./tests/analyses/rust/logging/Cargo.toml";
+const EVENT_PREFIX: &str = "[ANALYSIS:] ";
 
 const INPUT_PROGRAM_MANIFEST: &str = r#"
 package.name = "rust-denan-input-program"
@@ -37,3 +58,148 @@ impl Source {
         }
     }
 }
+
+/// The logging analysis, for `hooks` only
+pub fn logging_analysis(hooks: HashSet<Hook>) -> ProcessedAnalysis<Rust> {
+    rust_analysis(PATH_LOGGING_ANALYSIS, hooks)
+}
+
+/// The Rust analysis at `manifest_path`, for `hooks` only
+pub fn rust_analysis(manifest_path: &str, hooks: HashSet<Hook>) -> ProcessedAnalysis<Rust> {
+    let source = RustSource::Manifest(WasiSupport::Enabled, absolute(manifest_path).unwrap());
+    RustAnalysisSpec { source, hooks }.into()
+}
+
+/// Instruments `input_program` with the logging analysis, for `hooks` only
+pub fn wastrument_logging(
+    input_program: &[u8],
+    hooks: HashSet<Hook>,
+    configuration: &Configuration,
+) -> Vec<u8> {
+    let (wastrumented, _side_tables) =
+        try_wastrument(input_program, logging_analysis(hooks), configuration)
+            .expect("Wastrumentation should succeed");
+    wastrumented
+}
+
+/// Instruments `input_program` with `analysis`, yielding the side tables as well
+pub fn try_wastrument(
+    input_program: &[u8],
+    analysis: ProcessedAnalysis<Rust>,
+    configuration: &Configuration,
+) -> Result<(Vec<u8>, SideTables), Error<Rust, Rust>> {
+    let analysis_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+    let instrumentation_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+    let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
+    wastrumenter.wastrument_with_side_tables(input_program, analysis, configuration)
+}
+
+/// The outcome of invoking `main` of an instrumented program
+pub struct Run<Results> {
+    pub result: Results,
+    stdout: String,
+}
+
+impl<Results> Run<Results> {
+    /// The events the logging analysis printed whose message starts with
+    /// `message`, e.g. `memory_fill` or `call pre`, in the order they occurred
+    pub fn events(&self, message: &str) -> Vec<&str> {
+        self.stdout
+            .split(EVENT_PREFIX)
+            .skip(1)
+            .filter(|event| event.starts_with(message))
+            .collect()
+    }
+}
+
+/// The `(function index, instruction index)` an event of the logging analysis reports
+pub fn location(event: &str) -> (i64, i64) {
+    let index = |field: &str| -> i64 {
+        let (_, rest) = event
+            .rsplit_once(&format!("{field}: "))
+            .unwrap_or_else(|| panic!("no {field} in event {event:?}"));
+        let digits: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '-')
+            .collect();
+        digits.parse().unwrap()
+    };
+    (index("funct_index"), index("instr_index"))
+}
+
+/// Invokes the `main` export of `wastrumented`, capturing what the analysis prints
+pub fn run_main<Results: WasmResults>(wastrumented: &[u8]) -> Run<Results> {
+    Instance::new(wastrumented, |_linker| ()).run_main()
+}
+
+/// Like [`run_main`], with the host functions `link` defines
+pub fn run_main_linking<Results: WasmResults>(
+    wastrumented: &[u8],
+    link: impl FnOnce(&mut Linker<WasiP1Ctx>),
+) -> Run<Results> {
+    Instance::new(wastrumented, link).run_main()
+}
+
+/// An instrumented program, instantiated as module `main`
+pub struct Instance {
+    pub store: Store<WasiP1Ctx>,
+    pub linker: Linker<WasiP1Ctx>,
+    stdout: MemoryOutputPipe,
+}
+
+impl Instance {
+    /// Instantiates `wastrumented` with the host functions `link` defines
+    pub fn new(wastrumented: &[u8], link: impl FnOnce(&mut Linker<WasiP1Ctx>)) -> Self {
+        let stdout = MemoryOutputPipe::new(usize::MAX);
+
+        let mut config = Config::new();
+        config
+            .wasm_backtrace(true)
+            .wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+        let engine = Engine::new(&config).unwrap();
+
+        let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |t| t).unwrap();
+        link(&mut linker);
+
+        let wasi_ctx = WasiCtxBuilder::new()
+            .stdout(stdout.clone())
+            .inherit_stderr()
+            .build_p1();
+        let mut store = Store::new(&engine, wasi_ctx);
+
+        // Note: This is a module built against the preview1 WASI API.
+        let module = Module::from_binary(&engine, wastrumented).unwrap();
+        linker.module(&mut store, "main", &module).unwrap();
+
+        Self {
+            store,
+            linker,
+            stdout,
+        }
+    }
+
+    /// Invokes the `main` export, with what the analysis printed so far
+    pub fn run_main<Results: WasmResults>(&mut self) -> Run<Results> {
+        let result = self
+            .export("main")
+            .into_func()
+            .unwrap()
+            .typed::<(), Results>(&self.store)
+            .unwrap()
+            .call(&mut self.store, ())
+            .unwrap();
+        self.run(result)
+    }
+
+    /// A run that yielded `result`, with what the analysis printed so far
+    pub fn run<Results>(&self, result: Results) -> Run<Results> {
+        let stdout = String::from_utf8(self.stdout.contents().to_vec()).unwrap();
+        Run { result, stdout }
+    }
+
+    /// The export `name` of the instrumented program
+    pub fn export(&mut self, name: &str) -> Extern {
+        self.linker.get(&mut self.store, "main", name).unwrap()
+    }
+}
@@ -191,6 +191,7 @@ fn report_memoization_benches_for(
     let configuration = Configuration {
         target_indices: Some(immutable_set.iter().copied().collect()),
         primary_selection: Some(PrimaryTarget::Analysis),
+        ..Default::default()
     };
 
     let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
@@ -255,6 +256,7 @@ fn report_memoization_benches_for(
     let configuration = Configuration {
         target_indices: Some(pure_functions_of_interest.clone()),
         primary_selection: Some(PrimaryTarget::Analysis),
+        ..Default::default()
     };
 
     let wastrumented = wastrumenter
@@ -0,0 +1,63 @@
+// Wastrumentation imports
+use wastrumentation::{BasicBlock, Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::BasicBlockEnter].into_iter().collect();
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let (wastrumented, side_tables) =
+        try_wastrument(&input_program, logging_analysis(hooks), &configuration)
+            .expect("Wastrumentation should succeed");
+
+    // The side table numbers the blocks by function & instruction index
+    let block = |id, function_index, instructions| BasicBlock {
+        id,
+        function_index,
+        instructions,
+    };
+    assert_eq!(
+        side_tables.basic_blocks,
+        vec![
+            block(0, 0, 0..4),
+            block(1, 1, 0..1),
+            block(2, 1, 1..4),
+            block(3, 1, 4..14),
+            block(4, 1, 14..16),
+        ]
+    );
+
+    let run = run_main::<i32>(&wastrumented);
+
+    // The basic block instrumentation leaves the program untouched
+    assert_eq!(run.result, 12);
+
+    // Every block is reported at its first instruction, under its id in the side table
+    let mut entered = Vec::new();
+    for event in run.events("basic_block enter") {
+        let (_, id) = event.split_once("BasicBlockId(").unwrap();
+        let (id, _) = id.split_once(')').unwrap();
+        let block = &side_tables.basic_blocks[id.parse::<usize>().unwrap()];
+        let start = (
+            i64::from(block.function_index),
+            i64::try_from(block.instructions.start).unwrap(),
+        );
+        assert_eq!(location(event), start, "{event}");
+        entered.push(block.id);
+    }
+    // Each of the 4 iterations enters the loop body, `$double` & the rest of the loop
+    assert_eq!(entered, [vec![1], [2, 0, 3].repeat(4), vec![4]].concat());
+}
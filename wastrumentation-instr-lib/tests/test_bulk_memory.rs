@@ -0,0 +1,71 @@
+// Wastrumentation imports
+use wastrumentation::error::{Error, InstrumentationError};
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/bulk_memory.wat";
+
+fn configuration() -> Configuration {
+    Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![
+        Hook::MemoryFill,
+        Hook::MemoryCopy,
+        Hook::MemoryInit,
+        Hook::DataDrop,
+        Hook::Load,
+    ]
+    .into_iter()
+    .collect();
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration());
+    let run = run_main::<i32>(&wastrumented);
+
+    // The logging analysis forwards the operands as-is
+    assert_eq!(run.result, 111);
+
+    // Every bulk-memory instruction of `main` is reported once, at its own site
+    for (message, instr_index) in [
+        ("memory_init", 3),
+        ("data_drop", 4),
+        ("memory_copy", 8),
+        ("memory_fill", 12),
+    ] {
+        let events = run.events(message);
+        assert_eq!(events.len(), 1, "{message}");
+        assert_eq!(location(events[0]), (0, instr_index), "{message}");
+    }
+    let loads: Vec<_> = run.events("load").into_iter().map(location).collect();
+    assert_eq!(loads, vec![(0, 14), (0, 16)]);
+}
+
+#[test]
+fn test_missing_getter() {
+    let hooks = vec![Hook::MemoryFill].into_iter().collect();
+    let mut analysis = logging_analysis(hooks);
+    analysis.analysis_interface.memory_fill_get_size = None;
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = try_wastrument(&input_program, analysis, &configuration());
+    assert!(matches!(
+        wastrumented,
+        Err(Error::InstrumentationError(
+            InstrumentationError::MissingTrap {
+                missing: "memory_fill_get_size",
+                ..
+            }
+        ))
+    ));
+}
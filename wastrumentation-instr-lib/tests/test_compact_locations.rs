@@ -0,0 +1,80 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let configuration = |compact_locations| Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        manifest: true,
+        compact_locations,
+        ..Default::default()
+    };
+    let hooks = || vec![Hook::Binary, Hook::Local].into_iter().collect();
+
+    let (wastrumented, side_tables) = try_wastrument(
+        &input_program,
+        logging_analysis(hooks()),
+        &configuration(true),
+    )
+    .expect("Wastrumentation should succeed");
+    let manifest = side_tables.manifest.expect("Manifest was requested");
+
+    // Every site has a dense id, in order of site
+    let sites: Vec<Option<u32>> = manifest.entries.iter().map(|entry| entry.site).collect();
+    let expected: Vec<Option<u32>> = (0..manifest.entries.len())
+        .map(|site| Some(u32::try_from(site).unwrap()))
+        .collect();
+    assert_eq!(sites, expected);
+
+    let mut instance = Instance::new(&wastrumented, |_linker| ());
+    let run = instance.run_main::<i32>();
+
+    // Passing site ids leaves the program untouched
+    assert_eq!(run.result, 12);
+
+    // The analysis observes the same locations as without site ids
+    let wastrumented_in_full = wastrument_logging(&input_program, hooks(), &configuration(false));
+    let run_in_full = run_main::<i32>(&wastrumented_in_full);
+    let locations =
+        |run: &Run<i32>| -> Vec<(i64, i64)> { run.events("").into_iter().map(location).collect() };
+    assert!(!locations(&run).is_empty());
+    assert_eq!(locations(&run), locations(&run_in_full));
+
+    let site_table = instance
+        .export("__wastrumentation_site_table")
+        .into_memory()
+        .unwrap();
+    let site_table = site_table.data(&instance.store).to_vec();
+
+    // The input has no memory of its own, so the memory helpers of the analysis
+    // must not reach the site table, though it is the first memory of the module
+    let memory_size = instance
+        .export("instrumented_memory_size")
+        .into_func()
+        .unwrap()
+        .typed::<i32, i32>(&instance.store)
+        .unwrap();
+    assert_eq!(memory_size.call(&mut instance.store, 0).unwrap(), 0);
+
+    let load_u8 = instance
+        .export("instrumented_base_load_i32_8U")
+        .into_func()
+        .unwrap()
+        .typed::<(i32, i32, i32, i32), i32>(&instance.store)
+        .unwrap();
+    let entry = site_table.iter().position(|byte| *byte != 0).unwrap();
+    let entry = i32::try_from(entry).unwrap();
+    assert_eq!(
+        load_u8.call(&mut instance.store, (entry, 0, 0, 0)).unwrap(),
+        0
+    );
+}
@@ -0,0 +1,95 @@
+// Rust STD
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Wastrumentation imports
+use rust_to_wasm_compiler::CompilerSetupError;
+use wastrumentation::compiler::{CacheKey, CompilationResult, CompileCache, Compiles};
+use wastrumentation::{Configuration, PrimaryTarget, Wastrumenter};
+
+use wastrumentation_lang_rust::{
+    compile::{compiler::Compiler, options::CompilerOptions, Rust},
+    generate::analysis::Hook,
+};
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+/// Counts the modules the Rust compiler actually compiles
+struct CountingCompiler {
+    compiler: Compiler,
+    compilations: Arc<AtomicUsize>,
+}
+
+impl Compiles<Rust> for CountingCompiler {
+    type CompilerOptions = CompilerOptions;
+    type CompilerSetupError = CompilerSetupError;
+
+    fn setup_compiler() -> Result<Self, Self::CompilerSetupError> {
+        Ok(Self {
+            compiler: Compiler::setup_compiler()?,
+            compilations: Arc::default(),
+        })
+    }
+
+    fn compile(&self, compiler_options: &Self::CompilerOptions) -> CompilationResult<Rust> {
+        self.compilations.fetch_add(1, Ordering::SeqCst);
+        self.compiler.compile(compiler_options)
+    }
+
+    fn cache_key(&self, compiler_options: &Self::CompilerOptions) -> Option<CacheKey> {
+        self.compiler.cache_key(compiler_options)
+    }
+}
+
+#[test]
+fn test_analysis() {
+    let compile_cache = tempfile::tempdir().unwrap();
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+
+    // Both the analysis & the instrumentation library are compiled on the first run only
+    let (first, first_compilations) = wastrument(&input_program, compile_cache.path());
+    assert_eq!(first_compilations, 2);
+    let (second, second_compilations) = wastrument(&input_program, compile_cache.path());
+    assert_eq!(second_compilations, 0);
+    assert_eq!(first, second);
+
+    // The cached analysis observes the `i32.add`, `i32.add` & `i32.lt_u` of `$main`
+    // and the `i32.mul` of `$double`, once per iteration
+    let run = run_main::<i32>(&second);
+    assert_eq!(run.result, 12);
+    assert_eq!(run.events("binary generic").len(), 4 * 4);
+}
+
+/// The instrumented `input_program` & the number of modules compiled for it
+fn wastrument(input_program: &[u8], compile_cache: &std::path::Path) -> (Vec<u8>, usize) {
+    let analysis_compiler = CountingCompiler::setup_compiler().expect("Setup Rust compiler");
+    let instrumentation_compiler = CountingCompiler::setup_compiler().expect("Setup Rust compiler");
+    let compilations = Arc::new(AtomicUsize::new(0));
+    let analysis_compiler = CountingCompiler {
+        compilations: compilations.clone(),
+        ..analysis_compiler
+    };
+    let instrumentation_compiler = CountingCompiler {
+        compilations: compilations.clone(),
+        ..instrumentation_compiler
+    };
+
+    // Generic apply has the instrumentation library generated
+    let hooks = vec![Hook::GenericApply, Hook::Binary].into_iter().collect();
+    let analysis = logging_analysis(hooks);
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    let wastrumented = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into())
+        .with_compile_cache(CompileCache::new(compile_cache))
+        .wastrument(input_program, analysis, &configuration)
+        .expect("Wastrumentation should succeed");
+    (wastrumented, compilations.load(Ordering::SeqCst))
+}
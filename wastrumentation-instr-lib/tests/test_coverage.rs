@@ -0,0 +1,72 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        coverage: true,
+        ..Default::default()
+    };
+
+    // Coverage is maintained without calling into the analysis
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, Default::default(), &configuration);
+    let mut instance = Instance::new(&wastrumented, |_linker| ());
+    let run = instance.run_main::<i32>();
+
+    // The coverage instrumentation leaves the program untouched
+    assert_eq!(run.result, 12);
+    assert!(run.events("").is_empty());
+
+    // The bitmap is exported together with its base address & size
+    let mut exported_i32 = |name| {
+        let global = instance.export(name).into_global().unwrap();
+        global.get(&mut instance.store).unwrap_i32()
+    };
+    let base = exported_i32("__wastrumentation_coverage_map_base");
+    let size = exported_i32("__wastrumentation_coverage_map_size");
+    assert_eq!((base, size), (0, 1 << 16));
+
+    let map = instance
+        .export("__wastrumentation_coverage_map")
+        .into_memory()
+        .unwrap();
+    let base = usize::try_from(base).unwrap();
+    let size = usize::try_from(size).unwrap();
+    let bitmap = map.data(&instance.store)[base..base + size].to_vec();
+
+    // Every entered block bumps a single counter:
+    // main: 1 + 4 (loop body) + 4 (after the call) + 1 (after the loop), double: 4
+    let hits: u32 = bitmap.iter().map(|count| u32::from(*count)).sum();
+    assert_eq!(hits, 14);
+
+    // The input has no memory of its own, so the memory helpers of the analysis
+    // must not reach the bitmap, though it is the first memory of the module
+    let memory_size = instance
+        .export("instrumented_memory_size")
+        .into_func()
+        .unwrap()
+        .typed::<i32, i32>(&instance.store)
+        .unwrap();
+    assert_eq!(memory_size.call(&mut instance.store, 0).unwrap(), 0);
+
+    let load_u8 = instance
+        .export("instrumented_base_load_i32_8U")
+        .into_func()
+        .unwrap()
+        .typed::<(i32, i32, i32, i32), i32>(&instance.store)
+        .unwrap();
+    let hit = bitmap.iter().position(|count| *count != 0).unwrap();
+    let hit = i32::try_from(base + hit).unwrap();
+    assert_eq!(
+        load_u8.call(&mut instance.store, (hit, 0, 0, 0)).unwrap(),
+        0
+    );
+}
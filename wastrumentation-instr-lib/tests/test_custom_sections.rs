@@ -0,0 +1,57 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, CustomSections, PrimaryTarget};
+
+// Wasmtime imports
+use wasmtime::WasmBacktrace;
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/custom_sections.wat";
+
+#[test]
+fn test_analysis() {
+    assert_eq!(
+        crashing_function_name(CustomSections::Preserve).as_deref(),
+        Some("crash")
+    );
+    assert_eq!(crashing_function_name(CustomSections::Strip), None);
+}
+
+/// The name of the innermost frame when `main` traps
+fn crashing_function_name(custom_sections: CustomSections) -> Option<String> {
+    let hooks = vec![Hook::Binary, Hook::Local].into_iter().collect();
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        custom_sections,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+    let mut instance = Instance::new(&wastrumented, |_linker| ());
+    let main = instance
+        .export("main")
+        .into_func()
+        .unwrap()
+        .typed::<(), i32>(&instance.store)
+        .unwrap();
+
+    // The trap is reported with the name-section name of the crashing function
+    let error = main.call(&mut instance.store, ()).unwrap_err();
+
+    // Either way, `$crash` is instrumented up to the trap
+    let run = instance.run(());
+    let locations: Vec<_> = run.events("").into_iter().map(location).collect();
+    assert_eq!(locations, vec![(0, 0), (0, 2)]);
+
+    let backtrace = error.downcast_ref::<WasmBacktrace>().unwrap();
+    backtrace
+        .frames()
+        .first()
+        .and_then(|frame| frame.func_name())
+        .map(str::to_string)
+}
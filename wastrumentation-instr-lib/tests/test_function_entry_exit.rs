@@ -0,0 +1,49 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/function_entry_exit.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![
+        Hook::FunctionEntry,
+        Hook::FunctionExit,
+        Hook::Return,
+        Hook::Branch,
+    ]
+    .into_iter()
+    .collect();
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+    let run = run_main::<i32>(&wastrumented);
+
+    // The entry and exit traps leave the results of every return path untouched
+    assert_eq!(run.result, 60);
+
+    // `$main` (5) calls `$empty` (4), `$fall_through` (0), `$early_return` (1) twice,
+    // `$branch_out` (2) & `$branch_table_out` (3) twice
+    let functions = |message: &str| -> Vec<i64> {
+        let events = run.events(message);
+        events.into_iter().map(|event| location(event).0).collect()
+    };
+    assert_eq!(functions("Function entry"), vec![5, 4, 0, 1, 1, 2, 3, 3]);
+    // Every return path, be it fall-through, `return`, `br`, `br_if` or `br_table`, exits once
+    assert_eq!(functions("Function exit"), vec![4, 0, 1, 1, 2, 3, 3, 5]);
+
+    // The entry trap is reported at the first instruction
+    for event in run.events("Function entry") {
+        assert_eq!(location(event).1, 0);
+    }
+}
@@ -0,0 +1,56 @@
+// Rust STD
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget, TargetSelection};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::GenericApply, Hook::Binary].into_iter().collect();
+
+    // `generic_apply` on `$double` only, binary operations in `$main` only
+    let mut hook_targets = HashMap::new();
+    for trap_name in Hook::GenericApply.trap_names() {
+        hook_targets.insert(trap_name, TargetSelection::Indices([0].into()));
+    }
+    for trap_name in Hook::Binary.trap_names() {
+        let in_main =
+            TargetSelection::Predicate(Arc::new(|function_index: u32| function_index == 1));
+        hook_targets.insert(trap_name, in_main);
+    }
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        hook_targets,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+    let run = run_main::<i32>(&wastrumented);
+
+    // Restricting the hooks leaves the program untouched
+    assert_eq!(run.result, 12);
+
+    // `$double` is applied once per iteration, `$main` is never applied
+    assert_eq!(run.events("apply (pre)").len(), 4);
+    assert_eq!(run.events("apply (post)").len(), 4);
+
+    // The `i32.add`, `i32.add` & `i32.lt_u` of every iteration of `$main`,
+    // none of them the `i32.mul` of `$double`
+    let binaries = run.events("binary generic");
+    assert_eq!(binaries.len(), 3 * 4);
+    for binary in binaries {
+        assert_eq!(location(binary).0, 1);
+        assert!(!binary.contains("Mul"), "{binary}");
+    }
+}
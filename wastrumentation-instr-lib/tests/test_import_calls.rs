@@ -0,0 +1,53 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/import_calls.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::ImportCallPre, Hook::ImportCallPost]
+        .into_iter()
+        .collect();
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+
+    // The host functions imported by the input program
+    let run = run_main_linking::<i32>(&wastrumented, |linker| {
+        linker
+            .func_wrap("env", "add", |a: i32, b: i32| a + b)
+            .unwrap();
+        linker.func_wrap("env", "scale", |x: f64| x * 2.0).unwrap();
+        linker.func_wrap("env", "tick", || {}).unwrap();
+    });
+
+    // The import call traps pass the arguments and results through as-is
+    assert_eq!(run.result, 47);
+
+    // Both traps report every import call, naming the import
+    for message in ["import_call pre", "import_call post"] {
+        let events = run.events(message);
+        assert_eq!(events.len(), 3, "{message}");
+        for (event, name) in events.iter().zip(["tick", "add", "scale"]) {
+            assert!(event.contains(r#"module: "env""#), "{event}");
+            assert!(event.contains(&format!("name: {name:?}")), "{event}");
+        }
+    }
+    let calls: Vec<_> = run
+        .events("import_call pre")
+        .into_iter()
+        .map(location)
+        .collect();
+    assert_eq!(calls, vec![(3, 0), (3, 3), (3, 5)]);
+}
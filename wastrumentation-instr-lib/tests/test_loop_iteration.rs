@@ -0,0 +1,48 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/loop_iteration.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::LoopPre, Hook::LoopIteration, Hook::LoopPost]
+        .into_iter()
+        .collect();
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+    let run = run_main::<i32>(&wastrumented);
+
+    // The loop head instrumentation leaves the loop parameters untouched
+    assert_eq!(run.result, 30);
+
+    // `$outer` is entered once, `$inner` once per iteration of `$outer`
+    let loops: Vec<_> = run.events("loop_ pre").into_iter().map(location).collect();
+    assert_eq!(loops, vec![(0, 0), (0, 4), (0, 4), (0, 4), (0, 4), (0, 4)]);
+
+    // Only back-edges are reported, counting from 1 since the loop was entered
+    let iterations = |instr_index| -> Vec<i64> {
+        run.events("loop_ iteration")
+            .into_iter()
+            .filter(|event| location(event) == (0, instr_index))
+            .map(|event| {
+                let (_, count) = event.split_once("LoopIterationCount(").unwrap();
+                let (count, _) = count.split_once(')').unwrap();
+                count.parse().unwrap()
+            })
+            .collect()
+    };
+    assert_eq!(iterations(0), vec![1, 2, 3, 4]);
+    assert_eq!(iterations(4), [1, 2, 3].repeat(5));
+}
@@ -0,0 +1,103 @@
+// Rust STD
+use std::collections::HashSet;
+
+// Wastrumentation imports
+use wastrumentation::manifest::{ManifestEntry, Operands};
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::Binary, Hook::Local, Hook::GenericApply]
+        .into_iter()
+        .collect();
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        manifest: true,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let (wastrumented, side_tables) =
+        try_wastrument(&input_program, logging_analysis(hooks), &configuration)
+            .expect("Wastrumentation should succeed");
+    let manifest = side_tables.manifest.expect("Manifest was requested");
+
+    // The code of `$double` starts at offset 3: `local.get 0`, `i32.const 2` & `i32.mul`
+    assert!(manifest.entries.contains(&ManifestEntry {
+        trap: "trap_local_get_i32".into(),
+        funct_index: 0,
+        function_name: Some("double".into()),
+        instr_index: 0,
+        opcode: Some("local.get".into()),
+        code_offset: Some(3),
+        operands: Operands::Local { local: 0 },
+        site: None,
+    }));
+    assert!(manifest.entries.contains(&ManifestEntry {
+        trap: "binary_i32_i32_to_i32".into(),
+        funct_index: 0,
+        function_name: Some("double".into()),
+        instr_index: 2,
+        opcode: Some("i32.mul".into()),
+        code_offset: Some(7),
+        operands: Operands::None,
+        site: None,
+    }));
+    // One entry per site: `i32.mul`, both `i32.add` & `i32.lt_u`
+    let binary_sites = manifest
+        .entries
+        .iter()
+        .filter(|entry| entry.trap == "binary_i32_i32_to_i32")
+        .count();
+    assert_eq!(binary_sites, 4);
+    // `generic_apply` wraps whole functions, its calls have no site
+    assert!(manifest
+        .entries
+        .iter()
+        .all(|entry| entry.trap != "generic_apply"));
+
+    let json: serde_json::Value = serde_json::from_str(&manifest.to_json().unwrap()).unwrap();
+    assert_eq!(json["entries"][0]["operands"]["kind"], "local");
+
+    let run = run_main::<i32>(&wastrumented);
+
+    // Recording a manifest leaves the program untouched
+    assert_eq!(run.result, 12);
+
+    // Every observed binary operation & local is joined with the opcode of its site
+    let sites: HashSet<(i64, i64, &str)> = manifest
+        .entries
+        .iter()
+        .map(|entry| {
+            let opcode = entry.opcode.as_deref().unwrap();
+            (entry.funct_index.into(), entry.instr_index as i64, opcode)
+        })
+        .collect();
+    for (message, opcodes) in [
+        ("binary generic", &["i32.mul", "i32.add", "i32.lt_u"][..]),
+        (
+            "local generic",
+            &["local.get", "local.set", "local.tee"][..],
+        ),
+    ] {
+        let events = run.events(message);
+        assert!(!events.is_empty());
+        for event in events {
+            let (funct_index, instr_index) = location(event);
+            let joined = opcodes
+                .iter()
+                .any(|opcode| sites.contains(&(funct_index, instr_index, *opcode)));
+            assert!(joined, "{event}");
+        }
+    }
+    // `$double` is applied 4 times, `$main` once, without an entry in the manifest
+    assert_eq!(run.events("apply (pre)").len(), 5);
+}
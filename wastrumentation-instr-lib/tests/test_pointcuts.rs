@@ -0,0 +1,135 @@
+// Rust STD
+use std::collections::HashMap;
+
+// Wastrumentation imports
+use wastrumentation::error::{Error, InstrumentationError};
+use wastrumentation::{Configuration, Pointcut, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+const PATH_INPUT_PROGRAM_OPCODES: &str = "./tests/input-programs/wat/pointcut_opcodes.wat";
+
+#[test]
+fn test_analysis() {
+    let hooks = vec![Hook::Binary, Hook::Local].into_iter().collect();
+
+    // `i32.mul` only among the binary operations, locals inside the loop of `$main` only
+    let mut hook_pointcuts = HashMap::new();
+    for trap_name in Hook::Binary.trap_names() {
+        let multiplications = Pointcut::Opcodes(["i32.mul".to_string()].into());
+        hook_pointcuts.insert(trap_name, multiplications);
+    }
+    for trap_name in Hook::Local.trap_names() {
+        let in_loop = Pointcut::Within {
+            function_index: 1,
+            instr_index: 0,
+        };
+        hook_pointcuts.insert(trap_name, in_loop);
+    }
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        hook_pointcuts,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+    let run = run_main::<i32>(&wastrumented);
+
+    // Restricting the hooks to a pointcut leaves the program untouched
+    assert_eq!(run.result, 12);
+
+    // The `i32.mul` of `$double`, once per iteration, none of the `i32.add`s & `i32.lt_u` of `$main`
+    let binaries: Vec<_> = run
+        .events("binary generic")
+        .into_iter()
+        .map(location)
+        .collect();
+    assert_eq!(binaries, vec![(0, 2); 4]);
+
+    // The `local.get`, `local.set` & `local.tee` of every iteration, neither the
+    // `local.get` of `$double` nor the one following the loop
+    let locals: Vec<_> = run
+        .events("local generic")
+        .into_iter()
+        .map(location)
+        .collect();
+    let iteration = [(1, 1), (1, 2), (1, 5), (1, 6), (1, 9)];
+    assert_eq!(locals, iteration.repeat(4));
+}
+
+#[test]
+fn test_opcodes() {
+    let hooks = vec![Hook::Load, Hook::Store, Hook::Unary, Hook::Binary];
+
+    // One mnemonic of each operator table, spelled as in the text format
+    let opcodes =
+        Pointcut::opcodes(["i32.store8", "i32.load8_u", "i64.extend_i32_u", "i64.div_u"]).unwrap();
+    let mut hook_pointcuts = HashMap::new();
+    for hook in &hooks {
+        for trap_name in hook.trap_names() {
+            hook_pointcuts.insert(trap_name, opcodes.clone());
+        }
+    }
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        hook_pointcuts,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM_OPCODES).unwrap();
+    let wastrumented =
+        wastrument_logging(&input_program, hooks.into_iter().collect(), &configuration);
+    let run = run_main::<i32>(&wastrumented);
+    assert_eq!(run.result, 2);
+
+    // Neither the `i32.wrap_i64` nor the `i32.rem_s`
+    let sites = |prefix| -> Vec<_> { run.events(prefix).into_iter().map(location).collect() };
+    assert_eq!(sites("store generic"), vec![(0, 2)]);
+    assert_eq!(sites("load generic"), vec![(0, 4)]);
+    assert_eq!(sites("unary generic"), vec![(0, 5)]);
+    assert_eq!(sites("binary generic"), vec![(0, 7)]);
+}
+
+#[test]
+fn test_unknown_mnemonic() {
+    assert!(matches!(
+        Pointcut::opcodes(["i32.div_s", "i32.divs"]),
+        Err(InstrumentationError::UnknownMnemonic { mnemonic }) if mnemonic == "i32.divs",
+    ));
+
+    // Also when nested in a pointcut built by hand
+    let misspelled = Pointcut::Any(vec![
+        Pointcut::Opcodes(["i64.extend_i32_u".to_string()].into()),
+        Pointcut::All(vec![Pointcut::Opcodes(
+            ["i64.extend32_u".to_string()].into(),
+        )]),
+    ]);
+    let hook_pointcuts = Hook::Unary
+        .trap_names()
+        .into_iter()
+        .map(|trap_name| (trap_name, misspelled.clone()))
+        .collect();
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        hook_pointcuts,
+        ..Default::default()
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM_OPCODES).unwrap();
+    let analysis = logging_analysis([Hook::Unary].into_iter().collect());
+    assert!(matches!(
+        try_wastrument(&input_program, analysis, &configuration),
+        Err(Error::InstrumentationError(InstrumentationError::UnknownMnemonic { mnemonic }))
+            if mnemonic == "i64.extend32_u",
+    ));
+}
@@ -0,0 +1,82 @@
+// Rust STD
+use std::path::absolute;
+
+// Wastrumentation imports
+use rust_to_wasm_compiler::WasiSupport;
+use wastrumentation::{compiler::Compiles, Configuration, PrimaryTarget, Wastrumenter};
+
+// Wasmtime imports
+use wasmtime::{Config, Engine, Linker, Module, Store};
+
+use wasmtime_wasi::{p1::WasiP1Ctx, WasiCtxBuilder};
+
+use wastrumentation_lang_rust::{
+    compile::{compiler::Compiler, options::RustSource},
+    generate::analysis::{Hook, RustAnalysisSpec},
+};
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/reference_types_signatures.wat";
+const PATH_INPUT_ANALYSIS: &str = "./tests/analyses/rust/logging/Cargo.toml";
+
+#[test]
+fn test_analysis() {
+    let analysis_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+    let instrumentation_compiler = Compiler::setup_compiler().expect("Setup Rust compiler");
+
+    let source = RustSource::Manifest(WasiSupport::Enabled, absolute(PATH_INPUT_ANALYSIS).unwrap());
+    let hooks = vec![Hook::GenericApply, Hook::CallPre, Hook::CallPost]
+        .into_iter()
+        .collect();
+    let analysis = RustAnalysisSpec { source, hooks }.into();
+
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+
+    // compile wat to wasm
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+
+    let wastrumenter = Wastrumenter::new(instrumentation_compiler.into(), analysis_compiler.into());
+    let wastrumented = wastrumenter
+        .wastrument(&input_program, analysis, &configuration)
+        .expect("Wastrumentation should succeed");
+
+    /////////////////////
+    // WASMTIME ENGINE //
+    /////////////////////
+
+    // Construct the wasm engine
+    let mut config = Config::new();
+    config
+        .wasm_backtrace(true)
+        .wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+    let engine = Engine::new(&config).unwrap();
+
+    let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+    wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |t| t).unwrap();
+
+    let wasi_ctx = WasiCtxBuilder::new()
+        .inherit_stdout()
+        .inherit_stderr()
+        .build_p1();
+    let mut store = Store::new(&engine, wasi_ctx);
+
+    // Note: This is a module built against the preview1 WASI API.
+    let module = Module::from_binary(&engine, &wastrumented).unwrap();
+    linker.module(&mut store, "main", &module).unwrap();
+
+    // Get function
+    let entry_point_function = &linker
+        .get(&mut store, "main", "main")
+        .unwrap()
+        .into_func()
+        .unwrap()
+        .typed::<(), i32>(&store)
+        .unwrap();
+
+    // Reference-typed arguments and results must survive the round trip through the analysis
+    let result = entry_point_function.call(&mut store, ()).unwrap();
+    assert_eq!(result, 43);
+}
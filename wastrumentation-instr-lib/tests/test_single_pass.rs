@@ -0,0 +1,41 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+#[test]
+fn test_analysis() {
+    for input_program in [
+        "basic_blocks",
+        "br-table",
+        "bulk_memory",
+        "call_indirect",
+        "function_entry_exit",
+        "if-then-else-2-times",
+        "import_calls",
+        "nested-loops",
+        "select",
+        "tables",
+        "unwinding",
+    ] {
+        let path = format!("./tests/input-programs/wat/{input_program}.wat");
+        let input_program = wat::parse_file(path).unwrap();
+        let wastrument = |separate_passes| {
+            let configuration = Configuration {
+                target_indices: None,
+                primary_selection: Some(PrimaryTarget::Target),
+                unwinding: true,
+                separate_passes,
+                ..Default::default()
+            };
+            wastrument_logging(&input_program, Hook::all_hooks(), &configuration)
+        };
+
+        // Sharing a traversal among the instruction hooks yields the very same
+        // module as every hook traversing the bodies on its own
+        assert_eq!(wastrument(false), wastrument(true));
+    }
+}
@@ -0,0 +1,117 @@
+// Wastrumentation imports
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+/// Prints the source location of every constant & binary operation it observes
+const PATH_INPUT_ANALYSIS: &str = "./tests/analyses/rust/source-locations/Cargo.toml";
+
+fn append_custom_section(module: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let size = 1 + name.len() + data.len();
+    module.extend_from_slice(&[0, u8::try_from(size).unwrap()]);
+    module.push(u8::try_from(name.len()).unwrap());
+    module.extend_from_slice(name.as_bytes());
+    module.extend_from_slice(data);
+}
+
+/// A module whose `i32.const`s stem from line 3 of `/src/main.c`, its `i32.add` from line 4
+fn input_program() -> Vec<u8> {
+    // Code section contents: [count, size, no locals, 41 01, 41 02, 6a, 0b],
+    // the instructions start at code offsets 3, 5, 7 & 8
+    let mut module = wat::parse_str(
+        r#"
+        (module
+            (func (export "main") (result i32)
+                i32.const 1
+                i32.const 2
+                i32.add))"#,
+    )
+    .unwrap();
+
+    #[rustfmt::skip]
+    let debug_abbrev = [
+        // compile unit without children, with a `DW_AT_stmt_list` section offset
+        0x01, 0x11, 0x00, 0x10, 0x17, 0x00, 0x00,
+        0x00,
+    ];
+    #[rustfmt::skip]
+    let debug_info = [
+        // length, DWARF 4, abbreviation offset, address size
+        0x0c, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+        // the compile unit, its line program at offset 0
+        0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+    #[rustfmt::skip]
+    let debug_line = [
+        // length, DWARF 4, header length
+        0x41, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x00,
+        // instruction length, operations, is_stmt, line base, line range, opcode base
+        0x01, 0x01, 0x01, 0xfb, 0x0e, 0x0d,
+        // standard opcode lengths
+        0x00, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01,
+        // directories: "/src"
+        b'/', b's', b'r', b'c', 0x00, 0x00,
+        // files: "main.c" in directory 1
+        b'm', b'a', b'i', b'n', b'.', b'c', 0x00, 0x01, 0x00, 0x00, 0x00,
+        // address 3: line 3, column 5
+        0x00, 0x05, 0x02, 0x03, 0x00, 0x00, 0x00, 0x03, 0x02, 0x05, 0x05, 0x01,
+        // address 7: line 4, column 1
+        0x02, 0x04, 0x03, 0x01, 0x05, 0x01, 0x01,
+        // end of the sequence at address 9
+        0x02, 0x02, 0x00, 0x01, 0x01,
+    ];
+    append_custom_section(&mut module, ".debug_abbrev", &debug_abbrev);
+    append_custom_section(&mut module, ".debug_info", &debug_info);
+    append_custom_section(&mut module, ".debug_line", &debug_line);
+    module
+}
+
+fn run(source_locations: bool) -> Run<i32> {
+    let hooks = vec![Hook::Const, Hook::Binary].into_iter().collect();
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        source_locations,
+        ..Default::default()
+    };
+
+    let analysis = rust_analysis(PATH_INPUT_ANALYSIS, hooks);
+    let (wastrumented, _side_tables) = try_wastrument(&input_program(), analysis, &configuration)
+        .expect("Wastrumentation should succeed");
+    run_main::<i32>(&wastrumented)
+}
+
+#[test]
+fn test_analysis() {
+    let run = run(true);
+    assert_eq!(run.result, 3);
+
+    let source = |line, column| {
+        format!(r#"Some(SourceLocation {{ file: "/src/main.c", line: {line}, column: {column} }})"#)
+    };
+    let consts = run.events("const_");
+    assert_eq!(consts.len(), 2);
+    for (instr_index, event) in consts.into_iter().enumerate() {
+        assert_eq!(location(event), (0, instr_index as i64));
+        assert!(event.contains(&source(3, 5)), "{event}");
+    }
+    let binaries = run.events("binary");
+    assert_eq!(binaries.len(), 1);
+    assert_eq!(location(binaries[0]), (0, 2));
+    assert!(binaries[0].contains(&source(4, 1)), "{}", binaries[0]);
+}
+
+#[test]
+fn test_without_source_locations() {
+    let run = run(false);
+    assert_eq!(run.result, 3);
+
+    let events = run.events("");
+    assert_eq!(events.len(), 3);
+    for event in events {
+        assert!(event.contains("None"), "{event}");
+    }
+}
@@ -31,6 +31,7 @@ fn test_analysis() {
     let configuration = Configuration {
         target_indices: None,
         primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
     };
 
     // compile wat to wasm
@@ -0,0 +1,61 @@
+// Wastrumentation imports
+use wastrumentation::error::{Error, InstrumentationError};
+use wastrumentation::{Configuration, FunctionPattern, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/basic_blocks.wat";
+
+#[test]
+fn test_analysis() {
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+
+    // The name-section name of `$double`, the export name of `$main`
+    for (pattern, function_index) in [
+        (FunctionPattern::Glob("dou*".into()), 0),
+        (FunctionPattern::Name("main".into()), 1),
+    ] {
+        let configuration = Configuration {
+            target_indices: None,
+            primary_selection: Some(PrimaryTarget::Target),
+            target_names: Some(vec![pattern]),
+            ..Default::default()
+        };
+
+        let hooks = vec![Hook::Binary, Hook::Local].into_iter().collect();
+        let wastrumented = wastrument_logging(&input_program, hooks, &configuration);
+        let run = run_main::<i32>(&wastrumented);
+
+        // Selecting the functions by name leaves the program untouched
+        assert_eq!(run.result, 12);
+
+        // Only the selected function is instrumented, `$double` & `$main` both
+        // have binary operations & locals
+        let events = run.events("");
+        assert!(!events.is_empty());
+        for event in events {
+            assert_eq!(location(event).0, function_index, "{event}");
+        }
+    }
+}
+
+#[test]
+fn test_unmatched_pattern() {
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let configuration = Configuration {
+        target_names: Some(vec![FunctionPattern::Regex("fib_[0-9]+".into())]),
+        ..Default::default()
+    };
+
+    let hooks = vec![Hook::Binary].into_iter().collect();
+    let wastrumented = try_wastrument(&input_program, logging_analysis(hooks), &configuration);
+    assert!(matches!(
+        wastrumented,
+        Err(Error::InstrumentationError(
+            InstrumentationError::UnmatchedFunctionPattern { .. }
+        ))
+    ));
+}
@@ -0,0 +1,114 @@
+// Wastrumentation imports
+use wastrumentation::error::{Error, InstrumentationError};
+use wastrumentation::{Configuration, PrimaryTarget};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+/// The error instrumenting `input_program` with every hook yields
+fn wastrument_error(input_program: &str) -> InstrumentationError {
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        ..Default::default()
+    };
+    let input_program = wat::parse_str(input_program).unwrap();
+    let analysis = logging_analysis(Hook::all_hooks());
+    match try_wastrument(&input_program, analysis, &configuration) {
+        Err(Error::InstrumentationError(error)) => error,
+        Err(error) => panic!("unexpected error: {error}"),
+        Ok(_) => panic!("unsupported input should be rejected"),
+    }
+}
+
+// The pinned parser models no `v128` type or instruction
+#[test]
+fn test_simd() {
+    let error = wastrument_error(
+        r#"
+        (module
+          (func $main (export "main") (result i32)
+            (i32x4.extract_lane 0
+              (i32x4.add
+                (v128.const i32x4 1 2 3 4)
+                (v128.const i32x4 5 6 7 8)))))"#,
+    );
+    assert!(
+        matches!(error, InstrumentationError::ParseModuleError(_)),
+        "{error}"
+    );
+}
+
+// The pinned parser models no `return_call` or `return_call_indirect`
+#[test]
+fn test_tail_calls() {
+    let error = wastrument_error(
+        r#"
+        (module
+          (func $double (param i32) (result i32)
+            (i32.mul (local.get 0) (i32.const 2)))
+          (func $main (export "main") (result i32)
+            (return_call $double (i32.const 21))))"#,
+    );
+    assert!(
+        matches!(error, InstrumentationError::ParseModuleError(_)),
+        "{error}"
+    );
+}
+
+// The pinned parser models no tags, `try_table`, `throw` or `throw_ref`
+#[test]
+fn test_exception_handling() {
+    let error = wastrument_error(
+        r#"
+        (module
+          (tag $error (param i32))
+          (func $main (export "main") (result i32)
+            (block $caught (result i32)
+              (try_table (catch $error $caught)
+                (throw $error (i32.const 42)))
+              (i32.const 0))))"#,
+    );
+    assert!(
+        matches!(error, InstrumentationError::ParseModuleError(_)),
+        "{error}"
+    );
+}
+
+// The pinned parser models no shared memories or atomic instructions
+#[test]
+fn test_threads() {
+    let error = wastrument_error(
+        r#"
+        (module
+          (memory 1 1 shared)
+          (func $main (export "main") (result i32)
+            (i32.atomic.rmw.add (i32.const 0) (i32.const 1))))"#,
+    );
+    assert!(
+        matches!(error, InstrumentationError::ParseModuleError(_)),
+        "{error}"
+    );
+}
+
+// The memory traps pass i32 addresses, so a 64-bit memory is rejected up front
+#[test]
+fn test_memory64() {
+    let error = wastrument_error(
+        r#"
+        (module
+          (memory $small 1)
+          (memory $large i64 1)
+          (func $main (export "main") (result i32)
+            (i32.load $large (i64.const 0))))"#,
+    );
+    assert!(
+        matches!(
+            error,
+            InstrumentationError::UnsupportedMemory64 { memory: 1 }
+        ),
+        "{error}"
+    );
+}
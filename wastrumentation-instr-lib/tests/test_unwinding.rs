@@ -0,0 +1,118 @@
+// Rust STD
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// Wastrumentation imports
+use wastrumentation::{Configuration, Pointcut, PrimaryTarget, TargetSelection};
+
+use wastrumentation_lang_rust::generate::analysis::Hook;
+
+mod integration_util;
+use integration_util::*;
+
+const PATH_INPUT_PROGRAM: &str = "./tests/input-programs/wat/unwinding.wat";
+
+fn hooks() -> HashSet<Hook> {
+    vec![
+        Hook::BlockPre,
+        Hook::BlockPost,
+        Hook::LoopPre,
+        Hook::LoopPost,
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn run_unwinding(configuration: Configuration) -> Run<i32> {
+    let configuration = Configuration {
+        target_indices: None,
+        primary_selection: Some(PrimaryTarget::Target),
+        unwinding: true,
+        ..configuration
+    };
+
+    let input_program = wat::parse_file(PATH_INPUT_PROGRAM).unwrap();
+    let wastrumented = wastrument_logging(&input_program, hooks(), &configuration);
+    let run = run_main::<i32>(&wastrumented);
+
+    // Unwinding leaves the branch operands untouched
+    assert_eq!(run.result, 630);
+    run
+}
+
+/// Every construct left by a branch or return reports its post trap, innermost first
+fn assert_balanced(run: &Run<i32>) {
+    let mut entered = Vec::new();
+    for event in run.events("") {
+        match event.split_once([' ', ',']) {
+            Some((construct @ ("block" | "loop_"), rest)) if rest.starts_with("pre") => {
+                entered.push(construct);
+            }
+            Some((construct @ ("block" | "loop_"), rest)) if rest.starts_with("post") => {
+                assert_eq!(entered.pop(), Some(construct), "{event}");
+            }
+            _ => (),
+        }
+    }
+    assert!(entered.is_empty(), "not left: {entered:?}");
+}
+
+#[test]
+fn test_analysis() {
+    let run = run_unwinding(Configuration::default());
+
+    // `$find` enters `$done` once & `$skip` on each of its 4 iterations,
+    // every call of `$pick` enters its 3 blocks
+    assert_eq!(run.events("block pre").len(), 5 + 3 * 3);
+    assert_eq!(run.events("loop_ pre").len(), 1);
+    assert_balanced(&run);
+}
+
+#[test]
+fn test_pointcut() {
+    // A pointcut selecting the blocks leaves the branches that leave them unwound
+    let mut hook_pointcuts = HashMap::new();
+    for trap_name in Hook::BlockPre
+        .trap_names()
+        .into_iter()
+        .chain(Hook::BlockPost.trap_names())
+    {
+        hook_pointcuts.insert(trap_name, Pointcut::Opcodes(["block".to_string()].into()));
+    }
+
+    let run = run_unwinding(Configuration {
+        hook_pointcuts,
+        ..Default::default()
+    });
+
+    assert_eq!(run.events("block pre").len(), 5 + 3 * 3);
+    assert_eq!(run.events("block post").len(), 5 + 3 * 3);
+    assert_balanced(&run);
+}
+
+#[test]
+fn test_hook_targets() {
+    // The blocks of `$pick` only, the loop post trap keeps unwinding the loop of `$find`
+    let mut hook_targets = HashMap::new();
+    for trap_name in Hook::BlockPre
+        .trap_names()
+        .into_iter()
+        .chain(Hook::BlockPost.trap_names())
+    {
+        let in_pick =
+            TargetSelection::Predicate(Arc::new(|function_index: u32| function_index == 1));
+        hook_targets.insert(trap_name, in_pick);
+    }
+
+    let run = run_unwinding(Configuration {
+        hook_targets,
+        ..Default::default()
+    });
+
+    for event in run.events("block") {
+        assert_eq!(location(event).0, 1, "{event}");
+    }
+    assert_eq!(run.events("block pre").len(), 3 * 3);
+    assert_eq!(run.events("loop_ post").len(), 1);
+    assert_balanced(&run);
+}
@@ -41,6 +41,15 @@ pub const TRAP_NAME_BINARY_F64_F64_TO_F64: &str = "binary_f64_f64_to_f64";
 
 pub const TRAP_NAME_DROP: &str = "drop_trap";
 pub const TRAP_NAME_RETURN: &str = "return_trap";
+pub const TRAP_NAME_FUNCTION_ENTRY: &str = "trap_function_entry";
+pub const TRAP_NAME_FUNCTION_EXIT: &str = "trap_function_exit";
+
+pub const TRAP_NAME_IMPORT_CALL_PRE: &str = "trap_import_call_pre";
+pub const TRAP_NAME_IMPORT_CALL_POST: &str = "trap_import_call_post";
+pub const TRAP_NAME_IMPORT_NAME_BYTE: &str = "trap_import_name_byte";
+
+pub const TRAP_NAME_SOURCE_LOCATION: &str = "trap_source_location";
+pub const TRAP_NAME_SOURCE_FILE_BYTE: &str = "trap_source_file_byte";
 
 pub const TRAP_CONST_I32: &str = "trap_const_i32";
 pub const TRAP_CONST_F32: &str = "trap_const_f32";
@@ -79,11 +88,29 @@ pub const TRAP_NAME_I64_LOAD: &str = "trap_i64_load";
 
 pub const TRAP_NAME_MEMORY_SIZE: &str = "trap_memory_size";
 pub const TRAP_NAME_MEMORY_GROW: &str = "trap_memory_grow";
+pub const TRAP_NAME_MEMORY_FILL: &str = "trap_memory_fill";
+pub const TRAP_NAME_MEMORY_COPY: &str = "trap_memory_copy";
+pub const TRAP_NAME_MEMORY_INIT: &str = "trap_memory_init";
+pub const TRAP_NAME_DATA_DROP: &str = "trap_data_drop";
+
+pub const TRAP_NAME_MEMORY_FILL_GET_DESTINATION: &str = "trap_memory_fill_get_dst";
+pub const TRAP_NAME_MEMORY_FILL_GET_VALUE: &str = "trap_memory_fill_get_val";
+pub const TRAP_NAME_MEMORY_FILL_GET_SIZE: &str = "trap_memory_fill_get_size";
+
+pub const TRAP_NAME_MEMORY_COPY_GET_DESTINATION: &str = "trap_memory_copy_get_dst";
+pub const TRAP_NAME_MEMORY_COPY_GET_SOURCE: &str = "trap_memory_copy_get_src";
+pub const TRAP_NAME_MEMORY_COPY_GET_SIZE: &str = "trap_memory_copy_get_size";
+
+pub const TRAP_NAME_MEMORY_INIT_GET_DESTINATION: &str = "trap_memory_init_get_dst";
+pub const TRAP_NAME_MEMORY_INIT_GET_SOURCE: &str = "trap_memory_init_get_src";
+pub const TRAP_NAME_MEMORY_INIT_GET_SIZE: &str = "trap_memory_init_get_size";
 
 pub const TRAP_NAME_PRE_BLOCK: &str = "trap_block_pre";
 pub const TRAP_NAME_POST_BLOCK: &str = "trap_block_post";
 pub const TRAP_NAME_PRE_LOOP: &str = "trap_loop_pre";
 pub const TRAP_NAME_POST_LOOP: &str = "trap_loop_post";
+pub const TRAP_NAME_LOOP_ITERATION: &str = "trap_loop_iteration";
+pub const TRAP_NAME_BASIC_BLOCK_ENTER: &str = "trap_basic_block_enter";
 
 pub const TRAP_NAME_REF_FUNC: &str = "trap_ref_func";
 pub const TRAP_NAME_REF_NULL: &str = "trap_ref_null";
@@ -152,13 +179,22 @@ pub struct AnalysisInterface {
     pub pre_trap_call_indirect: Option<WasmExport>,
     pub post_trap_call: Option<WasmExport>,
     pub post_trap_call_indirect: Option<WasmExport>,
+    pub import_call_pre: Option<WasmExport>,
+    pub import_call_post: Option<WasmExport>,
+    pub import_name_byte: Option<WasmExport>,
+    pub source_location: Option<WasmExport>,
+    pub source_file_byte: Option<WasmExport>,
     pub pre_block: Option<WasmExport>,
     pub post_block: Option<WasmExport>,
     pub pre_loop: Option<WasmExport>,
     pub post_loop: Option<WasmExport>,
+    pub loop_iteration: Option<WasmExport>,
+    pub basic_block_enter: Option<WasmExport>,
     pub select: Option<WasmExport>,
     pub drop_trap: Option<WasmExport>,
     pub return_trap: Option<WasmExport>,
+    pub function_entry: Option<WasmExport>,
+    pub function_exit: Option<WasmExport>,
     pub const_i32_trap: Option<WasmExport>,
     pub const_f32_trap: Option<WasmExport>,
     pub const_i64_trap: Option<WasmExport>,
@@ -188,6 +224,19 @@ pub struct AnalysisInterface {
     pub binary_f64_f64_to_f64: Option<WasmExport>,
     pub memory_size: Option<WasmExport>,
     pub memory_grow: Option<WasmExport>,
+    pub memory_fill: Option<WasmExport>,
+    pub memory_fill_get_destination: Option<WasmExport>,
+    pub memory_fill_get_value: Option<WasmExport>,
+    pub memory_fill_get_size: Option<WasmExport>,
+    pub memory_copy: Option<WasmExport>,
+    pub memory_copy_get_destination: Option<WasmExport>,
+    pub memory_copy_get_source: Option<WasmExport>,
+    pub memory_copy_get_size: Option<WasmExport>,
+    pub memory_init: Option<WasmExport>,
+    pub memory_init_get_destination: Option<WasmExport>,
+    pub memory_init_get_source: Option<WasmExport>,
+    pub memory_init_get_size: Option<WasmExport>,
+    pub data_drop: Option<WasmExport>,
     pub local_get_i32: Option<WasmExport>,
     pub local_set_i32: Option<WasmExport>,
     pub local_tee_i32: Option<WasmExport>,
@@ -265,6 +314,250 @@ impl AnalysisInterface {
     }
 }
 
+impl AnalysisInterface {
+    /// The traps the analysis exports, including `generic_apply`
+    pub fn exports(&self) -> Vec<&WasmExport> {
+        let Self {
+            generic_interface,
+            if_then_else_trap,
+            if_then_else_post_trap,
+            if_then_trap,
+            if_then_post_trap,
+            br_trap,
+            br_if_trap,
+            pre_trap_call,
+            post_trap_call,
+            pre_trap_call_indirect,
+            post_trap_call_indirect,
+            import_call_pre,
+            import_call_post,
+            import_name_byte,
+            source_location,
+            source_file_byte,
+            br_table_trap,
+            pre_block,
+            post_block,
+            pre_loop,
+            post_loop,
+            loop_iteration,
+            basic_block_enter,
+            select,
+            drop_trap,
+            return_trap,
+            function_entry,
+            function_exit,
+            const_i32_trap,
+            const_f32_trap,
+            const_i64_trap,
+            const_f64_trap,
+            unary_i32_to_i32,
+            unary_i64_to_i32,
+            unary_i64_to_i64,
+            unary_f32_to_f32,
+            unary_f64_to_f64,
+            unary_f32_to_i32,
+            unary_f64_to_i32,
+            unary_i32_to_i64,
+            unary_f32_to_i64,
+            unary_f64_to_i64,
+            unary_i32_to_f32,
+            unary_i64_to_f32,
+            unary_f64_to_f32,
+            unary_i32_to_f64,
+            unary_i64_to_f64,
+            unary_f32_to_f64,
+            binary_i32_i32_to_i32,
+            binary_i64_i64_to_i32,
+            binary_f32_f32_to_i32,
+            binary_f64_f64_to_i32,
+            binary_i64_i64_to_i64,
+            binary_f32_f32_to_f32,
+            binary_f64_f64_to_f64,
+            memory_size,
+            memory_grow,
+            memory_fill,
+            memory_fill_get_destination,
+            memory_fill_get_value,
+            memory_fill_get_size,
+            memory_copy,
+            memory_copy_get_destination,
+            memory_copy_get_source,
+            memory_copy_get_size,
+            memory_init,
+            memory_init_get_destination,
+            memory_init_get_source,
+            memory_init_get_size,
+            data_drop,
+            local_get_i32,
+            local_set_i32,
+            local_tee_i32,
+            global_get_i32,
+            global_set_i32,
+            local_get_f32,
+            local_set_f32,
+            local_tee_f32,
+            global_get_f32,
+            global_set_f32,
+            local_get_i64,
+            local_set_i64,
+            local_tee_i64,
+            global_get_i64,
+            global_set_i64,
+            local_get_f64,
+            local_set_f64,
+            local_tee_f64,
+            global_get_f64,
+            global_set_f64,
+            f32_store,
+            f64_store,
+            i32_store,
+            i64_store,
+            f32_load,
+            f64_load,
+            i32_load,
+            i64_load,
+            ref_func,
+            ref_null,
+            ref_is_null,
+            table_get,
+            table_set,
+            table_size,
+            table_grow,
+            table_fill,
+            table_copy,
+            table_copy_get_source,
+            table_copy_get_destination,
+            table_copy_get_size,
+            table_init,
+            table_init_get_element_source,
+            table_init_get_table_destination,
+            table_init_get_size,
+            elem_drop,
+        } = self;
+        let generic_apply = generic_interface.as_ref().map(|(export, _)| export);
+        [
+            generic_apply,
+            if_then_else_trap.as_ref(),
+            if_then_else_post_trap.as_ref(),
+            if_then_trap.as_ref(),
+            if_then_post_trap.as_ref(),
+            br_trap.as_ref(),
+            br_if_trap.as_ref(),
+            pre_trap_call.as_ref(),
+            post_trap_call.as_ref(),
+            pre_trap_call_indirect.as_ref(),
+            post_trap_call_indirect.as_ref(),
+            import_call_pre.as_ref(),
+            import_call_post.as_ref(),
+            import_name_byte.as_ref(),
+            source_location.as_ref(),
+            source_file_byte.as_ref(),
+            br_table_trap.as_ref(),
+            pre_block.as_ref(),
+            post_block.as_ref(),
+            pre_loop.as_ref(),
+            post_loop.as_ref(),
+            loop_iteration.as_ref(),
+            basic_block_enter.as_ref(),
+            select.as_ref(),
+            drop_trap.as_ref(),
+            return_trap.as_ref(),
+            function_entry.as_ref(),
+            function_exit.as_ref(),
+            const_i32_trap.as_ref(),
+            const_f32_trap.as_ref(),
+            const_i64_trap.as_ref(),
+            const_f64_trap.as_ref(),
+            unary_i32_to_i32.as_ref(),
+            unary_i64_to_i32.as_ref(),
+            unary_i64_to_i64.as_ref(),
+            unary_f32_to_f32.as_ref(),
+            unary_f64_to_f64.as_ref(),
+            unary_f32_to_i32.as_ref(),
+            unary_f64_to_i32.as_ref(),
+            unary_i32_to_i64.as_ref(),
+            unary_f32_to_i64.as_ref(),
+            unary_f64_to_i64.as_ref(),
+            unary_i32_to_f32.as_ref(),
+            unary_i64_to_f32.as_ref(),
+            unary_f64_to_f32.as_ref(),
+            unary_i32_to_f64.as_ref(),
+            unary_i64_to_f64.as_ref(),
+            unary_f32_to_f64.as_ref(),
+            binary_i32_i32_to_i32.as_ref(),
+            binary_i64_i64_to_i32.as_ref(),
+            binary_f32_f32_to_i32.as_ref(),
+            binary_f64_f64_to_i32.as_ref(),
+            binary_i64_i64_to_i64.as_ref(),
+            binary_f32_f32_to_f32.as_ref(),
+            binary_f64_f64_to_f64.as_ref(),
+            memory_size.as_ref(),
+            memory_grow.as_ref(),
+            memory_fill.as_ref(),
+            memory_fill_get_destination.as_ref(),
+            memory_fill_get_value.as_ref(),
+            memory_fill_get_size.as_ref(),
+            memory_copy.as_ref(),
+            memory_copy_get_destination.as_ref(),
+            memory_copy_get_source.as_ref(),
+            memory_copy_get_size.as_ref(),
+            memory_init.as_ref(),
+            memory_init_get_destination.as_ref(),
+            memory_init_get_source.as_ref(),
+            memory_init_get_size.as_ref(),
+            data_drop.as_ref(),
+            local_get_i32.as_ref(),
+            local_set_i32.as_ref(),
+            local_tee_i32.as_ref(),
+            global_get_i32.as_ref(),
+            global_set_i32.as_ref(),
+            local_get_f32.as_ref(),
+            local_set_f32.as_ref(),
+            local_tee_f32.as_ref(),
+            global_get_f32.as_ref(),
+            global_set_f32.as_ref(),
+            local_get_i64.as_ref(),
+            local_set_i64.as_ref(),
+            local_tee_i64.as_ref(),
+            global_get_i64.as_ref(),
+            global_set_i64.as_ref(),
+            local_get_f64.as_ref(),
+            local_set_f64.as_ref(),
+            local_tee_f64.as_ref(),
+            global_get_f64.as_ref(),
+            global_set_f64.as_ref(),
+            f32_store.as_ref(),
+            f64_store.as_ref(),
+            i32_store.as_ref(),
+            i64_store.as_ref(),
+            f32_load.as_ref(),
+            f64_load.as_ref(),
+            i32_load.as_ref(),
+            i64_load.as_ref(),
+            ref_func.as_ref(),
+            ref_null.as_ref(),
+            ref_is_null.as_ref(),
+            table_get.as_ref(),
+            table_set.as_ref(),
+            table_size.as_ref(),
+            table_grow.as_ref(),
+            table_fill.as_ref(),
+            table_copy.as_ref(),
+            table_copy_get_source.as_ref(),
+            table_copy_get_destination.as_ref(),
+            table_copy_get_size.as_ref(),
+            table_init.as_ref(),
+            table_init_get_element_source.as_ref(),
+            table_init_get_table_destination.as_ref(),
+            table_init_get_size.as_ref(),
+            elem_drop.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
 macro_rules! simple_interfaces {
     ($($interface_call_name:ident $trap_name:ident : $($args:expr)* => $($results:expr)*),* $(,)?) => {
         $(
@@ -303,8 +596,15 @@ simple_interfaces! {
     interface_call_post             FUNCTION_NAME_SPECIALIZED_CALL_POST          :                                                      /*f_tgt:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
     interface_call_indirect_pre     FUNCTION_NAME_SPECIALIZED_CALL_INDIRECT_PRE  :                                 /*fn_tbl_idx:*/ I32 /*fn_tbl:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>    /*fn_tbl_idx:*/ I32 ,
     interface_call_indirect_post    FUNCTION_NAME_SPECIALIZED_CALL_INDIRECT_POST :                                                     /*fn_tbl:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_import_call_pre       TRAP_NAME_IMPORT_CALL_PRE                    : /*f_tgt:*/ I32 /*argc:*/ I32 /*resc:*/ I32 /*sigv:*/ I32 /*sigtypv:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_import_call_post      TRAP_NAME_IMPORT_CALL_POST                   : /*f_tgt:*/ I32 /*argc:*/ I32 /*resc:*/ I32 /*sigv:*/ I32 /*sigtypv:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_import_name_byte      TRAP_NAME_IMPORT_NAME_BYTE                   :                                  /*f_tgt:*/ I32 /*kind:*/ I32 /*byte:*/ I32 =>                /*void*/,
+    interface_source_location       TRAP_NAME_SOURCE_LOCATION                    :                /*file:*/ I32 /*line:*/ I32 /*column:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_source_file_byte      TRAP_NAME_SOURCE_FILE_BYTE                   :                                                   /*file:*/ I32 /*byte:*/ I32 =>                /*void*/,
     interface_select                FUNCTION_NAME_SELECT                         :                                                       /*cndt:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>           /*cont:*/ I32,
     interface_return                TRAP_NAME_RETURN                             :                                                            /*void*/ /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_function_entry        TRAP_NAME_FUNCTION_ENTRY                     :                                                            /*void*/ /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_function_exit         TRAP_NAME_FUNCTION_EXIT                      :                                                            /*void*/ /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
     interface_drop                  TRAP_NAME_DROP                               :                                                            /*void*/ /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
     interface_const_i32             TRAP_CONST_I32                               :                                                      /*const:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ I32,
     interface_const_f32             TRAP_CONST_F32                               :                                                      /*const:*/ F32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ F32,
@@ -353,20 +653,35 @@ simple_interfaces! {
     interface_global_set_i64        TRAP_NAME_GLOBAL_SET_I64                     :                                         /*value:*/ I64 /*idx:*/ I64 /*fidx*/ I64 /*iidx*/ I64 =>          /*value:*/ I64,
     interface_global_get_f64        TRAP_NAME_GLOBAL_GET_F64                     :                                         /*value:*/ F64 /*idx:*/ I64 /*fidx*/ I64 /*iidx*/ I64 =>          /*value:*/ F64,
     interface_global_set_f64        TRAP_NAME_GLOBAL_SET_F64                     :                                         /*value:*/ F64 /*idx:*/ I64 /*fidx*/ I64 /*iidx*/ I64 =>          /*value:*/ F64,
-    interface_f32_store             TRAP_NAME_F32_STORE                          : /*write_idx:*/ I32 /*val:*/ F32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
-    interface_f64_store             TRAP_NAME_F64_STORE                          : /*write_idx:*/ I32 /*val:*/ F64 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
-    interface_i32_store             TRAP_NAME_I32_STORE                          : /*write_idx:*/ I32 /*val:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
-    interface_i64_store             TRAP_NAME_I64_STORE                          : /*write_idx:*/ I32 /*val:*/ I64 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
-    interface_f32_load              TRAP_NAME_F32_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ F32,
-    interface_f64_load              TRAP_NAME_F64_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ F64,
-    interface_i32_load              TRAP_NAME_I32_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ I32,
-    interface_i64_load              TRAP_NAME_I64_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ I64,
+    interface_f32_store             TRAP_NAME_F32_STORE                          : /*write_idx:*/ I32 /*val:*/ F32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_f64_store             TRAP_NAME_F64_STORE                          : /*write_idx:*/ I32 /*val:*/ F64 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_i32_store             TRAP_NAME_I32_STORE                          : /*write_idx:*/ I32 /*val:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_i64_store             TRAP_NAME_I64_STORE                          : /*write_idx:*/ I32 /*val:*/ I64 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_f32_load              TRAP_NAME_F32_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ F32,
+    interface_f64_load              TRAP_NAME_F64_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ F64,
+    interface_i32_load              TRAP_NAME_I32_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ I32,
+    interface_i64_load              TRAP_NAME_I64_LOAD                           :               /*load_idx:*/ I32 /*offs:*/ I64 /*op:*/ SER_OPRTR_TYP /*mem_idx:*/ I64 /*align:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>            /*res:*/ I64,
     interface_memory_size           TRAP_NAME_MEMORY_SIZE                        :                                          /*size:*/ I32 /*idx:*/ I64 /*fidx*/ I64 /*iidx*/ I64 =>           /*size:*/ I32,
     interface_memory_grow           TRAP_NAME_MEMORY_GROW                        :                                        /*amount:*/ I32 /*idx:*/ I64 /*fidx*/ I64 /*iidx*/ I64 => /*delta-or-neg-1:*/ I32,
+    interface_memory_fill           TRAP_NAME_MEMORY_FILL                        :                       /*d:*/ I32 /*val:*/ I32 /*n:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_memory_fill_get_destination TRAP_NAME_MEMORY_FILL_GET_DESTINATION  :                                                                                               =>           /*dst:*/ I32,
+    interface_memory_fill_get_value TRAP_NAME_MEMORY_FILL_GET_VALUE              :                                                                                               =>           /*val:*/ I32,
+    interface_memory_fill_get_size  TRAP_NAME_MEMORY_FILL_GET_SIZE               :                                                                                               =>          /*size:*/ I32,
+    interface_memory_copy           TRAP_NAME_MEMORY_COPY                        :                         /*d:*/ I32 /*s:*/ I32 /*n:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_memory_copy_get_destination TRAP_NAME_MEMORY_COPY_GET_DESTINATION  :                                                                                               =>           /*dst:*/ I32,
+    interface_memory_copy_get_source TRAP_NAME_MEMORY_COPY_GET_SOURCE            :                                                                                               =>           /*src:*/ I32,
+    interface_memory_copy_get_size  TRAP_NAME_MEMORY_COPY_GET_SIZE               :                                                                                               =>          /*size:*/ I32,
+    interface_memory_init           TRAP_NAME_MEMORY_INIT                        :     /*d:*/ I32 /*s:*/ I32 /*n:*/ I32 /*data_idx:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
+    interface_memory_init_get_destination TRAP_NAME_MEMORY_INIT_GET_DESTINATION  :                                                                                               =>           /*dst:*/ I32,
+    interface_memory_init_get_source TRAP_NAME_MEMORY_INIT_GET_SOURCE            :                                                                                               =>           /*src:*/ I32,
+    interface_memory_init_get_size  TRAP_NAME_MEMORY_INIT_GET_SIZE               :                                                                                               =>          /*size:*/ I32,
+    interface_data_drop             TRAP_NAME_DATA_DROP                          :                                                  /*data_idx:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*/,
     interface_pre_block             TRAP_NAME_PRE_BLOCK                          :                                       /*input_c*/ I32 /*arity*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
     interface_post_block            TRAP_NAME_POST_BLOCK                         :                                                          /* void */ /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
     interface_pre_loop              TRAP_NAME_PRE_LOOP                           :                                       /*input_c*/ I32 /*arity*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
     interface_post_loop             TRAP_NAME_POST_LOOP                          :                                                          /* void */ /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
+    interface_loop_iteration        TRAP_NAME_LOOP_ITERATION                     :                                                  /*iteration*/ I64 /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
+    interface_basic_block_enter     TRAP_NAME_BASIC_BLOCK_ENTER                  :                                                   /*block_id*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void */,
     interface_ref_func              TRAP_NAME_REF_FUNC                           :                                                   /*func_idx:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>                /*void*:*/,
     interface_ref_null              TRAP_NAME_REF_NULL                           :                                                                     /*fidx*/ I64 /*iidx*/ I64 =>                /*void:*/,
     interface_ref_is_null           TRAP_NAME_REF_IS_NULL                        :                                                        /*res:*/ I32 /*fidx*/ I64 /*iidx*/ I64 =>        /*new_res:*/ I32,
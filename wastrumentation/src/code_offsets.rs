@@ -0,0 +1,74 @@
+use wasmparser::{BinaryReaderError, Parser, Payload, TypeRef};
+
+// The instruction indices of the instrumenter enumerate the operators of a
+// function body in order, including the final `end`. The byte offsets are
+// relative to the start of the contents of the code section, which is how
+// DWARF for WebAssembly addresses instructions.
+
+/// The byte offsets of the instructions of a module
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CodeOffsets {
+    imported_functions: u32,
+    /// The instruction offsets of every function body, in order of definition
+    instruction_offsets: Vec<Vec<u64>>,
+}
+
+impl CodeOffsets {
+    pub(crate) fn read(module: &[u8]) -> Result<Self, BinaryReaderError> {
+        let mut imported_functions = 0;
+        let mut code_section_start = 0;
+        let mut instruction_offsets = Vec::new();
+
+        for payload in Parser::new(0).parse_all(module) {
+            match payload? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        if let TypeRef::Func(_) = import?.ty {
+                            imported_functions += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionStart { range, .. } => code_section_start = range.start,
+                Payload::CodeSectionEntry(body) => {
+                    let offsets = body
+                        .get_operators_reader()?
+                        .into_iter_with_offsets()
+                        .map(|operator| {
+                            operator.map(|(_, offset)| {
+                                u64::try_from(offset - code_section_start).unwrap()
+                            })
+                        })
+                        .collect::<Result<Vec<u64>, BinaryReaderError>>()?;
+                    instruction_offsets.push(offsets);
+                }
+                _ => (),
+            }
+        }
+
+        Ok(Self {
+            imported_functions,
+            instruction_offsets,
+        })
+    }
+
+    /// The offset of the instruction at `instr_index` in function `funct_index`
+    pub(crate) fn offset(&self, funct_index: u32, instr_index: usize) -> Option<u64> {
+        let defined_index = funct_index.checked_sub(self.imported_functions)?;
+        self.instruction_offsets
+            .get(usize::try_from(defined_index).ok()?)?
+            .get(instr_index)
+            .copied()
+    }
+
+    /// Every instruction by its (function, instruction) index, with its offset
+    pub(crate) fn instructions(&self) -> impl Iterator<Item = ((u32, usize), u64)> + '_ {
+        (self.imported_functions..)
+            .zip(&self.instruction_offsets)
+            .flat_map(|(funct_index, offsets)| {
+                offsets
+                    .iter()
+                    .enumerate()
+                    .map(move |(instr_index, offset)| ((funct_index, instr_index), *offset))
+            })
+    }
+}
@@ -0,0 +1,113 @@
+use std::any::type_name;
+use std::fs;
+use std::path::PathBuf;
+
+use sha2::{Digest, Sha256};
+
+use super::{CompilationResult, Compiles, SourceCodeBound, WasmModule};
+
+/// What a compiled module is addressed by in a [`CompileCache`]: the source
+/// code & compiler options it is compiled from
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CacheKey(Vec<u8>);
+
+impl CacheKey {
+    /// Appends `part` to the key, length-prefixed so parts cannot run into each other
+    pub fn push(&mut self, part: impl AsRef<[u8]>) -> &mut Self {
+        let Self(key) = self;
+        let part = part.as_ref();
+        key.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        key.extend_from_slice(part);
+        self
+    }
+
+    fn digest(&self) -> String {
+        let Self(key) = self;
+        Sha256::digest(key)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// An on-disk cache of compiled modules, e.g. the analysis & instrumentation
+/// library, so unchanged sources are not compiled again on every run.
+///
+/// Every module is stored in `directory` under the SHA-256 of its key.
+/// The cache is best-effort: when it cannot be read or written, the
+/// module is compiled as if it were not there.
+#[derive(Debug, Clone)]
+pub struct CompileCache {
+    directory: PathBuf,
+}
+
+impl CompileCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.directory.join(format!("{}.wasm", key.digest()))
+    }
+
+    fn load(&self, key: &CacheKey) -> Option<WasmModule> {
+        fs::read(self.path(key)).ok()
+    }
+
+    fn store(&self, key: &CacheKey, module: &[u8]) {
+        let path = self.path(key);
+        // Written aside & renamed, so a concurrent run never reads a partial module
+        let partial = path.with_extension(format!("wasm.{}", std::process::id()));
+        let stored = fs::create_dir_all(&self.directory)
+            .and_then(|()| fs::write(&partial, module))
+            .and_then(|()| fs::rename(&partial, &path));
+        if stored.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+    }
+}
+
+/// Compiles with `compiler`, unless `compile_cache` holds the module already
+pub(crate) fn compile<Language, Compiler>(
+    compile_cache: Option<&CompileCache>,
+    compiler: &Compiler,
+    compiler_options: &Compiler::CompilerOptions,
+) -> CompilationResult<Language>
+where
+    Language: SourceCodeBound,
+    Compiler: Compiles<Language>,
+{
+    let Some((compile_cache, mut key)) = compile_cache.zip(compiler.cache_key(compiler_options))
+    else {
+        return compiler.compile(compiler_options);
+    };
+    key.push(type_name::<Language>());
+
+    if let Some(module) = compile_cache.load(&key) {
+        return Ok(module);
+    }
+    let module = compiler.compile(compiler_options)?;
+    compile_cache.store(&key, &module);
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_are_delimited() {
+        let mut split = CacheKey::default();
+        split.push("ab").push("c");
+        let mut joined = CacheKey::default();
+        joined.push("a").push("bc");
+        assert_ne!(split.digest(), joined.digest());
+
+        let mut same = CacheKey::default();
+        same.push("ab").push("c");
+        assert_eq!(split.digest(), same.digest());
+        assert_eq!(split.digest().len(), 64);
+    }
+}
@@ -2,6 +2,9 @@ use std::marker::PhantomData;
 
 use crate::wasm_constructs::Signature;
 
+pub(crate) mod cache;
+pub use cache::{CacheKey, CompileCache};
+
 #[derive(Debug, Clone)]
 pub struct Library<Language: SourceCodeBound> {
     pub content: Language::SourceCode,
@@ -56,6 +59,12 @@ where
     fn setup_compiler() -> Result<Self, Self::CompilerSetupError>;
 
     fn compile(&self, compiler_options: &Self::CompilerOptions) -> CompilationResult<Language>;
+
+    /// The source code & options the module compiled from `compiler_options`
+    /// depends on, `None` when it cannot be cached
+    fn cache_key(&self, _compiler_options: &Self::CompilerOptions) -> Option<CacheKey> {
+        None
+    }
 }
 
 pub trait DefaultCompilerOptions<Language: SourceCodeBound> {
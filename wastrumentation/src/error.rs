@@ -2,7 +2,9 @@ use thiserror::Error;
 use wasabi_wasm::{EncodeError, ParseError};
 use wasm_merge::error::Error as MergeError;
 
-use crate::{compiler::CompilationError, parse_nesting::LowToHighError};
+use crate::{
+    compiler::CompilationError, function_pattern::FunctionPattern, parse_nesting::LowToHighError,
+};
 
 #[derive(Debug, Error)]
 pub enum Error<AnalysisLangauge, InstrumentationLanguage> {
@@ -18,7 +20,11 @@ pub enum Error<AnalysisLangauge, InstrumentationLanguage> {
 
 #[derive(thiserror::Error, Debug)]
 pub enum InstrumentationError {
-    #[error("attempt to instrument an `import` function")]
+    /// Also raised for modules using proposals the parser does not support:
+    /// SIMD (`v128`), tail calls (`return_call`, `return_call_indirect`),
+    /// exception handling (`try_table`, `throw`, `throw_ref`) and threads
+    /// (shared memories, atomic instructions)
+    #[error("failed to parse the input module: {0}")]
     ParseModuleError(ParseError),
     #[error("attempt to instrument inner code of an `import` function")]
     AttemptInnerInstrumentImport,
@@ -26,4 +32,26 @@ pub enum InstrumentationError {
     LowToHighError { low_to_high_err: LowToHighError },
     #[error("Instrumentation Encode Error: {0}")]
     EncodeError(EncodeError),
+    #[error("function pattern {pattern:?} is not a valid regular expression: {error}")]
+    InvalidFunctionPattern {
+        pattern: FunctionPattern,
+        error: regex::Error,
+    },
+    #[error("function pattern {pattern:?} matches no export or name-section name")]
+    UnmatchedFunctionPattern { pattern: FunctionPattern },
+    #[error("failed to read the code section: {0}")]
+    CodeSectionParseError(wasmparser::BinaryReaderError),
+    #[error(
+        "the analysis interface has trap `{required_by}` but not `{missing}`, which it requires"
+    )]
+    MissingTrap {
+        missing: &'static str,
+        required_by: String,
+    },
+    #[error("failed to read the DWARF line table: {0}")]
+    DwarfError(gimli::Error),
+    #[error("memory {memory} has 64-bit addresses, which instrumentation does not support")]
+    UnsupportedMemory64 { memory: u32 },
+    #[error("pointcut opcode `{mnemonic}` is not the mnemonic of an instruction")]
+    UnknownMnemonic { mnemonic: String },
 }
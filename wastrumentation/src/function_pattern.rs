@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use regex::Regex;
+use wasabi_wasm::Module;
+
+use crate::error::InstrumentationError;
+
+/// Selects functions by their export names & their name in the name section
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FunctionPattern {
+    /// An exact name
+    Name(String),
+    /// A glob, `*` matches any sequence of characters & `?` any single character
+    Glob(String),
+    /// A regular expression, which has to match the whole name
+    Regex(String),
+}
+
+impl FunctionPattern {
+    fn to_regex(&self) -> Result<Regex, regex::Error> {
+        let pattern = match self {
+            FunctionPattern::Name(name) => regex::escape(name),
+            FunctionPattern::Glob(glob) => glob
+                .split('*')
+                .map(|part| {
+                    part.split('?')
+                        .map(regex::escape)
+                        .collect::<Vec<String>>()
+                        .join(".")
+                })
+                .collect::<Vec<String>>()
+                .join(".*"),
+            FunctionPattern::Regex(regex) => regex.clone(),
+        };
+        Regex::new(&format!("^(?:{pattern})$"))
+    }
+}
+
+/// Parses `glob:<glob>`, `regex:<regex>` or an exact name
+impl FromStr for FunctionPattern {
+    type Err = std::convert::Infallible;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Ok(if let Some(glob) = pattern.strip_prefix("glob:") {
+            FunctionPattern::Glob(glob.into())
+        } else if let Some(regex) = pattern.strip_prefix("regex:") {
+            FunctionPattern::Regex(regex.into())
+        } else {
+            FunctionPattern::Name(pattern.into())
+        })
+    }
+}
+
+/// The indices of the functions matching any of `patterns`
+///
+/// # Errors
+/// When a pattern is not a valid regular expression or matches no function
+pub(crate) fn resolve(
+    patterns: &[FunctionPattern],
+    module: &Module,
+) -> Result<HashSet<u32>, InstrumentationError> {
+    let mut indices = HashSet::new();
+    for pattern in patterns {
+        let regex =
+            pattern
+                .to_regex()
+                .map_err(|error| InstrumentationError::InvalidFunctionPattern {
+                    pattern: pattern.clone(),
+                    error,
+                })?;
+        let matching: Vec<u32> = module
+            .functions()
+            .filter(|(_, function)| {
+                function
+                    .export
+                    .iter()
+                    .chain(&function.name)
+                    .any(|name| regex.is_match(name))
+            })
+            .map(|(index, _)| index.to_u32())
+            .collect();
+        if matching.is_empty() {
+            return Err(InstrumentationError::UnmatchedFunctionPattern {
+                pattern: pattern.clone(),
+            });
+        }
+        indices.extend(matching);
+    }
+    Ok(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_whole_names() {
+        let glob = FunctionPattern::Glob("fib_*?".into()).to_regex().unwrap();
+        assert!(glob.is_match("fib_rec"));
+        assert!(glob.is_match("fib_1"));
+        assert!(!glob.is_match("fib_"));
+        assert!(!glob.is_match("my_fib_rec"));
+
+        let name = FunctionPattern::Name("a.b".into()).to_regex().unwrap();
+        assert!(name.is_match("a.b"));
+        assert!(!name.is_match("axb"));
+    }
+
+    #[test]
+    fn patterns_parse_by_prefix() {
+        let parse = |pattern: &str| pattern.parse::<FunctionPattern>().unwrap();
+        assert_eq!(parse("glob:f*"), FunctionPattern::Glob("f*".into()));
+        assert_eq!(parse("regex:f.+"), FunctionPattern::Regex("f.+".into()));
+        assert_eq!(parse("main"), FunctionPattern::Name("main".into()));
+    }
+}
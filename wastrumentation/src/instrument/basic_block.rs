@@ -0,0 +1,179 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use wasabi_wasm::{Function, Idx, Module, Val};
+
+use super::TransformationStrategy;
+
+// A basic block starts at the first instruction of a body and right after
+// every instruction that may transfer control: branches, calls, returns &
+// `unreachable`, as well as after every nested `block`, `loop` & `if`.
+//
+// Blocks are numbered densely across the module, in order of function index
+// and instruction index, so the ids are independent of the order in which
+// the functions are instrumented.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BasicBlock {
+    pub id: u32,
+    pub function_index: u32,
+    /// The original instructions of the block, the end is exclusive
+    pub instructions: Range<usize>,
+}
+
+pub struct Target {
+    pub trap_idx: Idx<Function>,
+    /// Block id by the (function, instruction) index of its first instruction
+    pub block_ids: HashMap<(u32, usize), u32>,
+}
+
+impl Target {
+    pub fn new(trap_idx: Idx<Function>, basic_blocks: &[BasicBlock]) -> Self {
+        let block_ids = basic_blocks
+            .iter()
+            .map(|block| ((block.function_index, block.instructions.start), block.id))
+            .collect();
+        Self {
+            trap_idx,
+            block_ids,
+        }
+    }
+}
+
+/// Computes the basic blocks of the original, untransformed `bodies`
+pub fn basic_blocks<'a>(
+    module: &Module,
+    bodies: impl IntoIterator<Item = (Idx<Function>, &'a HighLevelBody)>,
+) -> Vec<BasicBlock> {
+    let mut bodies: Vec<(Idx<Function>, &HighLevelBody)> = bodies.into_iter().collect();
+    bodies.sort_by_key(|(function_idx, _)| function_idx.to_u32());
+
+    let mut basic_blocks = Vec::new();
+    for (function_idx, HighLevelBody(body)) in bodies {
+        let mut leaders = BTreeSet::new();
+        collect_leaders(body, &mut leaders);
+        let leaders: Vec<usize> = leaders.into_iter().collect();
+        // The final `end` of the function belongs to its last block
+        let function_end = module
+            .function(function_idx)
+            .code()
+            .map_or(0, |code| code.body.len());
+
+        for (index, start) in leaders.iter().enumerate() {
+            let end = leaders.get(index + 1).copied().unwrap_or(function_end);
+            basic_blocks.push(BasicBlock {
+                id: u32::try_from(basic_blocks.len()).unwrap(),
+                function_index: function_idx.to_u32(),
+                instructions: *start..end,
+            });
+        }
+    }
+    basic_blocks
+}
+
+fn collect_leaders(body: &BodyInner, leaders: &mut BTreeSet<usize>) {
+    let mut is_leader = true;
+    for TypedHighLevelInstr {
+        instr_index, instr, ..
+    } in body
+    {
+        if is_leader {
+            leaders.insert(*instr_index);
+        }
+        is_leader = match instr {
+            Instr::Block(_, body) | Instr::Loop(_, body) => {
+                collect_leaders(body, leaders);
+                true
+            }
+            Instr::If(_, then, else_) => {
+                collect_leaders(then, leaders);
+                if let Some(else_) = else_ {
+                    collect_leaders(else_, leaders);
+                }
+                true
+            }
+            Instr::Br(_)
+            | Instr::BrIf(_)
+            | Instr::BrTable { .. }
+            | Instr::Return
+            | Instr::Call(_)
+            | Instr::CallIndirect(..)
+            | Instr::Unreachable => true,
+            _ => false,
+        };
+    }
+}
+
+impl TransformationStrategy for Target {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        _function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let transformed_body = self.transform_body(body);
+        HighLevelBody(transformed_body)
+    }
+}
+
+impl Target {
+    fn transform_body(&self, body: &BodyInner) -> BodyInner {
+        let mut result = Vec::new();
+
+        for typed_instr @ TypedHighLevelInstr {
+            funct_index,
+            instr_index,
+            instr,
+            ..
+        } in body
+        {
+            if typed_instr.is_uninstrumented() {
+                if let Some(block_id) = self.block_ids.get(&(*funct_index, *instr_index)) {
+                    // STACK: []
+                    result.push(typed_instr.instrument_with(Instr::Const(Val::I32(
+                        i32::try_from(*block_id).unwrap(),
+                    ))));
+                    // STACK: [block_id]
+                    result.extend_from_slice(&typed_instr.to_trap_call(&self.trap_idx));
+                    // STACK: []
+                }
+            }
+
+            match instr {
+                // DEFAULT TRAVERSAL
+                Instr::If(type_, then, None) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then),
+                        None,
+                    )));
+                }
+                Instr::If(type_, then, Some(else_)) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then),
+                        Some(self.transform_body(else_)),
+                    )));
+                }
+                Instr::Loop(type_, body) => {
+                    result.push(
+                        typed_instr.place_untouched(Instr::Loop(*type_, self.transform_body(body))),
+                    );
+                }
+                Instr::Block(type_, body) => {
+                    result.push(
+                        typed_instr
+                            .place_untouched(Instr::Block(*type_, self.transform_body(body))),
+                    );
+                }
+                instr => result.push(typed_instr.place_untouched(instr.clone())),
+            }
+        }
+        result
+    }
+}
@@ -1,7 +1,9 @@
 use crate::parse_nesting::{
     BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
 };
-use wasabi_wasm::{Function, Idx, Module};
+use crate::pointcut::Pointcut;
+use crate::TargetSelection;
+use wasabi_wasm::{BinaryOp, Function, FunctionType, Idx, LocalOp, Module, Val, ValType};
 
 use super::TransformationStrategy;
 
@@ -11,18 +13,287 @@ pub enum Target {
     BlockPost(Idx<Function>),
     LoopPre(Idx<Function>),
     LoopPost(Idx<Function>),
+    LoopIteration(Idx<Function>),
     Select(Idx<Function>),
 }
 
 impl TransformationStrategy for Target {
-    fn transform(&self, high_level_body: &HighLevelBody, _: &mut Module) -> HighLevelBody {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody {
         let HighLevelBody(body) = high_level_body;
-        let transformed_body = transform(body, *self);
-        HighLevelBody(transformed_body)
+        HighLevelBody(transform(body, *self, function))
     }
 }
 
-fn transform(body: &BodyInner, target: Target) -> BodyInner {
+/// A post trap unwound by [`Unwind`], for the functions & constructs its own hook selects
+pub struct UnwoundTrap {
+    pub trap_idx: Idx<Function>,
+    pub selection: Option<TargetSelection>,
+    pub pointcut: Option<Pointcut>,
+}
+
+impl UnwoundTrap {
+    fn selects_function(&self, function_index: Idx<Function>) -> bool {
+        self.selection
+            .as_ref()
+            .is_none_or(|selection| selection.contains(function_index.to_u32()))
+    }
+
+    /// Whether the construct at `typed_instr`, nested in the constructs at
+    /// instruction indices `enclosing`, is selected
+    fn selects(&self, typed_instr: &TypedHighLevelInstr, enclosing: &[usize]) -> bool {
+        self.pointcut
+            .as_ref()
+            .is_none_or(|pointcut| pointcut.selects(typed_instr, enclosing))
+    }
+}
+
+/// Calls the post traps of the constructs left by a branch or return.
+/// The post traps of `if` constructs are not unwound.
+///
+/// The branches themselves are never restricted: a pointcut of a post trap
+/// restricts the constructs it is unwound for, just like its regular calls.
+pub struct Unwind {
+    pub block_post: Option<UnwoundTrap>,
+    pub loop_post: Option<UnwoundTrap>,
+}
+
+impl TransformationStrategy for Unwind {
+    fn transform(
+        &self,
+        function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let selected = |post_trap: &Option<UnwoundTrap>| {
+            post_trap
+                .as_ref()
+                .filter(|post_trap| post_trap.selects_function(function_index))
+        };
+        let post_traps = PostTraps {
+            block_post: selected(&self.block_post),
+            loop_post: selected(&self.loop_post),
+        };
+        HighLevelBody(transform_unwinding(
+            body,
+            post_traps,
+            &mut Vec::new(),
+            function,
+        ))
+    }
+}
+
+#[derive(Copy, Clone)]
+struct PostTraps<'a> {
+    block_post: Option<&'a UnwoundTrap>,
+    loop_post: Option<&'a UnwoundTrap>,
+}
+
+/// A construct enclosing the instruction being transformed
+struct Enclosing {
+    /// Whether a branch to the construct continues it, only for original loops
+    is_loop: bool,
+    /// The instruction index of the construct, `None` when injected by instrumentation
+    instr_index: Option<usize>,
+    /// The post trap call reporting the construct is left, if it is reported at all
+    post_trap_call: Vec<TypedHighLevelInstr>,
+}
+
+impl Enclosing {
+    fn enter(typed_instr: &TypedHighLevelInstr, enclosing: &[Self], post_traps: PostTraps) -> Self {
+        let TypedHighLevelInstr {
+            funct_index,
+            instr_index,
+            type_,
+            instr,
+            ..
+        } = typed_instr;
+        let is_original = typed_instr.is_uninstrumented();
+        let post_trap = match instr {
+            Instr::Block(..) if is_original => post_traps.block_post,
+            Instr::Loop(..) if is_original => post_traps.loop_post,
+            _ => None,
+        };
+        let enclosing_indices: Vec<usize> = enclosing
+            .iter()
+            .filter_map(|construct| construct.instr_index)
+            .collect();
+        let post_trap_call = post_trap
+            .filter(|post_trap| post_trap.selects(typed_instr, &enclosing_indices))
+            .map(|post_trap| {
+                TypedHighLevelInstr::new_uninstrumented(
+                    *funct_index,
+                    *instr_index,
+                    type_.clone(),
+                    Instr::Nop,
+                )
+                .to_trap_call(&post_trap.trap_idx)
+            })
+            .unwrap_or_default();
+        Self {
+            is_loop: is_original && matches!(instr, Instr::Loop(..)),
+            instr_index: is_original.then_some(*instr_index),
+            post_trap_call,
+        }
+    }
+}
+
+/// The post trap calls, innermost first, for a jump to `label`.
+/// Branching to a loop continues it, any other construct is left.
+fn unwind_to(label: u32, enclosing: &[Enclosing]) -> Vec<TypedHighLevelInstr> {
+    let depth = usize::try_from(label).unwrap();
+    let left = match enclosing.iter().rev().nth(depth) {
+        Some(construct) if construct.is_loop => depth,
+        Some(_) => depth + 1,
+        // The function label
+        None => enclosing.len(),
+    };
+    unwind(left, enclosing)
+}
+
+/// The post trap calls, innermost first, when leaving the `left` innermost constructs
+fn unwind(left: usize, enclosing: &[Enclosing]) -> Vec<TypedHighLevelInstr> {
+    enclosing
+        .iter()
+        .rev()
+        .take(left)
+        .flat_map(|construct| construct.post_trap_call.iter().cloned())
+        .collect()
+}
+
+fn transform_unwinding(
+    body: &BodyInner,
+    post_traps: PostTraps<'_>,
+    enclosing: &mut Vec<Enclosing>,
+    function: &mut Function,
+) -> BodyInner {
+    let mut result = Vec::new();
+
+    for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
+        if typed_instr.is_uninstrumented() {
+            match instr {
+                Instr::Br(label) => {
+                    // STACK: [type_out]
+                    result.extend(unwind_to(label.to_u32(), enclosing));
+                    result.push(typed_instr.place_original(instr.clone()));
+                    continue;
+                }
+                Instr::Return => {
+                    // STACK: [type_out]
+                    result.extend(unwind(enclosing.len(), enclosing));
+                    result.push(typed_instr.place_original(instr.clone()));
+                    continue;
+                }
+                Instr::BrIf(label) => {
+                    let unwind_calls = unwind_to(label.to_u32(), enclosing);
+                    if !unwind_calls.is_empty() {
+                        let condition = function.add_fresh_local(ValType::I32);
+                        result.extend_from_slice(&[
+                            // STACK: [type_out, condition]
+                            typed_instr.instrument_with(Instr::Local(LocalOp::Tee, condition)),
+                            // STACK: [type_out, condition]
+                            typed_instr.instrument_with(Instr::If(
+                                FunctionType::new(&[], &[]),
+                                unwind_calls,
+                                None,
+                            )),
+                            // STACK: [type_out]
+                            typed_instr.instrument_with(Instr::Local(LocalOp::Get, condition)),
+                        ]);
+                    }
+                    // STACK: [type_out, condition]
+                    result.push(typed_instr.place_original(instr.clone()));
+                    continue;
+                }
+                Instr::BrTable { table, default } => {
+                    let table_index = function.add_fresh_local(ValType::I32);
+                    // STACK: [type_out, table_index]
+                    result
+                        .push(typed_instr.instrument_with(Instr::Local(LocalOp::Set, table_index)));
+                    // STACK: [type_out]
+                    for (index, label) in table.iter().enumerate() {
+                        let unwind_calls = unwind_to(label.to_u32(), enclosing);
+                        if unwind_calls.is_empty() {
+                            continue;
+                        }
+                        result.extend_from_slice(&[
+                            typed_instr.instrument_with(Instr::Local(LocalOp::Get, table_index)),
+                            typed_instr.instrument_with(Instr::Const(Val::I32(
+                                i32::try_from(index).unwrap(),
+                            ))),
+                            typed_instr.instrument_with(Instr::Binary(BinaryOp::I32Eq)),
+                            typed_instr.instrument_with(Instr::If(
+                                FunctionType::new(&[], &[]),
+                                unwind_calls,
+                                None,
+                            )),
+                        ]);
+                    }
+                    let unwind_calls = unwind_to(default.to_u32(), enclosing);
+                    if !unwind_calls.is_empty() {
+                        result.extend_from_slice(&[
+                            typed_instr.instrument_with(Instr::Local(LocalOp::Get, table_index)),
+                            typed_instr.instrument_with(Instr::Const(Val::I32(
+                                i32::try_from(table.len()).unwrap(),
+                            ))),
+                            typed_instr.instrument_with(Instr::Binary(BinaryOp::I32GeU)),
+                            typed_instr.instrument_with(Instr::If(
+                                FunctionType::new(&[], &[]),
+                                unwind_calls,
+                                None,
+                            )),
+                        ]);
+                    }
+                    // STACK: [type_out, table_index]
+                    result
+                        .push(typed_instr.instrument_with(Instr::Local(LocalOp::Get, table_index)));
+                    result.push(typed_instr.place_original(instr.clone()));
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        // DEFAULT TRAVERSAL, keeping track of the enclosing constructs
+        let mut transform_nested = |nested: &BodyInner| {
+            let construct = Enclosing::enter(typed_instr, enclosing, post_traps);
+            enclosing.push(construct);
+            let transformed = transform_unwinding(nested, post_traps, enclosing, function);
+            enclosing.pop();
+            transformed
+        };
+        match instr {
+            Instr::If(type_, then, None) => {
+                let then = transform_nested(then);
+                result.push(typed_instr.place_untouched(Instr::If(*type_, then, None)));
+            }
+            Instr::If(type_, then, Some(else_)) => {
+                let then = transform_nested(then);
+                let else_ = transform_nested(else_);
+                result.push(typed_instr.place_untouched(Instr::If(*type_, then, Some(else_))));
+            }
+            Instr::Loop(type_, body) => {
+                let body = transform_nested(body);
+                result.push(typed_instr.place_untouched(Instr::Loop(*type_, body)));
+            }
+            Instr::Block(type_, body) => {
+                let body = transform_nested(body);
+                result.push(typed_instr.place_untouched(Instr::Block(*type_, body)));
+            }
+            instr => result.push(typed_instr.place_untouched(instr.clone())),
+        }
+    }
+    result
+}
+
+fn transform(body: &BodyInner, target: Target, function: &mut Function) -> BodyInner {
     let mut result = Vec::new();
 
     for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
@@ -42,14 +313,14 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
                     // STACK: [type_in, input_c:i32, arity:i32]
                     injected_body.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // append rest of body
-                    injected_body.extend_from_slice(&transform(body, target));
+                    injected_body.extend_from_slice(&transform(body, target, function));
                     // STACK: [type_in]
                     result.push(typed_instr.place_original(Instr::Block(*type_, injected_body)));
                     continue;
                 }
                 (Target::BlockPost(trap_idx), Instr::Block(type_, body)) => {
                     // STACK: [type_in]
-                    let mut injected_body = transform(body, target);
+                    let mut injected_body = transform(body, target, function);
                     // append to rest of body
                     injected_body.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // STACK: [type_in]
@@ -70,20 +341,55 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
                     // STACK: [type_in, input_c:i32, arity:i32]
                     injected_body.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // append rest of body
-                    injected_body.extend_from_slice(&transform(body, target));
+                    injected_body.extend_from_slice(&transform(body, target, function));
                     // STACK: [type_in]
                     result.push(typed_instr.place_original(Instr::Loop(*type_, injected_body)));
                     continue;
                 }
                 (Target::LoopPost(trap_idx), Instr::Loop(type_, body)) => {
                     // STACK: [type_in]
-                    let mut injected_body = transform(body, target);
+                    let mut injected_body = transform(body, target, function);
                     // append to rest of body
                     injected_body.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // STACK: [type_in]
                     result.push(typed_instr.place_original(Instr::Loop(*type_, injected_body)));
                     continue;
                 }
+                (Target::LoopIteration(trap_idx), Instr::Loop(type_, body)) => {
+                    // The counter tracks how often the loop head was reached since entering
+                    let counter = function.add_fresh_local(ValType::I64);
+                    result.extend_from_slice(&[
+                        typed_instr.instrument_with(Instr::Const(Val::I64(0))),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Set, counter)),
+                    ]);
+                    // Only report the loop head when reached through a back-edge
+                    let mut report_iteration =
+                        vec![typed_instr.instrument_with(Instr::Local(LocalOp::Get, counter))];
+                    // STACK: [type_in, iteration:i64]
+                    report_iteration.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                    let mut injected_body = vec![
+                        // STACK: [type_in]
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Get, counter)),
+                        typed_instr.instrument_with(Instr::Const(Val::I64(0))),
+                        typed_instr.instrument_with(Instr::Binary(BinaryOp::I64Ne)),
+                        // STACK: [type_in, is_back_edge:i32]
+                        typed_instr.instrument_with(Instr::If(
+                            FunctionType::new(&[], &[]),
+                            report_iteration,
+                            None,
+                        )),
+                        // STACK: [type_in]
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Get, counter)),
+                        typed_instr.instrument_with(Instr::Const(Val::I64(1))),
+                        typed_instr.instrument_with(Instr::Binary(BinaryOp::I64Add)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Set, counter)),
+                    ];
+                    // append rest of body
+                    injected_body.extend_from_slice(&transform(body, target, function));
+                    // STACK: [type_in]
+                    result.push(typed_instr.place_original(Instr::Loop(*type_, injected_body)));
+                    continue;
+                }
                 (Target::Select(trap_idx), Instr::Select) => {
                     // STACK: [then_type_in, else_type_in, condition_i32]
                     result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
@@ -107,25 +413,27 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
             (target, Instr::If(type_, then, None)) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target),
+                    transform(then, target, function),
                     None,
                 )));
             }
             (target, Instr::If(type_, then, Some(else_))) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target),
-                    Some(transform(else_, target)),
+                    transform(then, target, function),
+                    Some(transform(else_, target, function)),
                 )))
             }
             (target, Instr::Loop(type_, body)) => {
                 result.push(
-                    typed_instr.place_untouched(Instr::Loop(*type_, transform(body, target))),
+                    typed_instr
+                        .place_untouched(Instr::Loop(*type_, transform(body, target, function))),
                 );
             }
             (target, Instr::Block(type_, body)) => {
                 result.push(
-                    typed_instr.place_untouched(Instr::Block(*type_, transform(body, target))),
+                    typed_instr
+                        .place_untouched(Instr::Block(*type_, transform(body, target, function))),
                 );
             }
             (_, instr) => result.push(typed_instr.place_untouched(instr.clone())),
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::parse_nesting::{
     BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
 };
@@ -16,7 +18,6 @@ pub enum Target {
     IfThenElsePost(Idx<Function>),
     Br(Idx<Function>),
     BrIf(Idx<Function>),
-    BrTable(Idx<Function>),
 }
 
 // Number of constant instructions in transformation
@@ -37,16 +38,22 @@ fn delta_to_instrument_instr(index_instr: &TypedHighLevelInstr) -> usize {
 // TODO: room for optimization - compute delta_to_instrument_body
 
 impl TransformationStrategy for Target {
-    fn transform(&self, high_level_body: &HighLevelBody, module: &mut Module) -> HighLevelBody {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        _function: &mut Function,
+    ) -> HighLevelBody {
         let HighLevelBody(body) = high_level_body;
-        let transformed_body = transform(body, *self, module);
+        let transformed_body = transform(body, *self);
         HighLevelBody(transformed_body)
     }
 }
 
 /// # Panics
 /// When the index cannot be cast from u32 to i32
-fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner {
+fn transform(body: &BodyInner, target: Target) -> BodyInner {
     let mut result: Vec<TypedHighLevelInstr> =
         Vec::with_capacity(body.iter().map(delta_to_instrument_instr).sum::<usize>() + body.len());
 
@@ -64,36 +71,6 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     result.push(typed_instr.place_original(instr.clone()));
                     continue;
                 }
-                (Target::BrTable(br_table_trap_idx), Instr::BrTable { table, default }) => {
-                    let reified_branch_table = BrTable {
-                        table: table.to_vec(),
-                        default: *default,
-                    }
-                    .reify();
-                    let index_to_index_and_effective_target = module.add_function(
-                        FunctionType::new(&[ValType::I32], &[ValType::I32, ValType::I32]),
-                        reified_branch_table
-                            .locals
-                            .iter()
-                            .map(|l| l.type_)
-                            .collect(),
-                        reified_branch_table.body,
-                    );
-                    result.extend_from_slice(&[
-                        // STACK: [table_target_index]
-                        typed_instr
-                            .instrument_with(Instr::Call(index_to_index_and_effective_target)),
-                        // STACK: [table_target_index, runtime_label]
-                        typed_instr.instrument_with(Instr::Const(Val::I32(
-                            i32::try_from(default.to_u32()).expect("i32->u32"),
-                        ))),
-                    ]);
-                    // STACK: [table_target_index, runtime_label, default]
-                    result.extend_from_slice(&typed_instr.to_trap_call(&br_table_trap_idx));
-                    // STACK: [table_target_index]
-                    result.push(typed_instr.place_original(instr.clone()));
-                    continue;
-                }
                 (Target::IfThen(if_then_trap_idx), Instr::If(type_, then, None)) => {
                     result.extend_from_slice(&[
                         // STACK: [type_in, condition]
@@ -109,17 +86,14 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     result.extend_from_slice(&typed_instr.to_trap_call(&if_then_trap_idx));
                     // STACK: [type_in, kontinuation]
                     result.push(
-                        typed_instr.place_original(Instr::if_then(
-                            *type_,
-                            transform(then, target, module),
-                        )),
+                        typed_instr.place_original(Instr::if_then(*type_, transform(then, target))),
                     );
                     // STACK: [type_out]
                     continue;
                 }
                 (Target::IfThenPost(if_then_post_trap_idx), Instr::If(type_, then, None)) => {
                     // STACK: [type_in, continuation]
-                    let mut injected_then_body = transform(then, target, module);
+                    let mut injected_then_body = transform(then, target);
                     // append to rest of body
                     injected_then_body
                         .extend_from_slice(&typed_instr.to_trap_call(&if_then_post_trap_idx));
@@ -162,9 +136,9 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     result.push(typed_instr.place_original(Instr::if_then_else(
                         *type_,
                         // STACK: [type_in]
-                        transform(then, target, module),
+                        transform(then, target),
                         // STACK: [type_in]
-                        transform(else_, target, module),
+                        transform(else_, target),
                     )));
                     // STACK: [type_out]
                     continue;
@@ -174,12 +148,12 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     Instr::If(type_, then, Some(else_)),
                 ) => {
                     // Inject into then-body
-                    let mut injected_then_body = transform(then, target, module);
+                    let mut injected_then_body = transform(then, target);
                     // append to rest of body
                     injected_then_body
                         .extend_from_slice(&typed_instr.to_trap_call(&if_then_else_post_trap_idx));
                     // Inject into else-body
-                    let mut injected_else_body = transform(else_, target, module);
+                    let mut injected_else_body = transform(else_, target);
                     // append to rest of body
                     injected_else_body
                         .extend_from_slice(&typed_instr.to_trap_call(&if_then_else_post_trap_idx));
@@ -217,27 +191,25 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
             (target, Instr::If(type_, then, None)) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target, module),
+                    transform(then, target),
                     None,
                 )));
             }
             (target, Instr::If(type_, then, Some(else_))) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target, module),
-                    Some(transform(else_, target, module)),
+                    transform(then, target),
+                    Some(transform(else_, target)),
                 )))
             }
             (target, Instr::Loop(type_, body)) => {
                 result.push(
-                    typed_instr
-                        .place_untouched(Instr::Loop(*type_, transform(body, target, module))),
+                    typed_instr.place_untouched(Instr::Loop(*type_, transform(body, target))),
                 );
             }
             (target, Instr::Block(type_, body)) => {
                 result.push(
-                    typed_instr
-                        .place_untouched(Instr::Block(*type_, transform(body, target, module))),
+                    typed_instr.place_untouched(Instr::Block(*type_, transform(body, target))),
                 );
             }
             (_, instr) => result.push(typed_instr.place_untouched(instr.clone())),
@@ -246,6 +218,132 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
     result
 }
 
+/// The functions that map the index of a `br_table` to its runtime label,
+/// one per site. They are generated up front, so that bodies can be
+/// transformed without extending the module.
+#[derive(Debug, Default, Clone)]
+pub struct BranchTables(HashMap<(u32, usize), Idx<Function>>);
+
+impl BranchTables {
+    /// Generates the function of every `br_table` in `bodies`
+    pub fn install<'a>(
+        module: &mut Module,
+        bodies: impl IntoIterator<Item = &'a HighLevelBody>,
+    ) -> Self {
+        let mut branch_tables = Self::default();
+        for HighLevelBody(body) in bodies {
+            branch_tables.install_nested(module, body);
+        }
+        branch_tables
+    }
+
+    fn install_nested(&mut self, module: &mut Module, body: &BodyInner) {
+        for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
+            match instr {
+                Instr::BrTable { table, default } => {
+                    let reified_branch_table = BrTable {
+                        table: table.to_vec(),
+                        default: *default,
+                    }
+                    .reify();
+                    let index_to_index_and_effective_target = module.add_function(
+                        FunctionType::new(&[ValType::I32], &[ValType::I32, ValType::I32]),
+                        reified_branch_table
+                            .locals
+                            .iter()
+                            .map(|l| l.type_)
+                            .collect(),
+                        reified_branch_table.body,
+                    );
+                    self.0
+                        .insert(site(typed_instr), index_to_index_and_effective_target);
+                }
+                Instr::Block(_, body) | Instr::Loop(_, body) => self.install_nested(module, body),
+                Instr::If(_, then, else_) => {
+                    self.install_nested(module, then);
+                    if let Some(else_) = else_ {
+                        self.install_nested(module, else_);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn site(typed_instr: &TypedHighLevelInstr) -> (u32, usize) {
+    (typed_instr.funct_index, typed_instr.instr_index)
+}
+
+/// The `br_table` target, it calls the generated function of the table to
+/// report the runtime label
+pub struct BranchTableTarget {
+    pub trap_idx: Idx<Function>,
+    pub branch_tables: BranchTables,
+}
+
+impl TransformationStrategy for BranchTableTarget {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        _function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        HighLevelBody(self.transform_body(body))
+    }
+}
+
+impl BranchTableTarget {
+    fn transform_body(&self, body: &BodyInner) -> BodyInner {
+        let mut result = Vec::with_capacity(body.len());
+
+        for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
+            match instr {
+                Instr::BrTable { default, .. } if typed_instr.is_uninstrumented() => {
+                    let BranchTables(branch_tables) = &self.branch_tables;
+                    let index_to_index_and_effective_target = branch_tables[&site(typed_instr)];
+                    result.extend_from_slice(&[
+                        // STACK: [table_target_index]
+                        typed_instr
+                            .instrument_with(Instr::Call(index_to_index_and_effective_target)),
+                        // STACK: [table_target_index, runtime_label]
+                        typed_instr.instrument_with(Instr::Const(Val::I32(
+                            i32::try_from(default.to_u32()).expect("i32->u32"),
+                        ))),
+                    ]);
+                    // STACK: [table_target_index, runtime_label, default]
+                    result.extend_from_slice(&typed_instr.to_trap_call(&self.trap_idx));
+                    // STACK: [table_target_index]
+                    result.push(typed_instr.place_original(instr.clone()));
+                }
+                // DEFAULT TRAVERSAL
+                Instr::If(type_, then, else_) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then),
+                        else_.as_ref().map(|else_| self.transform_body(else_)),
+                    )));
+                }
+                Instr::Loop(type_, body) => {
+                    result.push(
+                        typed_instr.place_untouched(Instr::Loop(*type_, self.transform_body(body))),
+                    );
+                }
+                Instr::Block(type_, body) => {
+                    result.push(
+                        typed_instr
+                            .place_untouched(Instr::Block(*type_, self.transform_body(body))),
+                    );
+                }
+                instr => result.push(typed_instr.place_untouched(instr.clone())),
+            }
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -473,8 +571,13 @@ mod tests {
         let code = function.code().unwrap();
         let high_level_body: HighLevelBody =
             (&wasm_module, function, code, &index).try_into().unwrap();
-        let transformed =
-            Target::IfThenElse(if_then_else_trap_idx).transform(&high_level_body, &mut wasm_module);
+        let mut function = wasm_module.function(index).clone();
+        let transformed = Target::IfThenElse(if_then_else_trap_idx).transform(
+            index,
+            &high_level_body,
+            &wasm_module,
+            &mut function,
+        );
 
         let LowLevelBody(low_level_body) = LowLevelBody::from(transformed);
         wasm_module.function_mut(index).code_mut().unwrap().body = low_level_body;
@@ -666,4 +769,30 @@ mod tests {
         assert_eq!(target.clone(), target);
         assert_eq!(format!("{target:?}"), "IfThen(Function 0)");
     }
+
+    #[test]
+    fn every_branch_table_gets_a_function() {
+        let wasm_bytes = wat::parse_str(
+            r#"
+            (module
+              (func $main (param i32)
+                (block (block
+                  (br_table 0 1 0 (local.get 0))))
+                (block (block
+                  (br_table 0 1 0 (local.get 0))))
+                (block (block
+                  (br_table 1 0 1 (local.get 0))))))"#,
+        )
+        .unwrap();
+        let (mut module, _, _) = Module::from_bytes(&wasm_bytes).unwrap();
+        let index = 0_usize.into();
+        let function = module.function(index);
+        let high_level_body: HighLevelBody = (&module, function, function.code().unwrap(), &index)
+            .try_into()
+            .unwrap();
+
+        let BranchTables(branch_tables) = BranchTables::install(&mut module, [&high_level_body]);
+        assert_eq!(branch_tables.len(), 3);
+        assert_eq!(module.functions.len(), 4);
+    }
 }
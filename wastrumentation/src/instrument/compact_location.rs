@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use wasabi_wasm::Instr::{Binary, Call, Const, End, Load, Local};
+use wasabi_wasm::{
+    BinaryOp, Data, Function, FunctionType, Idx, Limits, LoadOp, LocalOp, Memarg, Memory, Module,
+    Val, ValType,
+};
+
+// In compact-location mode a trap call passes the dense i32 id of its site,
+// rather than its function & instruction index as two i64 constants. Every
+// trap gets an adapter taking the site id in place of the location, which
+// looks the location up in the site table and calls the trap with it. The
+// analysis interface is left as is, so its `Location` decodes transparently.
+//
+// The site table is a data segment of a dedicated memory, holding the
+// function & instruction index of every site as two little-endian u32.
+
+/// Export name of the memory holding the site table
+pub const EXPORT_NAME_SITE_TABLE: &str = "__wastrumentation_site_table";
+
+const SITE_TABLE_ENTRY_SIZE: usize = 8;
+const PAGE_SIZE: usize = 65536;
+
+/// The dense id of every site, in order of (function, instruction) index
+pub struct SiteTable {
+    sites: BTreeMap<(u32, usize), u32>,
+}
+
+impl SiteTable {
+    pub fn new(trap_calls: &[(u32, usize, Idx<Function>)]) -> Self {
+        let mut sites: BTreeMap<(u32, usize), u32> = trap_calls
+            .iter()
+            .map(|(funct_index, instr_index, _)| ((*funct_index, *instr_index), 0))
+            .collect();
+        for (id, site) in sites.values_mut().enumerate() {
+            *site = u32::try_from(id).unwrap();
+        }
+        Self { sites }
+    }
+
+    /// The id of the site at `instr_index` in function `funct_index`
+    pub fn site(&self, funct_index: u32, instr_index: usize) -> Option<u32> {
+        self.sites.get(&(funct_index, instr_index)).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.sites.len() * SITE_TABLE_ENTRY_SIZE);
+        for (funct_index, instr_index) in self.sites.keys() {
+            bytes.extend_from_slice(&funct_index.to_le_bytes());
+            bytes.extend_from_slice(&u32::try_from(*instr_index).unwrap().to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Installs & exports the site table, generates an adapter for every trap in
+/// `trap_calls` and has the trap calls of `bodies` pass their site id instead.
+pub fn compact(
+    module: &mut Module,
+    site_table: &SiteTable,
+    trap_calls: &[(u32, usize, Idx<Function>)],
+    bodies: Vec<HighLevelBody>,
+) -> Vec<HighLevelBody> {
+    let bytes = site_table.bytes();
+    let pages = u32::try_from(bytes.len().div_ceil(PAGE_SIZE).max(1)).unwrap();
+    let table: Idx<Memory> = module.memories.len().into();
+    module.memories.push(Memory {
+        limits: Limits {
+            initial_size: pages,
+            max_size: Some(pages),
+        },
+        import: None,
+        data: vec![Data {
+            offset: vec![Const(Val::I32(0)), End],
+            bytes,
+        }],
+        export: vec![EXPORT_NAME_SITE_TABLE.to_string()],
+    });
+
+    let mut adapters = HashMap::new();
+    for (_, _, trap) in trap_calls {
+        if !adapters.contains_key(trap) {
+            let adapter = install_adapter(module, table, *trap);
+            adapters.insert(*trap, adapter);
+        }
+    }
+
+    let compaction = Compaction {
+        site_table,
+        adapters,
+    };
+    bodies
+        .into_iter()
+        .map(|HighLevelBody(body)| HighLevelBody(compaction.compact_body(&body)))
+        .collect()
+}
+
+/// Generates a function with the parameters of `trap`, the trailing location
+/// replaced by a site id, that calls `trap` with the location of the site.
+fn install_adapter(module: &mut Module, table: Idx<Memory>, trap: Idx<Function>) -> Idx<Function> {
+    let trap_type = module.function(trap).type_;
+    let (arguments, location) = trap_type.inputs().split_at(trap_type.inputs().len() - 2);
+    assert_eq!(location, [ValType::I64, ValType::I64]);
+
+    let site_local = arguments.len();
+    let mut inputs = arguments.to_vec();
+    inputs.push(ValType::I32);
+
+    let mut body: Vec<wasabi_wasm::Instr> = (0..arguments.len())
+        .map(|index| Local(LocalOp::Get, index.into()))
+        .collect();
+    // The function & instruction index, zero-extended
+    for offset in [0, 4] {
+        body.extend_from_slice(&[
+            Local(LocalOp::Get, site_local.into()),
+            Const(Val::I32(SITE_TABLE_ENTRY_SIZE.trailing_zeros() as i32)),
+            Binary(BinaryOp::I32Shl),
+            Load(
+                LoadOp::I64Load32U,
+                Memarg {
+                    alignment_exp: 2,
+                    offset,
+                    memory: table,
+                },
+            ),
+        ]);
+    }
+    body.extend_from_slice(&[Call(trap), End]);
+
+    module.add_function(
+        FunctionType::new(&inputs, trap_type.results()),
+        vec![],
+        body,
+    )
+}
+
+struct Compaction<'a> {
+    site_table: &'a SiteTable,
+    adapters: HashMap<Idx<Function>, Idx<Function>>,
+}
+
+impl Compaction<'_> {
+    fn compact_body(&self, body: &BodyInner) -> BodyInner {
+        let mut result: BodyInner = Vec::with_capacity(body.len());
+
+        for typed_instr in body {
+            match &typed_instr.instr {
+                Instr::Call(trap) if !typed_instr.is_original() => {
+                    match (self.adapters.get(trap), self.site_of(&result, typed_instr)) {
+                        (Some(adapter), Some(site)) => {
+                            // Replace the location by the site id
+                            result.truncate(result.len() - 2);
+                            result.push(
+                                typed_instr.place_untouched(Instr::Const(Val::I32(site as i32))),
+                            );
+                            result.push(typed_instr.place_untouched(Instr::Call(*adapter)));
+                        }
+                        _ => result.push(typed_instr.clone()),
+                    }
+                }
+                // DEFAULT TRAVERSAL
+                Instr::If(type_, then, else_) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.compact_body(then),
+                        else_.as_ref().map(|else_| self.compact_body(else_)),
+                    )));
+                }
+                Instr::Loop(type_, body) => {
+                    result.push(
+                        typed_instr.place_untouched(Instr::Loop(*type_, self.compact_body(body))),
+                    );
+                }
+                Instr::Block(type_, body) => {
+                    result.push(
+                        typed_instr.place_untouched(Instr::Block(*type_, self.compact_body(body))),
+                    );
+                }
+                _ => result.push(typed_instr.clone()),
+            }
+        }
+        result
+    }
+
+    /// The site id of a trap call, when the two instructions before it push its location
+    fn site_of(&self, preceding: &BodyInner, trap_call: &TypedHighLevelInstr) -> Option<u32> {
+        let [funct_index, instr_index] = preceding.last_chunk::<2>()?;
+        let expected = [
+            Val::I64(trap_call.funct_index.into()),
+            Val::I64(trap_call.instr_index.try_into().ok()?),
+        ];
+        let pushes_location =
+            [funct_index, instr_index]
+                .iter()
+                .zip(expected)
+                .all(|(typed_instr, expected)| {
+                    !typed_instr.is_original() && typed_instr.instr == Instr::Const(expected)
+                });
+        pushes_location
+            .then(|| {
+                self.site_table
+                    .site(trap_call.funct_index, trap_call.instr_index)
+            })
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sites_are_numbered_densely_in_order() {
+        let trap = Idx::from(0_usize);
+        let site_table = SiteTable::new(&[(2, 5, trap), (1, 3, trap), (2, 5, trap), (1, 0, trap)]);
+        assert_eq!(site_table.site(1, 0), Some(0));
+        assert_eq!(site_table.site(1, 3), Some(1));
+        assert_eq!(site_table.site(2, 5), Some(2));
+        assert_eq!(site_table.site(2, 6), None);
+        assert_eq!(
+            site_table.bytes(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use wasabi_wasm::Instr::{Const, End};
+use wasabi_wasm::{
+    BinaryOp, Global, GlobalOp, Idx, Limits, LoadOp, Memarg, Memory, Module, Mutability, StoreOp,
+    Val, ValType,
+};
+
+use super::basic_block::BasicBlock;
+use super::TransformationStrategy;
+
+// AFL-style edge coverage: every basic block gets a fixed location in the
+// bitmap, on entering a block the counter at `previous ^ current` is bumped
+// and `current >> 1` becomes the previous location. The shift keeps the
+// edges A -> B and B -> A apart, as well as the tight loop A -> A.
+//
+// No trap is called, the update is inlined in the instrumented module. The
+// bitmap lives in a dedicated memory, which the multi-memory merge keeps
+// apart from the memories of the input program and the analysis. It is
+// added after the memories of the input program, which are the only ones
+// the memory helpers of the analysis reach.
+
+/// One page, like the default map size of AFL
+pub const COVERAGE_MAP_SIZE: u32 = 1 << 16;
+const COVERAGE_MAP_PAGES: u32 = COVERAGE_MAP_SIZE >> 16;
+
+pub const EXPORT_NAME_COVERAGE_MAP: &str = "__wastrumentation_coverage_map";
+pub const EXPORT_NAME_COVERAGE_MAP_BASE: &str = "__wastrumentation_coverage_map_base";
+pub const EXPORT_NAME_COVERAGE_MAP_SIZE: &str = "__wastrumentation_coverage_map_size";
+
+pub struct Target {
+    map: Idx<Memory>,
+    previous_location: Idx<Global>,
+    /// Bitmap location by the (function, instruction) index of the first instruction of a block
+    block_locations: HashMap<(u32, usize), i32>,
+}
+
+impl Target {
+    /// Installs & exports the bitmap, its base address & size
+    pub fn install(module: &mut Module, basic_blocks: &[BasicBlock]) -> Self {
+        let map: Idx<Memory> = module.memories.len().into();
+        module.memories.push(Memory {
+            limits: Limits {
+                initial_size: COVERAGE_MAP_PAGES,
+                max_size: Some(COVERAGE_MAP_PAGES),
+            },
+            import: None,
+            data: vec![],
+            export: vec![EXPORT_NAME_COVERAGE_MAP.to_string()],
+        });
+
+        let mut add_exported_constant = |name: &str, value: u32| {
+            let value = i32::try_from(value).unwrap();
+            let global = module.add_global(
+                ValType::I32,
+                Mutability::Const,
+                vec![Const(Val::I32(value)), End],
+            );
+            module.globals[usize::try_from(global.to_u32()).unwrap()]
+                .export
+                .push(name.to_string());
+        };
+        add_exported_constant(EXPORT_NAME_COVERAGE_MAP_BASE, 0);
+        add_exported_constant(EXPORT_NAME_COVERAGE_MAP_SIZE, COVERAGE_MAP_SIZE);
+
+        let previous_location =
+            module.add_global(ValType::I32, Mutability::Mut, vec![Const(Val::I32(0)), End]);
+
+        let block_locations = basic_blocks
+            .iter()
+            .map(|block| {
+                (
+                    (block.function_index, block.instructions.start),
+                    location_of(block.id),
+                )
+            })
+            .collect();
+
+        Self {
+            map,
+            previous_location,
+            block_locations,
+        }
+    }
+}
+
+/// Spreads the dense block ids over the bitmap (Fibonacci hashing)
+fn location_of(block_id: u32) -> i32 {
+    let location = block_id.wrapping_mul(0x9E37_79B1) >> (32 - COVERAGE_MAP_SIZE.ilog2());
+    i32::try_from(location).unwrap()
+}
+
+impl TransformationStrategy for Target {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        _function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let transformed_body = self.transform_body(body);
+        HighLevelBody(transformed_body)
+    }
+}
+
+impl Target {
+    fn transform_body(&self, body: &BodyInner) -> BodyInner {
+        let mut result = Vec::new();
+
+        for typed_instr @ TypedHighLevelInstr {
+            funct_index,
+            instr_index,
+            instr,
+            ..
+        } in body
+        {
+            if typed_instr.is_uninstrumented() {
+                if let Some(location) = self.block_locations.get(&(*funct_index, *instr_index)) {
+                    result.extend(self.bump_edge(typed_instr, *location));
+                }
+            }
+
+            match instr {
+                // DEFAULT TRAVERSAL
+                Instr::If(type_, then, None) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then),
+                        None,
+                    )));
+                }
+                Instr::If(type_, then, Some(else_)) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then),
+                        Some(self.transform_body(else_)),
+                    )));
+                }
+                Instr::Loop(type_, body) => {
+                    result.push(
+                        typed_instr.place_untouched(Instr::Loop(*type_, self.transform_body(body))),
+                    );
+                }
+                Instr::Block(type_, body) => {
+                    result.push(
+                        typed_instr
+                            .place_untouched(Instr::Block(*type_, self.transform_body(body))),
+                    );
+                }
+                instr => result.push(typed_instr.place_untouched(instr.clone())),
+            }
+        }
+        result
+    }
+
+    fn bump_edge(&self, typed_instr: &TypedHighLevelInstr, location: i32) -> BodyInner {
+        let memarg = || Memarg {
+            alignment_exp: 0,
+            offset: 0,
+            memory: self.map,
+        };
+        let edge = || {
+            [
+                typed_instr.instrument_with(Instr::Const(Val::I32(location))),
+                typed_instr.instrument_with(Instr::Global(GlobalOp::Get, self.previous_location)),
+                typed_instr.instrument_with(Instr::Binary(BinaryOp::I32Xor)),
+            ]
+        };
+
+        let mut result = Vec::new();
+        // STACK: [] -> [edge, edge]
+        result.extend(edge());
+        result.extend(edge());
+        result.extend_from_slice(&[
+            // STACK: [edge, count]
+            typed_instr.instrument_with(Instr::Load(LoadOp::I32Load8U, memarg())),
+            typed_instr.instrument_with(Instr::Const(Val::I32(1))),
+            typed_instr.instrument_with(Instr::Binary(BinaryOp::I32Add)),
+            // STACK: [edge, count + 1]
+            typed_instr.instrument_with(Instr::Store(StoreOp::I32Store8, memarg())),
+            // STACK: []
+            typed_instr.instrument_with(Instr::Const(Val::I32(location >> 1))),
+            typed_instr.instrument_with(Instr::Global(GlobalOp::Set, self.previous_location)),
+        ]);
+        result
+    }
+}
@@ -0,0 +1,88 @@
+use wasabi_wasm::{ImportOrPresent, Module};
+
+// Instrumentation shifts every instruction of the input program, so the code
+// offsets DWARF refers to no longer hold. Rather than shipping a stale line
+// table, the DWARF sections are dropped. The name section, the producers
+// section and any other custom section do not refer to code offsets, and are
+// kept as is.
+
+/// Prefix of the name-section names of the functions instrumentation injects
+pub const INJECTED_FUNCTION_PREFIX: &str = "__wastr_";
+
+/// Keeps the custom sections that remain valid after instrumentation and names
+/// the functions injected from `first_injected` onward, e.g. `__wastr_trap_load`
+pub fn preserve(module: &mut Module, first_injected: usize) {
+    module
+        .custom_sections
+        .retain(|section| !is_debug_section(&section.name));
+    name_injected_functions(module, first_injected);
+}
+
+/// Whether a custom section holds DWARF, or refers to debug information elsewhere
+fn is_debug_section(name: &str) -> bool {
+    name.starts_with(".debug_") || name == "external_debug_info" || name == "sourceMappingURL"
+}
+
+fn name_injected_functions(module: &mut Module, first_injected: usize) {
+    for (index, function) in module.functions.iter_mut().enumerate().skip(first_injected) {
+        if function.name.is_some() {
+            continue;
+        }
+        // Traps are named after their import, injected helpers after their export
+        let name = match (&function.code, function.export.first()) {
+            (ImportOrPresent::Import(_, field), _) => field.clone(),
+            (ImportOrPresent::Present(_), Some(export)) => export.clone(),
+            (ImportOrPresent::Present(_), None) => format!("function_{index}"),
+        };
+        function.name = Some(format!("{INJECTED_FUNCTION_PREFIX}{name}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasabi_wasm::FunctionType;
+
+    #[test]
+    fn injected_functions_are_named() {
+        let wasm_bytes = wat::parse_str(
+            r#"
+            (module
+                (func $main (export "main"))
+                (@custom ".debug_line" "stale")
+                (@custom "producers" "kept"))"#,
+        )
+        .unwrap();
+        let (mut module, _, _) = Module::from_bytes(&wasm_bytes).unwrap();
+        let first_injected = module.functions.len();
+        module.add_function_import(
+            FunctionType::new(&[], &[]),
+            "analysis".to_string(),
+            "trap_load".to_string(),
+        );
+        module.add_function(FunctionType::new(&[], &[]), vec![], vec![]);
+
+        preserve(&mut module, first_injected);
+
+        let names: Vec<Option<&str>> = module
+            .functions
+            .iter()
+            .map(|function| function.name.as_deref())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("main"),
+                Some("__wastr_trap_load"),
+                Some("__wastr_function_2"),
+            ]
+        );
+        let sections: Vec<&str> = module
+            .custom_sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert!(sections.contains(&"producers"));
+        assert!(!sections.contains(&".debug_line"));
+    }
+}
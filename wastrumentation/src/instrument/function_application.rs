@@ -1,13 +1,13 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::compiler::{LibGeneratable, Library};
-use crate::stack_library::StackLibrary;
+use crate::stack_library::ModuleLinkedStackHooks;
 use wasabi_wasm::ElementMode;
 use wasabi_wasm::Function;
 use wasabi_wasm::FunctionType;
+use wasabi_wasm::GlobalOp;
 use wasabi_wasm::Idx;
 use wasabi_wasm::Instr;
-use wasabi_wasm::Instr::{Call, CallIndirect, Const, End, Local, RefFunc};
+use wasabi_wasm::Instr::{Call, CallIndirect, Const, End, Global, Local, RefFunc};
 use wasabi_wasm::Limits;
 use wasabi_wasm::LocalOp;
 use wasabi_wasm::Module;
@@ -20,6 +20,9 @@ use wasabi_wasm::{Code, Element, ImportOrPresent};
 use crate::analysis::{WasmExport, WasmImport};
 
 use super::FunctionTypeConvertible;
+use reference_handles::ReferenceHandleTables;
+
+mod reference_handles;
 
 pub const INSTRUMENTATION_STACK_MODULE: &str = "wastrumentation_stack";
 pub const INSTRUMENTATION_ANALYSIS_MODULE: &str = "WASTRUMENTATION_ANALYSIS";
@@ -29,12 +32,13 @@ pub const CODE_IS_PRESENT: i32 = 0;
 pub const CODE_IS_IMPORT: i32 = 1;
 
 #[allow(clippy::too_many_lines)]
-pub fn instrument<InstrumentationLanguage: LibGeneratable>(
+pub fn instrument(
     module: &mut Module,
     pre_instrumentation_function_indices: &HashSet<Idx<Function>>,
+    signature_import_links: &HashMap<FunctionType, ModuleLinkedStackHooks>,
     wasp_exported_generic_apply_trap: &WasmExport,
     wasp_imported_generic_apply_base: &WasmImport,
-) -> Library<InstrumentationLanguage> {
+) {
     // 0. GENERATE GENERIC APPLY
     let generic_apply_index = module.add_function_import(
         wasp_exported_generic_apply_trap.as_function_type(),
@@ -42,14 +46,9 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         wasp_exported_generic_apply_trap.name.to_string(),
     );
 
-    // 1. GENERATE IMPORTS FOR INSTRUMENTATION STACK LIBRARY
-    let StackLibrary {
-        library,
-        signature_import_links,
-    } = StackLibrary::<InstrumentationLanguage>::from_module(
-        module,
-        pre_instrumentation_function_indices,
-    );
+    // 1. GENERATE HANDLE TABLES FOR REFERENCE-TYPED VALUES
+    let reference_handle_tables =
+        ReferenceHandleTables::install(module, pre_instrumentation_function_indices);
 
     // 2. Generate function instrumentation functionality
     let apply_table_index = module.tables.len();
@@ -98,18 +97,40 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
 
         apply_instructions.push(local_get_stack_ptr());
 
-        for load_call in &stack_library_for_target.arg_load_n {
+        for (load_call, arg_type) in stack_library_for_target
+            .arg_load_n
+            .iter()
+            .zip(target_function_type.inputs())
+        {
             apply_instructions.push(local_get_stack_ptr());
             apply_instructions.push(Call(*load_call));
+            if let ValType::Ref(ref_type) = arg_type {
+                apply_instructions.push(Call(reference_handle_tables.get(*ref_type).from_handle));
+            }
+        }
+
+        apply_instructions.push(call_base);
+
+        let mut apply_locals = vec![signature_buffer_pointer_type];
+        let results = target_function_type.results();
+        if results.iter().any(|ty| matches!(ty, ValType::Ref(_))) {
+            // Only the top of the stack can be converted, so set the results aside first
+            let results_local_base = apply_type.inputs().len() + apply_locals.len();
+            apply_locals.extend_from_slice(results);
+            for index in (0..results.len()).rev() {
+                apply_instructions.push(Local(LocalOp::Set, (results_local_base + index).into()));
+            }
+            for (index, result_type) in results.iter().enumerate() {
+                apply_instructions.push(Local(LocalOp::Get, (results_local_base + index).into()));
+                if let ValType::Ref(ref_type) = result_type {
+                    apply_instructions.push(Call(reference_handle_tables.get(*ref_type).to_handle));
+                }
+            }
         }
 
-        apply_instructions.extend_from_slice(&[call_base, call_stack_store_rets, End]);
+        apply_instructions.extend_from_slice(&[call_stack_store_rets, End]);
 
-        let apply_index = module.add_function(
-            apply_type,
-            vec![signature_buffer_pointer_type],
-            apply_instructions,
-        );
+        let apply_index = module.add_function(apply_type, apply_locals, apply_instructions);
 
         let apply_table_index = apply_table_funs.len();
         apply_table_funs.push(apply_index);
@@ -120,11 +141,34 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         let stack_ptr_local = original_function.add_fresh_local(ValType::I32);
         let stack_ptr_types_local = original_function.add_fresh_local(ValType::I32);
 
+        // Remember the top of each handle stack this call allocates handles on
+        let handle_tops: Vec<(Idx<wasabi_wasm::Global>, Idx<wasabi_wasm::Local>)> =
+            [RefType::FuncRef, RefType::ExternRef]
+                .into_iter()
+                .filter(|ref_type| {
+                    target_function_type
+                        .inputs()
+                        .iter()
+                        .chain(target_function_type.results())
+                        .any(|ty| *ty == ValType::Ref(*ref_type))
+                })
+                .map(|ref_type| {
+                    let top = reference_handle_tables.get(ref_type).top;
+                    (top, original_function.add_fresh_local(ValType::I32))
+                })
+                .collect();
+
         let push_args_on_stack: Vec<Instr> = target_function_type
             .inputs()
             .iter()
             .enumerate()
-            .map(|(index, _int_type)| Local(LocalOp::Get, index.into()))
+            .flat_map(|(index, arg_type)| match arg_type {
+                ValType::Ref(ref_type) => vec![
+                    Local(LocalOp::Get, index.into()),
+                    Call(reference_handle_tables.get(*ref_type).to_handle),
+                ],
+                _ => vec![Local(LocalOp::Get, index.into())],
+            })
             .collect();
         let call_allocate_values_buffer = Call(stack_library_for_target.allocate_values_buffer);
         let local_set_values_buffer_ptr = Local(LocalOp::Set, stack_ptr_local);
@@ -152,6 +196,10 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         }));
 
         let mut instrumented_body = Vec::new();
+        for (top, saved_top_local) in &handle_tops {
+            instrumented_body.push(Global(GlobalOp::Get, *top));
+            instrumented_body.push(Local(LocalOp::Set, *saved_top_local));
+        }
         instrumented_body.extend(push_args_on_stack);
         instrumented_body.push(call_allocate_values_buffer);
         instrumented_body.push(local_set_values_buffer_ptr);
@@ -169,9 +217,22 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
             call_generic_apply,
         ]);
 
-        for load_call in &stack_library_for_target.ret_load_n {
+        for (load_call, ret_type) in stack_library_for_target
+            .ret_load_n
+            .iter()
+            .zip(target_function_type.results())
+        {
             instrumented_body.push(local_get_stack_ptr());
             instrumented_body.push(Call(*load_call));
+            if let ValType::Ref(ref_type) = ret_type {
+                instrumented_body.push(Call(reference_handle_tables.get(*ref_type).from_handle));
+            }
+        }
+
+        // Release the handles allocated for this call
+        for (top, saved_top_local) in handle_tops {
+            instrumented_body.push(Local(LocalOp::Get, saved_top_local));
+            instrumented_body.push(Global(GlobalOp::Set, top));
         }
 
         instrumented_body.push(local_get_stack_ptr());
@@ -227,6 +288,4 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         .function_mut(call_base_idx)
         .export
         .push(wasp_imported_generic_apply_base.name.to_string());
-
-    library
 }
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use wasabi_wasm::Instr::{
+    Binary, Const, Drop, Else, End, Global, If, Local, TableGet, TableGrow, TableSet, TableSize,
+};
+use wasabi_wasm::{
+    BinaryOp, Function, FunctionType, GlobalOp, Idx, Limits, LocalOp, Module, Mutability, RefType,
+    Table, Val, ValType,
+};
+
+// Reference-typed values cannot be stored in the linear memory of the stack library.
+// Instead, the instrumented module keeps them in a table per reference type and hands
+// the index into that table, an `i32` handle, to the stack library.
+//
+// Handles are allocated like a stack: an instrumented function remembers the top of
+// the handle stack before pushing its arguments and restores it once the results have
+// been retrieved, so slots are reused across calls.
+
+#[derive(Clone, Copy)]
+pub struct ReferenceHandleTable {
+    pub top: Idx<wasabi_wasm::Global>,
+    pub to_handle: Idx<Function>,
+    pub from_handle: Idx<Function>,
+}
+
+pub struct ReferenceHandleTables {
+    func_ref: Option<ReferenceHandleTable>,
+    extern_ref: Option<ReferenceHandleTable>,
+}
+
+impl ReferenceHandleTables {
+    /// Installs a handle table for each reference type used in the signatures of `functions`
+    pub fn install(module: &mut Module, functions: &HashSet<Idx<Function>>) -> Self {
+        let used_ref_types: HashSet<RefType> = functions
+            .iter()
+            .flat_map(|index| {
+                let function_type = module.function(*index).type_;
+                function_type
+                    .inputs()
+                    .iter()
+                    .chain(function_type.results())
+                    .filter_map(|val_type| match val_type {
+                        ValType::Ref(ref_type) => Some(*ref_type),
+                        _ => None,
+                    })
+                    .collect::<Vec<RefType>>()
+            })
+            .collect();
+
+        let mut install_if_used = |ref_type| {
+            used_ref_types
+                .contains(&ref_type)
+                .then(|| ReferenceHandleTable::install(module, ref_type))
+        };
+
+        Self {
+            func_ref: install_if_used(RefType::FuncRef),
+            extern_ref: install_if_used(RefType::ExternRef),
+        }
+    }
+
+    pub fn get(&self, ref_type: RefType) -> ReferenceHandleTable {
+        match ref_type {
+            RefType::FuncRef => self.func_ref,
+            RefType::ExternRef => self.extern_ref,
+        }
+        .expect("handle table installed for each reference type in an instrumented signature")
+    }
+}
+
+impl ReferenceHandleTable {
+    fn install(module: &mut Module, ref_type: RefType) -> Self {
+        let table: Idx<Table> = module.tables.len().into();
+        module.tables.push(Table {
+            limits: Limits {
+                initial_size: 0,
+                max_size: None,
+            },
+            import: None,
+            ref_type,
+            export: vec![],
+        });
+
+        let top = module.add_global(ValType::I32, Mutability::Mut, vec![Const(Val::I32(0)), End]);
+
+        let reference = || Local(LocalOp::Get, Idx::from(0_usize));
+        let handle = || Local(LocalOp::Get, Idx::from(0_usize));
+
+        let to_handle = module.add_function(
+            FunctionType::new(&[ValType::Ref(ref_type)], &[ValType::I32]),
+            vec![],
+            vec![
+                // Grow the table if every slot is in use, otherwise reuse the slot at the top
+                Global(GlobalOp::Get, top),
+                TableSize(table),
+                Binary(BinaryOp::I32Eq),
+                If(FunctionType::new(&[], &[])),
+                reference(),
+                Const(Val::I32(1)),
+                TableGrow(table),
+                Drop,
+                Else,
+                Global(GlobalOp::Get, top),
+                reference(),
+                TableSet(table),
+                End,
+                // The slot index is the handle, bump the top past it
+                Global(GlobalOp::Get, top),
+                Global(GlobalOp::Get, top),
+                Const(Val::I32(1)),
+                Binary(BinaryOp::I32Add),
+                Global(GlobalOp::Set, top),
+                End,
+            ],
+        );
+
+        let from_handle = module.add_function(
+            FunctionType::new(&[ValType::I32], &[ValType::Ref(ref_type)]),
+            vec![],
+            vec![handle(), TableGet(table), End],
+        );
+
+        Self {
+            top,
+            to_handle,
+            from_handle,
+        }
+    }
+}
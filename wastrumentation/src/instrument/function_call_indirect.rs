@@ -3,7 +3,7 @@ use crate::parse_nesting::{
 };
 use wasabi_wasm::{Function, Idx, Module, Val};
 
-use super::TransformationStrategy;
+use super::{InstructionStrategy, TransformationStrategy};
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Target {
@@ -14,11 +14,23 @@ pub enum Target {
 }
 
 impl TransformationStrategy for Target {
-    fn transform(&self, high_level_body: &HighLevelBody, _: &mut Module) -> HighLevelBody {
+    fn transform(
+        &self,
+        _: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _: &Module,
+        _: &mut Function,
+    ) -> HighLevelBody {
         high_level_body.transform_call_indirect(*self)
     }
 }
 
+impl InstructionStrategy for Target {
+    fn instrument(&self, typed_instr: &TypedHighLevelInstr, result: &mut BodyInner) -> bool {
+        instrument(typed_instr, *self, result)
+    }
+}
+
 impl HighLevelBody {
     #[must_use]
     pub fn transform_call_indirect(&self, target: Target) -> Self {
@@ -32,68 +44,8 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
     let mut result = Vec::new();
 
     for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
-        if typed_instr.is_uninstrumented() {
-            match (target, instr) {
-                (Target::Pre(call_pre_idx), Instr::Call(index)) => {
-                    // STACK: [type_in]
-                    result.push(typed_instr.instrument_with(Instr::Const(Val::I32(
-                        i32::try_from(index.to_u32()).unwrap(),
-                    ))));
-                    // STACK: [type_in, f_idx]
-                    result.extend_from_slice(&typed_instr.to_trap_call(&call_pre_idx));
-                    // STACK: [type_in]
-                    result.push(typed_instr.place_original(instr.clone()));
-                    // STACK: [type_out]
-                    continue;
-                }
-                (Target::Post(call_post_idx), Instr::Call(index)) => {
-                    result.extend_from_slice(&[
-                        // STACK: [type_in]
-                        typed_instr.place_original(instr.clone()),
-                        // STACK: [type_out]
-                        typed_instr.instrument_with(Instr::Const(Val::I32(
-                            i32::try_from(index.to_u32()).unwrap(),
-                        ))),
-                        // STACK: [type_out, f_idx]
-                    ]);
-                    result.extend_from_slice(&typed_instr.to_trap_call(&call_post_idx));
-                    // STACK: [type_out]
-                    continue;
-                }
-                (
-                    Target::IndirectPre(call_pre_idx),
-                    Instr::CallIndirect(_function_type, table_index),
-                ) => {
-                    // STACK: [type_in, table_function_index]
-                    result.push(typed_instr.instrument_with(Instr::Const(Val::I32(
-                        i32::try_from(table_index.to_u32()).unwrap(),
-                    ))));
-                    // STACK: [type_in, table_function_index, table_index]
-                    result.extend_from_slice(&typed_instr.to_trap_call(&call_pre_idx));
-                    // STACK: [type_in, table_function_index]
-                    result.push(typed_instr.place_original(instr.clone()));
-                    // STACK: [type_out]
-                    continue;
-                }
-                (
-                    Target::IndirectPost(call_post_idx),
-                    Instr::CallIndirect(_function_type, table_index),
-                ) => {
-                    result.extend_from_slice(&[
-                        // STACK: [type_in, table_function_index]
-                        typed_instr.place_original(instr.clone()),
-                        // STACK: [type_out]
-                        typed_instr.instrument_with(Instr::Const(Val::I32(
-                            i32::try_from(table_index.to_u32()).unwrap(),
-                        ))),
-                        // STACK: [type_out, table_index]
-                    ]);
-                    result.extend_from_slice(&typed_instr.to_trap_call(&call_post_idx));
-                    // STACK: [type_out]
-                    continue;
-                }
-                _ => {}
-            }
+        if typed_instr.is_uninstrumented() && instrument(typed_instr, target, &mut result) {
+            continue;
         }
 
         match (target, instr) {
@@ -128,4 +80,65 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
     result
 }
 
+/// Instruments the uninstrumented `typed_instr` into `result`, false when `target` leaves it as is
+fn instrument(typed_instr: &TypedHighLevelInstr, target: Target, result: &mut BodyInner) -> bool {
+    let instr = &typed_instr.instr;
+    match (target, instr) {
+        (Target::Pre(call_pre_idx), Instr::Call(index)) => {
+            // STACK: [type_in]
+            result.push(typed_instr.instrument_with(Instr::Const(Val::I32(
+                i32::try_from(index.to_u32()).unwrap(),
+            ))));
+            // STACK: [type_in, f_idx]
+            result.extend_from_slice(&typed_instr.to_trap_call(&call_pre_idx));
+            // STACK: [type_in]
+            result.push(typed_instr.place_original(instr.clone()));
+            // STACK: [type_out]
+            return true;
+        }
+        (Target::Post(call_post_idx), Instr::Call(index)) => {
+            result.extend_from_slice(&[
+                // STACK: [type_in]
+                typed_instr.place_original(instr.clone()),
+                // STACK: [type_out]
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    i32::try_from(index.to_u32()).unwrap(),
+                ))),
+                // STACK: [type_out, f_idx]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&call_post_idx));
+            // STACK: [type_out]
+            return true;
+        }
+        (Target::IndirectPre(call_pre_idx), Instr::CallIndirect(_function_type, table_index)) => {
+            // STACK: [type_in, table_function_index]
+            result.push(typed_instr.instrument_with(Instr::Const(Val::I32(
+                i32::try_from(table_index.to_u32()).unwrap(),
+            ))));
+            // STACK: [type_in, table_function_index, table_index]
+            result.extend_from_slice(&typed_instr.to_trap_call(&call_pre_idx));
+            // STACK: [type_in, table_function_index]
+            result.push(typed_instr.place_original(instr.clone()));
+            // STACK: [type_out]
+            return true;
+        }
+        (Target::IndirectPost(call_post_idx), Instr::CallIndirect(_function_type, table_index)) => {
+            result.extend_from_slice(&[
+                // STACK: [type_in, table_function_index]
+                typed_instr.place_original(instr.clone()),
+                // STACK: [type_out]
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    i32::try_from(table_index.to_u32()).unwrap(),
+                ))),
+                // STACK: [type_out, table_index]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&call_post_idx));
+            // STACK: [type_out]
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
 // TODO: implement tests
@@ -0,0 +1,108 @@
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use wasabi_wasm::types::InferredInstructionType;
+use wasabi_wasm::{Function, FunctionType, Idx, Module};
+
+// Unlike the other targets, the entry and exit traps are not tied to
+// an instruction of the body, but to the function itself. A body
+// can be empty, so the function index & type are passed in explicitly.
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Target {
+    Entry(Idx<Function>),
+    Exit(Idx<Function>),
+}
+
+impl Target {
+    pub fn transform_function(
+        &self,
+        function_idx: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        module: &Module,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let function = module.function(function_idx);
+        let end_index = function
+            .code()
+            .map_or(0, |code| code.body.len().saturating_sub(1));
+
+        let transformed_body = match self {
+            Target::Entry(trap_idx) => {
+                // The entry trap is reported at the first instruction
+                let anchor = anchor(function_idx, 0);
+                let mut transformed_body = anchor.to_trap_call(trap_idx);
+                transformed_body.extend_from_slice(body);
+                transformed_body
+            }
+            Target::Exit(trap_idx) => {
+                // The exit trap is reported at the final `end`
+                let anchor = anchor(function_idx, end_index);
+                // Wrapping the body in a block of the function's result type
+                // makes every branch to the function label (`br`, `br_if` and
+                // `br_table`) target the end of that block instead; the
+                // original label depths remain valid. Fall-through ends up
+                // there too, `return` is instrumented in-place.
+                let block_type = FunctionType::new(&[], function.type_.results());
+                let mut transformed_body = vec![anchor
+                    .instrument_with(Instr::Block(block_type, transform_returns(body, *trap_idx)))];
+                transformed_body.extend_from_slice(&anchor.to_trap_call(trap_idx));
+                transformed_body
+            }
+        };
+        HighLevelBody(transformed_body)
+    }
+}
+
+fn anchor(function_idx: Idx<Function>, instr_index: usize) -> TypedHighLevelInstr {
+    TypedHighLevelInstr::new_uninstrumented(
+        function_idx.to_u32(),
+        instr_index,
+        InferredInstructionType::Unreachable,
+        Instr::Nop,
+    )
+}
+
+fn transform_returns(body: &BodyInner, trap_idx: Idx<Function>) -> BodyInner {
+    let mut result = Vec::new();
+
+    for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
+        match instr {
+            Instr::Return if typed_instr.is_uninstrumented() => {
+                // STACK: [type_out]
+                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                // STACK: [type_out]
+                result.push(typed_instr.place_original(instr.clone()));
+            }
+            // DEFAULT TRAVERSAL
+            Instr::If(type_, then, None) => {
+                result.push(typed_instr.place_untouched(Instr::If(
+                    *type_,
+                    transform_returns(then, trap_idx),
+                    None,
+                )));
+            }
+            Instr::If(type_, then, Some(else_)) => {
+                result.push(typed_instr.place_untouched(Instr::If(
+                    *type_,
+                    transform_returns(then, trap_idx),
+                    Some(transform_returns(else_, trap_idx)),
+                )));
+            }
+            Instr::Loop(type_, body) => {
+                result.push(
+                    typed_instr
+                        .place_untouched(Instr::Loop(*type_, transform_returns(body, trap_idx))),
+                );
+            }
+            Instr::Block(type_, body) => {
+                result.push(
+                    typed_instr
+                        .place_untouched(Instr::Block(*type_, transform_returns(body, trap_idx))),
+                );
+            }
+            instr => result.push(typed_instr.place_untouched(instr.clone())),
+        }
+    }
+    result
+}
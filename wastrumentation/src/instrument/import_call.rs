@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use crate::stack_library::ModuleLinkedStackHooks;
+use wasabi_wasm::Instr::{BrIf, Call, Const, End, Global};
+use wasabi_wasm::{
+    Function, FunctionType, GlobalOp, Idx, ImportOrPresent, LocalOp, Module, Mutability, Val,
+    ValType,
+};
+
+use super::TransformationStrategy;
+
+// Calls to imported functions pass their arguments and results through the
+// stack library, so the analysis can inspect them as `MutDynArgs` and `MutDynResults`.
+// Only direct `call`s are reported: an imported function placed in a table and
+// reached through `call_indirect` bypasses these traps, so an audit of the imports
+// should combine them with the call indirect traps.
+//
+// The module and field names of the imports are streamed into the analysis byte by
+// byte, where they are kept in a string table. This happens once, right before the
+// first call to an import is reported.
+
+pub const NAME_KIND_MODULE: i32 = 0;
+pub const NAME_KIND_FIELD: i32 = 1;
+
+pub struct Target {
+    pub pre_trap_idx: Option<Idx<Function>>,
+    pub post_trap_idx: Option<Idx<Function>>,
+    pub register_names_idx: Idx<Function>,
+    pub callee_stack_hooks: HashMap<Idx<Function>, ModuleLinkedStackHooks>,
+}
+
+/// The imported functions whose calls can be reported. References cannot
+/// cross the stack library outside of generic apply, so imports with
+/// reference-typed signatures are left alone.
+pub fn import_call_targets(module: &Module) -> HashSet<Idx<Function>> {
+    module
+        .functions()
+        .filter(|(_, function)| function.code().is_none())
+        .filter(|(_, function)| {
+            !function
+                .type_
+                .inputs()
+                .iter()
+                .chain(function.type_.results())
+                .any(|val_type| matches!(val_type, ValType::Ref(_)))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Generates a function that streams the name of every import in
+/// `targets` into the analysis on its first invocation.
+pub fn install_name_registration(
+    module: &mut Module,
+    targets: &HashSet<Idx<Function>>,
+    name_byte_trap_idx: Idx<Function>,
+) -> Idx<Function> {
+    let registered =
+        module.add_global(ValType::I32, Mutability::Mut, vec![Const(Val::I32(0)), End]);
+
+    let mut sorted_targets: Vec<Idx<Function>> = targets.iter().copied().collect();
+    sorted_targets.sort_by_key(|target| target.to_u32());
+
+    let mut body = vec![
+        // Only register once
+        Global(GlobalOp::Get, registered),
+        BrIf(0_usize.into()),
+        Const(Val::I32(1)),
+        Global(GlobalOp::Set, registered),
+    ];
+
+    for target in sorted_targets {
+        let ImportOrPresent::Import(module_name, field_name) = &module.function(target).code else {
+            continue;
+        };
+        let function_index = i32::try_from(target.to_u32()).unwrap();
+        for (kind, name) in [
+            (NAME_KIND_MODULE, module_name),
+            (NAME_KIND_FIELD, field_name),
+        ] {
+            for byte in name.bytes() {
+                body.extend_from_slice(&[
+                    Const(Val::I32(function_index)),
+                    Const(Val::I32(kind)),
+                    Const(Val::I32(byte.into())),
+                    Call(name_byte_trap_idx),
+                ]);
+            }
+        }
+    }
+
+    body.push(End);
+    module.add_function(FunctionType::new(&[], &[]), vec![], body)
+}
+
+impl TransformationStrategy for Target {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let transformed_body = self.transform_body(body, module, function);
+        HighLevelBody(transformed_body)
+    }
+}
+
+impl Target {
+    fn transform_body(
+        &self,
+        body: &BodyInner,
+        module: &Module,
+        function: &mut Function,
+    ) -> BodyInner {
+        let mut result = Vec::new();
+
+        for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
+            match instr {
+                Instr::Call(callee) if typed_instr.is_uninstrumented() => {
+                    if let Some(stack_hooks) = self.callee_stack_hooks.get(callee) {
+                        result.extend(self.transform_call(
+                            typed_instr,
+                            *callee,
+                            stack_hooks,
+                            module,
+                            function,
+                        ));
+                    } else {
+                        result.push(typed_instr.place_original(instr.clone()));
+                    }
+                }
+                // DEFAULT TRAVERSAL
+                Instr::If(type_, then, None) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then, module, function),
+                        None,
+                    )));
+                }
+                Instr::If(type_, then, Some(else_)) => {
+                    result.push(typed_instr.place_untouched(Instr::If(
+                        *type_,
+                        self.transform_body(then, module, function),
+                        Some(self.transform_body(else_, module, function)),
+                    )));
+                }
+                Instr::Loop(type_, body) => {
+                    result.push(typed_instr.place_untouched(Instr::Loop(
+                        *type_,
+                        self.transform_body(body, module, function),
+                    )));
+                }
+                Instr::Block(type_, body) => {
+                    result.push(typed_instr.place_untouched(Instr::Block(
+                        *type_,
+                        self.transform_body(body, module, function),
+                    )));
+                }
+                instr => result.push(typed_instr.place_untouched(instr.clone())),
+            }
+        }
+        result
+    }
+
+    fn transform_call(
+        &self,
+        typed_instr: &TypedHighLevelInstr,
+        callee: Idx<Function>,
+        stack_hooks: &ModuleLinkedStackHooks,
+        module: &Module,
+        caller: &mut Function,
+    ) -> BodyInner {
+        let callee_type = module.function(callee).type_;
+        let values_buffer_local = caller.add_fresh_local(ValType::I32);
+        let types_buffer_local = caller.add_fresh_local(ValType::I32);
+        let result_locals: Vec<Idx<wasabi_wasm::Local>> = callee_type
+            .results()
+            .iter()
+            .map(|result_type| caller.add_fresh_local(*result_type))
+            .collect();
+
+        let values_buffer =
+            || typed_instr.instrument_with(Instr::Local(LocalOp::Get, values_buffer_local));
+        let trap_arguments = || {
+            vec![
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    i32::try_from(callee.to_u32()).unwrap(),
+                ))),
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    callee_type.inputs().len().try_into().unwrap(),
+                ))),
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    callee_type.results().len().try_into().unwrap(),
+                ))),
+                values_buffer(),
+                typed_instr.instrument_with(Instr::Local(LocalOp::Get, types_buffer_local)),
+            ]
+        };
+
+        let mut result = vec![
+            // STACK: [args]
+            typed_instr.instrument_with(Instr::Call(stack_hooks.allocate_values_buffer)),
+            typed_instr.instrument_with(Instr::Local(LocalOp::Set, values_buffer_local)),
+            typed_instr.instrument_with(Instr::Call(stack_hooks.allocate_types_buffer)),
+            typed_instr.instrument_with(Instr::Local(LocalOp::Set, types_buffer_local)),
+            // STACK: []
+            typed_instr.instrument_with(Instr::Call(self.register_names_idx)),
+        ];
+
+        if let Some(pre_trap_idx) = self.pre_trap_idx {
+            // STACK: [f_tgt, argc, resc, sigv, sigtypv]
+            result.extend(trap_arguments());
+            result.extend_from_slice(&typed_instr.to_trap_call(&pre_trap_idx));
+        }
+
+        // STACK: [] -> [args]
+        for arg_load in &stack_hooks.arg_load_n {
+            result.push(values_buffer());
+            result.push(typed_instr.instrument_with(Instr::Call(*arg_load)));
+        }
+
+        // STACK: [args] -> [results]
+        result.push(typed_instr.place_original(Instr::Call(callee)));
+
+        // STACK: [results] -> []
+        for result_local in result_locals.iter().rev() {
+            result.push(typed_instr.instrument_with(Instr::Local(LocalOp::Set, *result_local)));
+        }
+        result.push(values_buffer());
+        for result_local in &result_locals {
+            result.push(typed_instr.instrument_with(Instr::Local(LocalOp::Get, *result_local)));
+        }
+        result.push(typed_instr.instrument_with(Instr::Call(stack_hooks.ret_store_all)));
+
+        if let Some(post_trap_idx) = self.post_trap_idx {
+            // STACK: [f_tgt, argc, resc, sigv, sigtypv]
+            result.extend(trap_arguments());
+            result.extend_from_slice(&typed_instr.to_trap_call(&post_trap_idx));
+        }
+
+        // STACK: [] -> [results]
+        for ret_load in &stack_hooks.ret_load_n {
+            result.push(values_buffer());
+            result.push(typed_instr.instrument_with(Instr::Call(*ret_load)));
+        }
+
+        result.extend_from_slice(&[
+            values_buffer(),
+            typed_instr.instrument_with(Instr::Call(stack_hooks.free_values_buffer)),
+            typed_instr.instrument_with(Instr::Local(LocalOp::Get, types_buffer_local)),
+            typed_instr.instrument_with(Instr::Call(stack_hooks.free_types_buffer)),
+        ]);
+        result
+    }
+}
@@ -1,4 +1,4 @@
-use super::TransformationStrategy;
+use super::{InstructionStrategy, TransformationStrategy};
 use crate::parse_nesting::{
     BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
 };
@@ -7,12 +7,34 @@ use wasabi_wasm::types::InferredInstructionType;
 use wasabi_wasm::{
     Function, FunctionType, GlobalOp, Idx, LoadOp, LocalOp, Memarg, Module, StoreOp, Val, ValType,
 };
+use wasmparser::{Parser, Payload, TypeRef};
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Target {
     MemorySize(Idx<Function>),
     MemoryGrow(Idx<Function>),
 
+    // Bulk memory
+    MemoryFill {
+        trap_idx: Idx<Function>,
+        get_dst_idx: Idx<Function>,
+        get_val_idx: Idx<Function>,
+        get_size_idx: Idx<Function>,
+    },
+    MemoryCopy {
+        trap_idx: Idx<Function>,
+        get_dst_idx: Idx<Function>,
+        get_src_idx: Idx<Function>,
+        get_size_idx: Idx<Function>,
+    },
+    MemoryInit {
+        trap_idx: Idx<Function>,
+        get_dst_idx: Idx<Function>,
+        get_src_idx: Idx<Function>,
+        get_size_idx: Idx<Function>,
+    },
+    DataDrop(Idx<Function>),
+
     // Local: Get / Set / Tee
     // - I32
     LocalGetI32(Idx<Function>),
@@ -53,248 +75,31 @@ pub enum Target {
 }
 
 impl TransformationStrategy for Target {
-    fn transform(&self, high_level_body: &HighLevelBody, _: &mut Module) -> HighLevelBody {
+    fn transform(
+        &self,
+        _: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _: &Module,
+        _: &mut Function,
+    ) -> HighLevelBody {
         let HighLevelBody(body) = high_level_body;
         let transformed_body = transform(body, *self);
         HighLevelBody(transformed_body)
     }
 }
 
+impl InstructionStrategy for Target {
+    fn instrument(&self, typed_instr: &TypedHighLevelInstr, result: &mut BodyInner) -> bool {
+        instrument(typed_instr, *self, result)
+    }
+}
+
 fn transform(body: &BodyInner, target: Target) -> BodyInner {
     let mut result = Vec::new();
 
     for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
-        if typed_instr.is_uninstrumented() {
-            match (target, instr) {
-                (Target::MemorySize(trap_idx), Instr::MemorySize(idx)) => {
-                    result.extend_from_slice(&[
-                        // []                   // Perform operation
-                        typed_instr.place_original(instr.clone()),
-                        // [size:I32]           // Push memory index on stack
-                        typed_instr.instrument_with(Instr::Const(Val::I64(idx.to_u32().into()))),
-                        // [size:I32,index:I64]
-                    ]);
-                    result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                    // [size:I32]
-                    continue;
-                }
-                (Target::MemoryGrow(trap_idx), Instr::MemoryGrow(idx)) => {
-                    result.extend_from_slice(&[
-                        // [amount:I32]                   // Push memory index on stack
-                        typed_instr.instrument_with(Instr::Const(Val::I64(idx.to_u32().into()))),
-                        // [amount:I32,index:I64]
-                    ]);
-                    result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                    // [previous-size-or-neg-one:I32]
-
-                    continue;
-                }
-                _ => (),
-            }
-
-            {
-                use GlobalOp::{Get as GGet, Set as GSet};
-                use Instr::{Global, Local};
-                use LocalOp::{Get as LGet, Set as LSet, Tee};
-                use Target::*;
-                use ValType::{F32, F64, I32, I64};
-
-                match typed_instr.type_ {
-                    InferredInstructionType::Reachable(type_) => {
-                        let (params, results) = (type_.inputs(), type_.results());
-                        match (target, &typed_instr.instr, params, results) {
-                            (LocalGetI32(trap_idx), Local(LGet, get_idx), &[], &[I32])
-                            | (LocalGetF32(trap_idx), Local(LGet, get_idx), &[], &[F32])
-                            | (LocalGetI64(trap_idx), Local(LGet, get_idx), &[], &[I64])
-                            | (LocalGetF64(trap_idx), Local(LGet, get_idx), &[], &[F64]) => {
-                                result.extend_from_slice(&[
-                                    // Perform operation
-                                    typed_instr.place_original(instr.clone()),
-                                    // [gotten-value]
-                                    // Push get-index
-                                    typed_instr.instrument_with(
-                                        Instr::Const(Val::I64(i64::from((get_idx).to_u32())))
-                                            .clone(),
-                                    ),
-                                    // [gotten-value, index]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                // [local-gotten-value]
-                                continue;
-                            }
-                            (GlobalGetI32(trap_idx), Global(GGet, get_idx), &[], &[I32])
-                            | (GlobalGetF32(trap_idx), Global(GGet, get_idx), &[], &[F32])
-                            | (GlobalGetI64(trap_idx), Global(GGet, get_idx), &[], &[I64])
-                            | (GlobalGetF64(trap_idx), Global(GGet, get_idx), &[], &[F64]) => {
-                                result.extend_from_slice(&[
-                                    // Perform operation
-                                    typed_instr.place_original(instr.clone()),
-                                    // [gotten-value]
-                                    // Push get-index
-                                    typed_instr.instrument_with(
-                                        Instr::Const(Val::I64(i64::from((get_idx).to_u32())))
-                                            .clone(),
-                                    ),
-                                    // [gotten-value, index]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                // [global-gotten-value]
-                                continue;
-                            }
-                            (LocalSetI32(trap_idx), Local(LSet, set_idx), &[I32], &[])
-                            | (LocalSetF32(trap_idx), Local(LSet, set_idx), &[F32], &[])
-                            | (LocalSetI64(trap_idx), Local(LSet, set_idx), &[I64], &[])
-                            | (LocalSetF64(trap_idx), Local(LSet, set_idx), &[F64], &[]) => {
-                                result.extend_from_slice(&[
-                                    // [value-to-write]
-                                    typed_instr.instrument_with(
-                                        // Push set-index
-                                        Instr::Const(Val::I64(i64::from((set_idx).to_u32())))
-                                            .clone(),
-                                    ),
-                                    // [value-to-write, set-index]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                // [trap-determined-value-to-write]
-                                // Perform operation
-                                result.push(typed_instr.place_original(instr.clone()));
-                                continue;
-                            }
-                            (GlobalSetI32(trap_idx), Global(GSet, set_idx), &[I32], &[])
-                            | (GlobalSetF32(trap_idx), Global(GSet, set_idx), &[F32], &[])
-                            | (GlobalSetI64(trap_idx), Global(GSet, set_idx), &[I64], &[])
-                            | (GlobalSetF64(trap_idx), Global(GSet, set_idx), &[F64], &[]) => {
-                                result.extend_from_slice(&[
-                                    // [value-to-write]
-                                    typed_instr.instrument_with(
-                                        // Push set-index
-                                        Instr::Const(Val::I64(i64::from((set_idx).to_u32())))
-                                            .clone(),
-                                    ),
-                                    // [value-to-write, set-index]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                // [trap-determined-value-to-write]
-                                // Perform operation
-                                result.push(typed_instr.place_original(instr.clone()));
-                                continue;
-                            }
-                            (LocalTeeI32(trap_idx), Local(Tee, tee_idx), &[I32], &[I32])
-                            | (LocalTeeF32(trap_idx), Local(Tee, tee_idx), &[F32], &[F32])
-                            | (LocalTeeI64(trap_idx), Local(Tee, tee_idx), &[I64], &[I64])
-                            | (LocalTeeF64(trap_idx), Local(Tee, tee_idx), &[F64], &[F64]) => {
-                                result.extend_from_slice(&[
-                                    // [value-to-write]
-                                    typed_instr.instrument_with(
-                                        // Push tee-index
-                                        Instr::Const(Val::I64(i64::from((tee_idx).to_u32())))
-                                            .clone(),
-                                    ),
-                                    // [value-to-write, tee-index]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                // [trap-determined-value-to-write]
-                                result.push(typed_instr.place_original(instr.clone()));
-                                continue;
-                            }
-
-                            _ => (), // Skip
-                        };
-                    }
-                    InferredInstructionType::Unreachable => (), // Skip
-                };
-            }
-
-            macro_rules! instrument_memory_op {
-                (
-                    store:
-                    $(
-                        ($target:ident, $store_op:ident)
-                    ),*
-                ) => {
-                    match (target, instr) {
-                        $(
-                            (Target::$target(trap_idx), Instr::Store($store_op, Memarg { offset, .. })) => {
-                                result.extend_from_slice(&[
-                                    // [i32: index to write to, F32: value to write to] // FIXME: not sure if TOS index or value
-                                    typed_instr.instrument_with(Instr::Const(Val::I64((*offset).into()))),
-                                    // [i32: index to write to, F32: value to write to, U32 as I64: Offset]
-                                    typed_instr.instrument_with(Instr::Const(Val::I32($store_op.serialize()))),
-                                    // [i32: index to write to, F32: value to write to, U32 as I64: Offset, i32: serialized operation]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                continue;
-                            }
-                        ),*
-                        _ => (),
-                    }
-                };
-                (
-                    load:
-                    $(
-                        ($target:ident, $load_op:ident)
-                    ),*
-                ) => {
-                    match (target, instr) {
-                        $(
-                            (Target::$target(trap_idx), Instr::Load($load_op, Memarg { offset, .. })) => {
-                                result.extend_from_slice(&[
-                                    // [i32: index to load from]
-                                    typed_instr.instrument_with(Instr::Const(Val::I64((*offset).into()))),
-                                    // [i32: index to load from,  U32as I64: Offset]
-                                    typed_instr.instrument_with(Instr::Const(Val::I32($load_op.serialize()))),
-                                    // [i32: index to load from,  U32as I64: Offset, i32: serialized operation]
-                                ]);
-                                result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
-                                continue;
-                            }
-                        ),*
-                        _ => (),
-                    }
-                };
-
-            }
-
-            {
-                use StoreOp::{
-                    F32Store, F64Store, I32Store, I32Store16, I32Store8, I64Store, I64Store16,
-                    I64Store32, I64Store8,
-                };
-                instrument_memory_op!(
-                    store:
-                    (F32Store, F32Store),
-                    (F64Store, F64Store),
-                    (I32Store, I32Store),
-                    (I32Store, I32Store16),
-                    (I32Store, I32Store8),
-                    (I64Store, I64Store),
-                    (I64Store, I64Store16),
-                    (I64Store, I64Store32),
-                    (I64Store, I64Store8)
-                );
-
-                use LoadOp::{
-                    F32Load, F64Load, I32Load, I32Load16S, I32Load16U, I32Load8S, I32Load8U,
-                    I64Load, I64Load16S, I64Load16U, I64Load32S, I64Load32U, I64Load8S, I64Load8U,
-                };
-                instrument_memory_op!(
-                    load:
-                    (F32Load, F32Load),
-                    (F64Load, F64Load),
-                    (I32Load, I32Load),
-                    (I32Load, I32Load16S),
-                    (I32Load, I32Load16U),
-                    (I32Load, I32Load8S),
-                    (I32Load, I32Load8U),
-                    (I64Load, I64Load),
-                    (I64Load, I64Load16S),
-                    (I64Load, I64Load16U),
-                    (I64Load, I64Load32S),
-                    (I64Load, I64Load32U),
-                    (I64Load, I64Load8S),
-                    (I64Load, I64Load8U)
-                );
-            }
+        if typed_instr.is_uninstrumented() && instrument(typed_instr, target, &mut result) {
+            continue;
         }
 
         match (target, instr) {
@@ -329,6 +134,344 @@ fn transform(body: &BodyInner, target: Target) -> BodyInner {
     result
 }
 
+/// Instruments the uninstrumented `typed_instr` into `result`, false when `target` leaves it as is
+fn instrument(typed_instr: &TypedHighLevelInstr, target: Target, result: &mut BodyInner) -> bool {
+    let instr = &typed_instr.instr;
+    match (target, instr) {
+        (Target::MemorySize(trap_idx), Instr::MemorySize(idx)) => {
+            result.extend_from_slice(&[
+                // []                   // Perform operation
+                typed_instr.place_original(instr.clone()),
+                // [size:I32]           // Push memory index on stack
+                typed_instr.instrument_with(Instr::Const(Val::I64(idx.to_u32().into()))),
+                // [size:I32,index:I64]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // [size:I32]
+            return true;
+        }
+        (Target::MemoryGrow(trap_idx), Instr::MemoryGrow(idx)) => {
+            result.extend_from_slice(&[
+                // [amount:I32]                   // Push memory index on stack
+                typed_instr.instrument_with(Instr::Const(Val::I64(idx.to_u32().into()))),
+                // [amount:I32,index:I64]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // [previous-size-or-neg-one:I32]
+
+            return true;
+        }
+        // memory.fill: [d, val, n] -> []
+        (
+            Target::MemoryFill {
+                trap_idx,
+                get_dst_idx,
+                get_val_idx,
+                get_size_idx,
+            },
+            Instr::MemoryFill,
+        ) => {
+            // [d:I32, val:I32, n:I32]
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // []
+            // load d_new, val_new, n_new from analysis exports
+            result.extend_from_slice(&[
+                typed_instr.instrument_with(Instr::Call(get_dst_idx)),
+                // [d_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_val_idx)),
+                // [d_new:I32, val_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_size_idx)),
+                // [d_new:I32, val_new:I32, n_new:I32]
+            ]);
+            result.push(typed_instr.place_original(instr.clone()));
+            // []
+            return true;
+        }
+        // memory.copy: [d, s, n] -> []
+        (
+            Target::MemoryCopy {
+                trap_idx,
+                get_dst_idx,
+                get_src_idx,
+                get_size_idx,
+            },
+            Instr::MemoryCopy,
+        ) => {
+            // [d:I32, s:I32, n:I32]
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // []
+            // load d_new, s_new, n_new from analysis exports
+            result.extend_from_slice(&[
+                typed_instr.instrument_with(Instr::Call(get_dst_idx)),
+                // [d_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_src_idx)),
+                // [d_new:I32, s_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_size_idx)),
+                // [d_new:I32, s_new:I32, n_new:I32]
+            ]);
+            result.push(typed_instr.place_original(instr.clone()));
+            // []
+            return true;
+        }
+        // memory.init x: [d, s, n] -> []
+        (
+            Target::MemoryInit {
+                trap_idx,
+                get_dst_idx,
+                get_src_idx,
+                get_size_idx,
+            },
+            Instr::MemoryInit(data_idx),
+        ) => {
+            result.extend_from_slice(&[
+                // [d:I32, s:I32, n:I32]
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    i32::try_from(data_idx.to_u32()).unwrap(),
+                ))),
+                // [d:I32, s:I32, n:I32, data_idx:I32]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // []
+            // load d_new, s_new, n_new from analysis exports
+            result.extend_from_slice(&[
+                typed_instr.instrument_with(Instr::Call(get_dst_idx)),
+                // [d_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_src_idx)),
+                // [d_new:I32, s_new:I32]
+                typed_instr.instrument_with(Instr::Call(get_size_idx)),
+                // [d_new:I32, s_new:I32, n_new:I32]
+            ]);
+            result.push(typed_instr.place_original(instr.clone()));
+            // []
+            return true;
+        }
+        // data.drop x: [] -> []
+        (Target::DataDrop(trap_idx), Instr::DataDrop(data_idx)) => {
+            result.extend_from_slice(&[
+                // []
+                typed_instr.instrument_with(Instr::Const(Val::I32(
+                    i32::try_from(data_idx.to_u32()).unwrap(),
+                ))),
+                // [data_idx:I32]
+            ]);
+            result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+            // []
+            result.push(typed_instr.place_original(instr.clone()));
+            // []
+            return true;
+        }
+        _ => (),
+    }
+
+    {
+        use GlobalOp::{Get as GGet, Set as GSet};
+        use Instr::{Global, Local};
+        use LocalOp::{Get as LGet, Set as LSet, Tee};
+        use Target::*;
+        use ValType::{F32, F64, I32, I64};
+
+        match typed_instr.type_ {
+            InferredInstructionType::Reachable(type_) => {
+                let (params, results) = (type_.inputs(), type_.results());
+                match (target, &typed_instr.instr, params, results) {
+                    (LocalGetI32(trap_idx), Local(LGet, get_idx), &[], &[I32])
+                    | (LocalGetF32(trap_idx), Local(LGet, get_idx), &[], &[F32])
+                    | (LocalGetI64(trap_idx), Local(LGet, get_idx), &[], &[I64])
+                    | (LocalGetF64(trap_idx), Local(LGet, get_idx), &[], &[F64]) => {
+                        result.extend_from_slice(&[
+                            // Perform operation
+                            typed_instr.place_original(instr.clone()),
+                            // [gotten-value]
+                            // Push get-index
+                            typed_instr.instrument_with(
+                                Instr::Const(Val::I64(i64::from((get_idx).to_u32()))).clone(),
+                            ),
+                            // [gotten-value, index]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        // [local-gotten-value]
+                        return true;
+                    }
+                    (GlobalGetI32(trap_idx), Global(GGet, get_idx), &[], &[I32])
+                    | (GlobalGetF32(trap_idx), Global(GGet, get_idx), &[], &[F32])
+                    | (GlobalGetI64(trap_idx), Global(GGet, get_idx), &[], &[I64])
+                    | (GlobalGetF64(trap_idx), Global(GGet, get_idx), &[], &[F64]) => {
+                        result.extend_from_slice(&[
+                            // Perform operation
+                            typed_instr.place_original(instr.clone()),
+                            // [gotten-value]
+                            // Push get-index
+                            typed_instr.instrument_with(
+                                Instr::Const(Val::I64(i64::from((get_idx).to_u32()))).clone(),
+                            ),
+                            // [gotten-value, index]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        // [global-gotten-value]
+                        return true;
+                    }
+                    (LocalSetI32(trap_idx), Local(LSet, set_idx), &[I32], &[])
+                    | (LocalSetF32(trap_idx), Local(LSet, set_idx), &[F32], &[])
+                    | (LocalSetI64(trap_idx), Local(LSet, set_idx), &[I64], &[])
+                    | (LocalSetF64(trap_idx), Local(LSet, set_idx), &[F64], &[]) => {
+                        result.extend_from_slice(&[
+                            // [value-to-write]
+                            typed_instr.instrument_with(
+                                // Push set-index
+                                Instr::Const(Val::I64(i64::from((set_idx).to_u32()))).clone(),
+                            ),
+                            // [value-to-write, set-index]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        // [trap-determined-value-to-write]
+                        // Perform operation
+                        result.push(typed_instr.place_original(instr.clone()));
+                        return true;
+                    }
+                    (GlobalSetI32(trap_idx), Global(GSet, set_idx), &[I32], &[])
+                    | (GlobalSetF32(trap_idx), Global(GSet, set_idx), &[F32], &[])
+                    | (GlobalSetI64(trap_idx), Global(GSet, set_idx), &[I64], &[])
+                    | (GlobalSetF64(trap_idx), Global(GSet, set_idx), &[F64], &[]) => {
+                        result.extend_from_slice(&[
+                            // [value-to-write]
+                            typed_instr.instrument_with(
+                                // Push set-index
+                                Instr::Const(Val::I64(i64::from((set_idx).to_u32()))).clone(),
+                            ),
+                            // [value-to-write, set-index]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        // [trap-determined-value-to-write]
+                        // Perform operation
+                        result.push(typed_instr.place_original(instr.clone()));
+                        return true;
+                    }
+                    (LocalTeeI32(trap_idx), Local(Tee, tee_idx), &[I32], &[I32])
+                    | (LocalTeeF32(trap_idx), Local(Tee, tee_idx), &[F32], &[F32])
+                    | (LocalTeeI64(trap_idx), Local(Tee, tee_idx), &[I64], &[I64])
+                    | (LocalTeeF64(trap_idx), Local(Tee, tee_idx), &[F64], &[F64]) => {
+                        result.extend_from_slice(&[
+                            // [value-to-write]
+                            typed_instr.instrument_with(
+                                // Push tee-index
+                                Instr::Const(Val::I64(i64::from((tee_idx).to_u32()))).clone(),
+                            ),
+                            // [value-to-write, tee-index]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        // [trap-determined-value-to-write]
+                        result.push(typed_instr.place_original(instr.clone()));
+                        return true;
+                    }
+
+                    _ => (), // Skip
+                };
+            }
+            InferredInstructionType::Unreachable => (), // Skip
+        };
+    }
+
+    macro_rules! instrument_memory_op {
+        (
+            store:
+            $(
+                ($target:ident, $store_op:ident)
+            ),*
+        ) => {
+            match (target, instr) {
+                $(
+                    (Target::$target(trap_idx), Instr::Store($store_op, Memarg { alignment_exp, offset, memory })) => {
+                        result.extend_from_slice(&[
+                            // [i32: index to write to, F32: value to write to] // FIXME: not sure if TOS index or value
+                            typed_instr.instrument_with(Instr::Const(Val::I64((*offset).into()))),
+                            // [i32: index to write to, F32: value to write to, U32 as I64: Offset]
+                            typed_instr.instrument_with(Instr::Const(Val::I32($store_op.serialize()))),
+                            // [i32: index to write to, F32: value to write to, U32 as I64: Offset, i32: serialized operation]
+                            typed_instr.instrument_with(Instr::Const(Val::I64(memory.to_u32().into()))),
+                            // [..., i32: serialized operation, U32 as I64: memory index]
+                            typed_instr.instrument_with(Instr::Const(Val::I32((*alignment_exp).into()))),
+                            // [..., i32: serialized operation, U32 as I64: memory index, U8 as I32: alignment exponent]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        return true;
+                    }
+                ),*
+                _ => (),
+            }
+        };
+        (
+            load:
+            $(
+                ($target:ident, $load_op:ident)
+            ),*
+        ) => {
+            match (target, instr) {
+                $(
+                    (Target::$target(trap_idx), Instr::Load($load_op, Memarg { alignment_exp, offset, memory })) => {
+                        result.extend_from_slice(&[
+                            // [i32: index to load from]
+                            typed_instr.instrument_with(Instr::Const(Val::I64((*offset).into()))),
+                            // [i32: index to load from,  U32as I64: Offset]
+                            typed_instr.instrument_with(Instr::Const(Val::I32($load_op.serialize()))),
+                            // [i32: index to load from,  U32as I64: Offset, i32: serialized operation]
+                            typed_instr.instrument_with(Instr::Const(Val::I64(memory.to_u32().into()))),
+                            // [..., i32: serialized operation, U32 as I64: memory index]
+                            typed_instr.instrument_with(Instr::Const(Val::I32((*alignment_exp).into()))),
+                            // [..., i32: serialized operation, U32 as I64: memory index, U8 as I32: alignment exponent]
+                        ]);
+                        result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
+                        return true;
+                    }
+                ),*
+                _ => (),
+            }
+        };
+
+    }
+
+    {
+        use StoreOp::{
+            F32Store, F64Store, I32Store, I32Store16, I32Store8, I64Store, I64Store16, I64Store32,
+            I64Store8,
+        };
+        instrument_memory_op!(
+            store:
+            (F32Store, F32Store),
+            (F64Store, F64Store),
+            (I32Store, I32Store),
+            (I32Store, I32Store16),
+            (I32Store, I32Store8),
+            (I64Store, I64Store),
+            (I64Store, I64Store16),
+            (I64Store, I64Store32),
+            (I64Store, I64Store8)
+        );
+
+        use LoadOp::{
+            F32Load, F64Load, I32Load, I32Load16S, I32Load16U, I32Load8S, I32Load8U, I64Load,
+            I64Load16S, I64Load16U, I64Load32S, I64Load32U, I64Load8S, I64Load8U,
+        };
+        instrument_memory_op!(
+            load:
+            (F32Load, F32Load),
+            (F64Load, F64Load),
+            (I32Load, I32Load),
+            (I32Load, I32Load16S),
+            (I32Load, I32Load16U),
+            (I32Load, I32Load8S),
+            (I32Load, I32Load8U),
+            (I64Load, I64Load),
+            (I64Load, I64Load16S),
+            (I64Load, I64Load16U),
+            (I64Load, I64Load32S),
+            (I64Load, I64Load32U),
+            (I64Load, I64Load8S),
+            (I64Load, I64Load8U)
+        );
+    }
+    false
+}
+
 trait Serialize {
     fn serialize(&self) -> i32;
 }
@@ -370,7 +513,97 @@ impl Serialize for LoadOp {
     }
 }
 
-pub fn inject_memory_loads(module: &mut Module) {
+/// The index of the first memory of `module` with 64-bit addresses, if any.
+/// The memory traps & helpers pass addresses, pages and deltas as i32, so
+/// such a memory cannot be instrumented. A module that cannot be read is
+/// left to the parser to report.
+pub fn first_memory64(module: &[u8]) -> Option<u32> {
+    let mut memories = 0;
+    for payload in Parser::new(0).parse_all(module) {
+        let memory_types: Vec<_> = match payload.ok()? {
+            Payload::ImportSection(imports) => imports
+                .into_iter()
+                .filter_map(|import| match import.ok()?.ty {
+                    TypeRef::Memory(memory_type) => Some(memory_type),
+                    _ => None,
+                })
+                .collect(),
+            Payload::MemorySection(memory_types) => {
+                memory_types.into_iter().filter_map(Result::ok).collect()
+            }
+            _ => continue,
+        };
+        for memory_type in memory_types {
+            if memory_type.memory64 {
+                return Some(memories);
+            }
+            memories += 1;
+        }
+    }
+    None
+}
+
+/// The memory index and alignment of a memory instruction are immediates,
+/// so an injected helper that receives them as arguments dispatches to a
+/// statically encoded instruction for each of the possible `cases`.
+///
+/// Each case is a pair of `(memory index, alignment exponent)` and the
+/// `perform` closure yields the instructions to execute for that case.
+/// Without an `alignment_local`, only the memory index is matched on.
+/// When no case matches, the helper traps.
+fn dispatch_on_memarg(
+    cases: Vec<(u32, u8)>,
+    memory_local: u32,
+    alignment_local: Option<u32>,
+    block_type: FunctionType,
+    perform: impl Fn(u32, u8) -> Vec<wasabi_wasm::Instr>,
+) -> Vec<wasabi_wasm::Instr> {
+    use wasabi_wasm::BinaryOp::{I32And, I32Eq};
+    use wasabi_wasm::Instr::{Binary, Const, Else, End, If, Local, Unreachable};
+
+    let mut body = vec![];
+    let case_count = cases.len();
+    for (memory, alignment_exp) in cases {
+        body.extend_from_slice(&[
+            // []
+            Local(LocalOp::Get, memory_local.into()),
+            Const(Val::I32(memory.try_into().unwrap())),
+            Binary(I32Eq),
+            // [memory-matches:i32]
+        ]);
+        if let Some(alignment_local) = alignment_local {
+            body.extend_from_slice(&[
+                // [memory-matches:i32]
+                Local(LocalOp::Get, alignment_local.into()),
+                Const(Val::I32(alignment_exp.into())),
+                Binary(I32Eq),
+                // [memory-matches:i32, alignment-matches:i32]
+                Binary(I32And),
+                // [case-matches:i32]
+            ]);
+        }
+        body.push(If(block_type));
+        body.extend(perform(memory, alignment_exp));
+        body.push(Else);
+    }
+    body.push(Unreachable);
+    body.extend(std::iter::repeat_n(End, case_count));
+    body
+}
+
+/// All `(memory index, alignment exponent)` pairs an access with natural
+/// alignment `natural_alignment_exp` can be encoded with, in the first
+/// `input_memories` memories.
+fn memarg_cases(input_memories: usize, natural_alignment_exp: u8) -> Vec<(u32, u8)> {
+    (0..input_memories)
+        .flat_map(|memory| {
+            let memory = u32::try_from(memory).unwrap();
+            (0..=natural_alignment_exp).map(move |alignment_exp| (memory, alignment_exp))
+        })
+        .collect()
+}
+
+pub fn inject_memory_loads(module: &mut Module, input_memories: usize) {
     use wasabi_wasm::Instr::{Binary, Const, End, Load, Local};
     use wasabi_wasm::LoadOp::{F32Load, F64Load, I32Load, I64Load}; // Regular
     use wasabi_wasm::LoadOp::{I32Load16S, I32Load16U, I32Load8S, I32Load8U}; // I32 Specialized
@@ -384,8 +617,6 @@ pub fn inject_memory_loads(module: &mut Module) {
         I64Load8U,
     };
 
-    assert!(module.memories.len() <= 1);
-
     for (name, load_op, load_type) in [
         // Regular
         ("instrumented_base_load_i32", I32Load, ValType::I32),
@@ -405,8 +636,12 @@ pub fn inject_memory_loads(module: &mut Module) {
         ("instrumented_base_load_i64_32S", I64Load32S, ValType::I64),
         ("instrumented_base_load_i64_32U", I64Load32U, ValType::I64),
     ] {
-        let function_type = FunctionType::new(&[ValType::I32, ValType::I32], &[load_type]);
-        let body = if module.memories.is_empty() {
+        // ptr, offset, memory index, alignment exponent
+        let function_type = FunctionType::new(
+            &[ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+            &[load_type],
+        );
+        let body = if input_memories == 0 {
             vec![
                 // []
                 Const(load_type.zero()),
@@ -414,18 +649,35 @@ pub fn inject_memory_loads(module: &mut Module) {
                 End,
             ]
         } else {
-            vec![
-                // []
-                Local(LocalOp::Get, 0_u32.into()),
-                // [ptr]
-                Local(LocalOp::Get, 1_u32.into()),
-                // [ptr, offset]
-                Binary(wasabi_wasm::BinaryOp::I32Add),
-                // [ptr + offset]
-                Load(load_op, Memarg::default(load_op)),
-                // [value]
-                End,
-            ]
+            let natural_alignment_exp = Memarg::default(load_op).alignment_exp;
+            let mut body = dispatch_on_memarg(
+                memarg_cases(input_memories, natural_alignment_exp),
+                2,
+                Some(3),
+                FunctionType::new(&[], &[load_type]),
+                |memory, alignment_exp| {
+                    vec![
+                        // []
+                        Local(LocalOp::Get, 0_u32.into()),
+                        // [ptr]
+                        Local(LocalOp::Get, 1_u32.into()),
+                        // [ptr, offset]
+                        Binary(wasabi_wasm::BinaryOp::I32Add),
+                        // [ptr + offset]
+                        Load(
+                            load_op,
+                            Memarg {
+                                alignment_exp,
+                                offset: 0,
+                                memory: memory.into(),
+                            },
+                        ),
+                        // [value]
+                    ]
+                },
+            );
+            body.push(End);
+            body
         };
 
         let memory_function_idx = module.add_function(function_type, vec![], body);
@@ -436,14 +688,12 @@ pub fn inject_memory_loads(module: &mut Module) {
     }
 }
 
-pub fn inject_memory_stores(module: &mut Module) {
+pub fn inject_memory_stores(module: &mut Module, input_memories: usize) {
     use wasabi_wasm::Instr::{Binary, End, Local, Store};
     use wasabi_wasm::StoreOp::{F32Store, F64Store, I32Store, I64Store}; // Regular
     use wasabi_wasm::StoreOp::{I32Store16, I32Store8}; // I32 Specialized
     use wasabi_wasm::StoreOp::{I64Store16, I64Store32, I64Store8}; // I64 Specialized
 
-    assert!(module.memories.len() <= 1);
-
     for (name, store_op, store_type) in [
         // Regular
         ("instrumented_base_store_i32", I32Store, ValType::I32),
@@ -458,24 +708,51 @@ pub fn inject_memory_stores(module: &mut Module) {
         ("instrumented_base_store_i64_16", I64Store16, ValType::I64),
         ("instrumented_base_store_i64_32", I64Store32, ValType::I64),
     ] {
-        let function_type = FunctionType::new(&[ValType::I32, store_type, ValType::I32], &[]);
-        let body = if module.memories.is_empty() {
+        // ptr, value, offset, memory index, alignment exponent
+        let function_type = FunctionType::new(
+            &[
+                ValType::I32,
+                store_type,
+                ValType::I32,
+                ValType::I32,
+                ValType::I32,
+            ],
+            &[],
+        );
+        let body = if input_memories == 0 {
             vec![End]
         } else {
-            vec![
-                // []
-                Local(LocalOp::Get, 0_u32.into()),
-                // [ptr]
-                Local(LocalOp::Get, 2_u32.into()),
-                // [ptr, offset]
-                Binary(wasabi_wasm::BinaryOp::I32Add),
-                // [ptr + offset]
-                Local(LocalOp::Get, 1_u32.into()),
-                // [ptr + offset, value]
-                Store(store_op, Memarg::default(store_op)),
-                // []
-                End,
-            ]
+            let natural_alignment_exp = Memarg::default(store_op).alignment_exp;
+            let mut body = dispatch_on_memarg(
+                memarg_cases(input_memories, natural_alignment_exp),
+                3,
+                Some(4),
+                FunctionType::new(&[], &[]),
+                |memory, alignment_exp| {
+                    vec![
+                        // []
+                        Local(LocalOp::Get, 0_u32.into()),
+                        // [ptr]
+                        Local(LocalOp::Get, 2_u32.into()),
+                        // [ptr, offset]
+                        Binary(wasabi_wasm::BinaryOp::I32Add),
+                        // [ptr + offset]
+                        Local(LocalOp::Get, 1_u32.into()),
+                        // [ptr + offset, value]
+                        Store(
+                            store_op,
+                            Memarg {
+                                alignment_exp,
+                                offset: 0,
+                                memory: memory.into(),
+                            },
+                        ),
+                        // []
+                    ]
+                },
+            );
+            body.push(End);
+            body
         };
 
         let memory_function_idx: Idx<Function> = module.add_function(function_type, vec![], body);
@@ -486,12 +763,11 @@ pub fn inject_memory_stores(module: &mut Module) {
     }
 }
 
-pub fn inject_memory_grow(module: &mut Module) {
+pub fn inject_memory_grow(module: &mut Module, input_memories: usize) {
     use wasabi_wasm::Instr::{Const, End, Local, MemoryGrow};
-    assert!(module.memories.len() <= 1);
 
     let function_type = FunctionType::new(&[ValType::I32, ValType::I32], &[ValType::I32]);
-    let body = if module.memories.is_empty() {
+    let body = if input_memories == 0 {
         vec![
             // []
             Const(Val::I32(-1)),
@@ -499,14 +775,24 @@ pub fn inject_memory_grow(module: &mut Module) {
             End,
         ]
     } else {
-        vec![
-            // []
-            Local(LocalOp::Get, 0_u32.into()),
-            // [amount:i32]
-            MemoryGrow(0_u32.into()),
-            // [delta_or_neg_1:i32]
-            End,
-        ]
+        // memory.grow carries no alignment, dispatch on the memory index only
+        let mut body = dispatch_on_memarg(
+            memarg_cases(input_memories, 0),
+            1,
+            None,
+            FunctionType::new(&[], &[ValType::I32]),
+            |memory, _| {
+                vec![
+                    // []
+                    Local(LocalOp::Get, 0_u32.into()),
+                    // [amount:i32]
+                    MemoryGrow(memory.into()),
+                    // [delta_or_neg_1:i32]
+                ]
+            },
+        );
+        body.push(End);
+        body
     };
 
     let memory_function_idx: Idx<Function> = module.add_function(function_type, vec![], body);
@@ -516,12 +802,11 @@ pub fn inject_memory_grow(module: &mut Module) {
         .push("instrumented_memory_grow".to_string());
 }
 
-pub fn inject_memory_size(module: &mut Module) {
+pub fn inject_memory_size(module: &mut Module, input_memories: usize) {
     use wasabi_wasm::Instr::{Const, End, MemorySize};
-    assert!(module.memories.len() <= 1);
 
     let function_type = FunctionType::new(&[ValType::I32], &[ValType::I32]);
-    let body = if module.memories.is_empty() {
+    let body = if input_memories == 0 {
         vec![
             // []
             Const(Val::I32(0)),
@@ -529,12 +814,22 @@ pub fn inject_memory_size(module: &mut Module) {
             End,
         ]
     } else {
-        vec![
-            // []
-            MemorySize(0_u32.into()),
-            // [size_in_pages:i32]
-            End,
-        ]
+        // memory.size carries no alignment, dispatch on the memory index only
+        let mut body = dispatch_on_memarg(
+            memarg_cases(input_memories, 0),
+            0,
+            None,
+            FunctionType::new(&[], &[ValType::I32]),
+            |memory, _| {
+                vec![
+                    // []
+                    MemorySize(memory.into()),
+                    // [size_in_pages:i32]
+                ]
+            },
+        );
+        body.push(End);
+        body
     };
 
     let memory_function_idx: Idx<Function> = module.add_function(function_type, vec![], body);
@@ -1,8 +1,12 @@
-use std::collections::HashSet;
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashSet};
+
+use rayon::prelude::*;
 
 use wasabi_wasm::Code;
 use wasabi_wasm::FunctionType;
 use wasabi_wasm::ImportOrPresent;
+use wasabi_wasm::Local;
 use wasabi_wasm::Module;
 use wasabi_wasm::ValType;
 
@@ -11,38 +15,59 @@ use wasabi_wasm::Function;
 use wasabi_wasm::Idx;
 
 use crate::analysis::{AnalysisInterface, WasmExport, WasmImport, WasmType};
+use crate::code_offsets::CodeOffsets;
 use crate::error::InstrumentationError;
-use crate::parse_nesting::HighLevelBody;
+use crate::manifest::{self, Manifest};
 use crate::parse_nesting::LowLevelBody;
+use crate::parse_nesting::{BodyInner, HighLevelBody, TypedHighLevelInstr};
+use crate::pointcut::{self, Pointcut};
+use crate::source_map::SourceMap;
+use crate::stack_library::StackLibrary;
+use crate::{Configuration, CustomSections, TargetSelection};
 
-use self::block_loop::Target::{BlockPost, BlockPre, LoopPost, LoopPre, Select};
-use self::branch_if::Target::{Br, BrIf, BrTable, IfThen, IfThenElse, IfThenElsePost, IfThenPost};
+use self::basic_block::BasicBlock;
+use self::block_loop::Target::{BlockPost, BlockPre, LoopIteration, LoopPost, LoopPre, Select};
+use self::block_loop::Unwind;
+use self::branch_if::Target::{Br, BrIf, IfThen, IfThenElse, IfThenElsePost, IfThenPost};
 use self::function_application::INSTRUMENTATION_ANALYSIS_MODULE;
 use self::function_call_indirect::Target::{
     IndirectPost as CallIndirectPost, IndirectPre as CallIndirectPre, Post as CallPost,
     Pre as CallPre,
 };
+use self::function_entry_exit::Target::{Entry as FunctionEntry, Exit as FunctionExit};
 use self::memory::Target::*;
 use self::simple_operations::Target::*;
+use self::single_pass::{Selected, Strategy, Strategy::Body, Strategy::Instruction};
 use self::table::Target::*;
 
+pub mod basic_block;
 pub mod block_loop;
 pub mod branch_if;
+pub mod compact_location;
+pub mod coverage;
+pub mod custom_sections;
 pub mod function_application;
 pub mod function_call_indirect;
+pub mod function_entry_exit;
+pub mod import_call;
 pub mod memory;
 pub mod simple_operations;
+pub mod single_pass;
+pub mod source_location;
 pub mod table;
 
 pub struct Instrumented<InstrumentationLanguage: LibGeneratable> {
     pub module: Vec<u8>,
     pub instrumentation_library: Option<Library<InstrumentationLanguage>>,
+    /// Side table of the blocks reported by the basic block enter trap
+    pub basic_blocks: Vec<BasicBlock>,
+    pub manifest: Option<Manifest>,
 }
 
 pub fn instrument<InstrumentationLanguage: LibGeneratable>(
     module: &[u8],
     analysis_interface: &AnalysisInterface,
-    target_indices: &Option<Vec<u32>>,
+    configuration: &Configuration,
 ) -> Result<Instrumented<InstrumentationLanguage>, InstrumentationError> {
     let AnalysisInterface {
         generic_interface,
@@ -56,14 +81,23 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         post_trap_call,
         pre_trap_call_indirect,
         post_trap_call_indirect,
+        import_call_pre,
+        import_call_post,
+        import_name_byte,
+        source_location,
+        source_file_byte,
         br_table_trap,
         pre_block,
         post_block,
         pre_loop,
         post_loop,
+        loop_iteration,
+        basic_block_enter,
         select,
         drop_trap,
         return_trap,
+        function_entry,
+        function_exit,
         const_i32_trap,
         const_f32_trap,
         const_i64_trap,
@@ -93,6 +127,19 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         binary_f64_f64_to_f64,
         memory_size,
         memory_grow,
+        memory_fill,
+        memory_fill_get_destination,
+        memory_fill_get_value,
+        memory_fill_get_size,
+        memory_copy,
+        memory_copy_get_destination,
+        memory_copy_get_source,
+        memory_copy_get_size,
+        memory_init,
+        memory_init_get_destination,
+        memory_init_get_source,
+        memory_init_get_size,
+        data_drop,
         local_get_i32,
         local_set_i32,
         local_tee_i32,
@@ -140,35 +187,76 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         elem_drop,
     } = analysis_interface;
 
+    let Configuration {
+        unwinding,
+        coverage,
+        custom_sections,
+        source_locations,
+        manifest,
+        compact_locations,
+        separate_passes,
+        ..
+    } = configuration;
+
+    if let Some(memory) = memory::first_memory64(module) {
+        return Err(InstrumentationError::UnsupportedMemory64 { memory });
+    }
+    for pointcut in configuration.hook_pointcuts.values() {
+        pointcut.validate()?;
+    }
+
+    // Read before the code is transformed, DWARF refers to the original code offsets
+    let source_map = source_locations
+        .then(|| SourceMap::from_module(module))
+        .transpose()?;
+    let code_offsets = manifest
+        .then(|| CodeOffsets::read(module))
+        .transpose()
+        .map_err(InstrumentationError::CodeSectionParseError)?;
+
     let (mut module, _offsets, _issue) =
         Module::from_bytes(module).map_err(InstrumentationError::ParseModuleError)?;
+    let configuration = &configuration.with_resolved_names(&module)?;
+    let first_injected_function = module.functions.len();
+    // Memories added by instrumentation come after these, out of reach of the analysis
+    let input_memories = module.memories.len();
 
     let target_indices_including_imports: HashSet<Idx<Function>> = module
         .functions()
-        .filter(|(_index, f)| !uses_reference_types(f))
         .map(|(idx, _)| idx)
-        .filter(|index| {
-            target_indices
-                .as_ref()
-                .is_none_or(|ts| ts.contains(&index.to_u32()))
-        })
+        .filter(|index| configuration.is_target(index.to_u32()))
         .collect();
 
-    let target_indices: HashSet<Idx<Function>> = module
+    let generic_apply_targets: HashSet<Idx<Function>> = match generic_interface {
+        Some((generic_apply, _)) => match configuration.hook_selection(&generic_apply.name) {
+            Some(selection) => target_indices_including_imports
+                .iter()
+                .filter(|index| selection.contains(index.to_u32()))
+                .copied()
+                .collect(),
+            None => target_indices_including_imports,
+        },
+        None => HashSet::new(),
+    };
+
+    // Computed before any trap is installed, since traps are installed as imports
+    let import_call_targets: HashSet<Idx<Function>> =
+        if import_call_pre.is_some() || import_call_post.is_some() {
+            import_call::import_call_targets(&module)
+        } else {
+            HashSet::new()
+        };
+
+    let target_indices: Vec<Idx<Function>> = module
         .functions()
         .filter(|(_index, f)| f.code().is_some())
-        .filter(|(_index, f)| !uses_reference_types(f))
         .map(|(idx, _)| idx)
-        .filter(|index| {
-            target_indices
-                .as_ref()
-                .is_none_or(|ts| ts.contains(&index.to_u32()))
-        })
+        .filter(|index| configuration.is_target(index.to_u32()))
         .collect();
 
     // For each function, generate high-level typed AST
     let target_high_level_functions: Vec<HighLevelBody> = target_indices
-        .iter()
+        .par_iter()
         .map(|target_function_idx| {
             let target_function = module.function(*target_function_idx);
             let code = target_function
@@ -180,107 +268,247 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
         })
         .collect::<Result<Vec<HighLevelBody>, InstrumentationError>>()?;
 
+    // Basic blocks are computed on the original bodies
+    let basic_blocks = if basic_block_enter.is_some() || *coverage {
+        basic_block::basic_blocks(
+            &module,
+            target_indices
+                .iter()
+                .copied()
+                .zip(&target_high_level_functions),
+        )
+    } else {
+        Vec::new()
+    };
+
+    // The functions mapping the indices of br tables to their labels are generated up front
+    let branch_tables = match br_table_trap {
+        Some(br_table_trap) => {
+            let selection = configuration.hook_selection(&br_table_trap.name);
+            branch_if::BranchTables::install(
+                &mut module,
+                target_indices
+                    .iter()
+                    .zip(&target_high_level_functions)
+                    .filter(|(index, _)| {
+                        selection
+                            .as_ref()
+                            .is_none_or(|selection| selection.contains(index.to_u32()))
+                    })
+                    .map(|(_, high_level_body)| high_level_body),
+            )
+        }
+        None => branch_if::BranchTables::default(),
+    };
+
+    // The block & loop post traps are shared with the unwinding target
+    let (post_block_idx, post_loop_idx) = (Cell::new(None), Cell::new(None));
+
     //  Install all tarps
-    type TFn = fn(Idx<Function>) -> Box<dyn TransformationStrategy>;
-    let traps_target_generators = [
-        (pre_block, (|i| Box::new(BlockPre(i)))),
-        (post_block, (|i| Box::new(BlockPost(i)))),
-        (pre_loop, (|i| Box::new(LoopPre(i)))),
-        (post_loop, (|i| Box::new(LoopPost(i)))),
-        (select, (|i| Box::new(Select(i)))),
-        (pre_trap_call, (|i| Box::new(CallPre(i)))),
-        (post_trap_call, (|i| Box::new(CallPost(i)))),
-        (pre_trap_call_indirect, (|i| Box::new(CallIndirectPre(i)))),
-        (post_trap_call_indirect, (|i| Box::new(CallIndirectPost(i)))),
-        (if_then_trap, (|i| Box::new(IfThen(i)))),
-        (if_then_post_trap, (|i| Box::new(IfThenPost(i)))),
-        (if_then_else_trap, (|i| Box::new(IfThenElse(i)))),
-        (if_then_else_post_trap, (|i| Box::new(IfThenElsePost(i)))),
-        (br_trap, (|i| Box::new(Br(i)))),
-        (br_if_trap, (|i| Box::new(BrIf(i)))),
-        (br_table_trap, (|i| Box::new(BrTable(i)))),
-        (drop_trap, (|i| Box::new(Drop(i)))),
-        (return_trap, (|i| Box::new(Return(i)))),
-        (const_i32_trap, (|i| Box::new(ConstI32(i)))),
-        (const_f32_trap, (|i| Box::new(ConstF32(i)))),
-        (const_i64_trap, (|i| Box::new(ConstI64(i)))),
-        (const_f64_trap, (|i| Box::new(ConstF64(i)))),
-        (unary_i32_to_i32, (|i| Box::new(UnaryI32ToI32(i)))),
-        (unary_i64_to_i32, (|i| Box::new(UnaryI64ToI32(i)))),
-        (unary_i64_to_i64, (|i| Box::new(UnaryI64ToI64(i)))),
-        (unary_f32_to_f32, (|i| Box::new(UnaryF32ToF32(i)))),
-        (unary_f64_to_f64, (|i| Box::new(UnaryF64ToF64(i)))),
-        (unary_f32_to_i32, (|i| Box::new(UnaryF32ToI32(i)))),
-        (unary_f64_to_i32, (|i| Box::new(UnaryF64ToI32(i)))),
-        (unary_i32_to_i64, (|i| Box::new(UnaryI32ToI64(i)))),
-        (unary_f32_to_i64, (|i| Box::new(UnaryF32ToI64(i)))),
-        (unary_f64_to_i64, (|i| Box::new(UnaryF64ToI64(i)))),
-        (unary_i32_to_f32, (|i| Box::new(UnaryI32ToF32(i)))),
-        (unary_i64_to_f32, (|i| Box::new(UnaryI64ToF32(i)))),
-        (unary_f64_to_f32, (|i| Box::new(UnaryF64ToF32(i)))),
-        (unary_i32_to_f64, (|i| Box::new(UnaryI32ToF64(i)))),
-        (unary_i64_to_f64, (|i| Box::new(UnaryI64ToF64(i)))),
-        (unary_f32_to_f64, (|i| Box::new(UnaryF32ToF64(i)))),
-        (binary_i32_i32_to_i32, (|i| Box::new(BinaryI32I32toI32(i)))),
-        (binary_i64_i64_to_i32, (|i| Box::new(BinaryI64I64toI32(i)))),
-        (binary_f32_f32_to_i32, (|i| Box::new(BinaryF32F32toI32(i)))),
-        (binary_f64_f64_to_i32, (|i| Box::new(BinaryF64F64toI32(i)))),
-        (binary_i64_i64_to_i64, (|i| Box::new(BinaryI64I64toI64(i)))),
-        (binary_f32_f32_to_f32, (|i| Box::new(BinaryF32F32toF32(i)))),
-        (binary_f64_f64_to_f64, (|i| Box::new(BinaryF64F64toF64(i)))),
-        (memory_size, (|i| Box::new(MemorySize(i)))),
-        (memory_grow, (|i| Box::new(MemoryGrow(i)))),
-        (local_get_i32, (|i| Box::new(LocalGetI32(i)))),
-        (local_set_i32, (|i| Box::new(LocalSetI32(i)))),
-        (local_tee_i32, (|i| Box::new(LocalTeeI32(i)))),
-        (global_get_i32, (|i| Box::new(GlobalGetI32(i)))),
-        (global_set_i32, (|i| Box::new(GlobalSetI32(i)))),
-        (local_get_f32, (|i| Box::new(LocalGetF32(i)))),
-        (local_set_f32, (|i| Box::new(LocalSetF32(i)))),
-        (local_tee_f32, (|i| Box::new(LocalTeeF32(i)))),
-        (global_get_f32, (|i| Box::new(GlobalGetF32(i)))),
-        (global_set_f32, (|i| Box::new(GlobalSetF32(i)))),
-        (local_get_i64, (|i| Box::new(LocalGetI64(i)))),
-        (local_set_i64, (|i| Box::new(LocalSetI64(i)))),
-        (local_tee_i64, (|i| Box::new(LocalTeeI64(i)))),
-        (global_get_i64, (|i| Box::new(GlobalGetI64(i)))),
-        (global_set_i64, (|i| Box::new(GlobalSetI64(i)))),
-        (local_get_f64, (|i| Box::new(LocalGetF64(i)))),
-        (local_set_f64, (|i| Box::new(LocalSetF64(i)))),
-        (local_tee_f64, (|i| Box::new(LocalTeeF64(i)))),
-        (global_get_f64, (|i| Box::new(GlobalGetF64(i)))),
-        (global_set_f64, (|i| Box::new(GlobalSetF64(i)))),
-        (f32_store, (|i| Box::new(F32Store(i)))),
-        (f64_store, (|i| Box::new(F64Store(i)))),
-        (i32_store, (|i| Box::new(I32Store(i)))),
-        (i64_store, (|i| Box::new(I64Store(i)))),
-        (f32_load, (|i| Box::new(F32Load(i)))),
-        (f64_load, (|i| Box::new(F64Load(i)))),
-        (i32_load, (|i| Box::new(I32Load(i)))),
-        (i64_load, (|i| Box::new(I64Load(i)))),
-        (ref_func, (|i| Box::new(RefFunc(i)))),
-        (ref_null, (|i| Box::new(RefNull(i)))),
-        (ref_is_null, (|i| Box::new(RefIsNull(i)))),
-        (table_get, (|i| Box::new(TableGet(i)))),
-        (table_set, (|i| Box::new(TableSet(i)))),
-        (table_size, (|i| Box::new(TableSize(i)))),
-        (table_grow, (|i| Box::new(TableGrow(i)))),
-        (table_fill, (|i| Box::new(TableFill(i)))),
+    type TFn<'a> = &'a dyn Fn(Idx<Function>) -> Strategy;
+    let traps_target_generators: [(&Option<WasmExport>, TFn); 86] = [
+        (pre_block, &|i| Body(Box::new(BlockPre(i)))),
+        (post_block, &|i| {
+            post_block_idx.set(Some(i));
+            Body(Box::new(BlockPost(i)))
+        }),
+        (pre_loop, &|i| Body(Box::new(LoopPre(i)))),
+        (post_loop, &|i| {
+            post_loop_idx.set(Some(i));
+            Body(Box::new(LoopPost(i)))
+        }),
+        (loop_iteration, &|i| Body(Box::new(LoopIteration(i)))),
+        (select, &|i| Body(Box::new(Select(i)))),
+        (pre_trap_call, &|i| Instruction(Box::new(CallPre(i)))),
+        (post_trap_call, &|i| Instruction(Box::new(CallPost(i)))),
+        (pre_trap_call_indirect, &|i| {
+            Instruction(Box::new(CallIndirectPre(i)))
+        }),
+        (post_trap_call_indirect, &|i| {
+            Instruction(Box::new(CallIndirectPost(i)))
+        }),
+        (if_then_trap, &|i| Body(Box::new(IfThen(i)))),
+        (if_then_post_trap, &|i| Body(Box::new(IfThenPost(i)))),
+        (if_then_else_trap, &|i| Body(Box::new(IfThenElse(i)))),
+        (if_then_else_post_trap, &|i| {
+            Body(Box::new(IfThenElsePost(i)))
+        }),
+        (br_trap, &|i| Body(Box::new(Br(i)))),
+        (br_if_trap, &|i| Body(Box::new(BrIf(i)))),
+        (br_table_trap, &|i| {
+            Body(Box::new(branch_if::BranchTableTarget {
+                trap_idx: i,
+                branch_tables: branch_tables.clone(),
+            }))
+        }),
+        (drop_trap, &|i| Instruction(Box::new(Drop(i)))),
+        (return_trap, &|i| Instruction(Box::new(Return(i)))),
+        (const_i32_trap, &|i| Instruction(Box::new(ConstI32(i)))),
+        (const_f32_trap, &|i| Instruction(Box::new(ConstF32(i)))),
+        (const_i64_trap, &|i| Instruction(Box::new(ConstI64(i)))),
+        (const_f64_trap, &|i| Instruction(Box::new(ConstF64(i)))),
+        (unary_i32_to_i32, &|i| {
+            Instruction(Box::new(UnaryI32ToI32(i)))
+        }),
+        (unary_i64_to_i32, &|i| {
+            Instruction(Box::new(UnaryI64ToI32(i)))
+        }),
+        (unary_i64_to_i64, &|i| {
+            Instruction(Box::new(UnaryI64ToI64(i)))
+        }),
+        (unary_f32_to_f32, &|i| {
+            Instruction(Box::new(UnaryF32ToF32(i)))
+        }),
+        (unary_f64_to_f64, &|i| {
+            Instruction(Box::new(UnaryF64ToF64(i)))
+        }),
+        (unary_f32_to_i32, &|i| {
+            Instruction(Box::new(UnaryF32ToI32(i)))
+        }),
+        (unary_f64_to_i32, &|i| {
+            Instruction(Box::new(UnaryF64ToI32(i)))
+        }),
+        (unary_i32_to_i64, &|i| {
+            Instruction(Box::new(UnaryI32ToI64(i)))
+        }),
+        (unary_f32_to_i64, &|i| {
+            Instruction(Box::new(UnaryF32ToI64(i)))
+        }),
+        (unary_f64_to_i64, &|i| {
+            Instruction(Box::new(UnaryF64ToI64(i)))
+        }),
+        (unary_i32_to_f32, &|i| {
+            Instruction(Box::new(UnaryI32ToF32(i)))
+        }),
+        (unary_i64_to_f32, &|i| {
+            Instruction(Box::new(UnaryI64ToF32(i)))
+        }),
+        (unary_f64_to_f32, &|i| {
+            Instruction(Box::new(UnaryF64ToF32(i)))
+        }),
+        (unary_i32_to_f64, &|i| {
+            Instruction(Box::new(UnaryI32ToF64(i)))
+        }),
+        (unary_i64_to_f64, &|i| {
+            Instruction(Box::new(UnaryI64ToF64(i)))
+        }),
+        (unary_f32_to_f64, &|i| {
+            Instruction(Box::new(UnaryF32ToF64(i)))
+        }),
+        (binary_i32_i32_to_i32, &|i| {
+            Instruction(Box::new(BinaryI32I32toI32(i)))
+        }),
+        (binary_i64_i64_to_i32, &|i| {
+            Instruction(Box::new(BinaryI64I64toI32(i)))
+        }),
+        (binary_f32_f32_to_i32, &|i| {
+            Instruction(Box::new(BinaryF32F32toI32(i)))
+        }),
+        (binary_f64_f64_to_i32, &|i| {
+            Instruction(Box::new(BinaryF64F64toI32(i)))
+        }),
+        (binary_i64_i64_to_i64, &|i| {
+            Instruction(Box::new(BinaryI64I64toI64(i)))
+        }),
+        (binary_f32_f32_to_f32, &|i| {
+            Instruction(Box::new(BinaryF32F32toF32(i)))
+        }),
+        (binary_f64_f64_to_f64, &|i| {
+            Instruction(Box::new(BinaryF64F64toF64(i)))
+        }),
+        (memory_size, &|i| Instruction(Box::new(MemorySize(i)))),
+        (memory_grow, &|i| Instruction(Box::new(MemoryGrow(i)))),
+        // memory fill is currently handled separately
+        // memory copy is currently handled separately
+        // memory init is currently handled separately
+        (data_drop, &|i| Instruction(Box::new(DataDrop(i)))),
+        (local_get_i32, &|i| Instruction(Box::new(LocalGetI32(i)))),
+        (local_set_i32, &|i| Instruction(Box::new(LocalSetI32(i)))),
+        (local_tee_i32, &|i| Instruction(Box::new(LocalTeeI32(i)))),
+        (global_get_i32, &|i| Instruction(Box::new(GlobalGetI32(i)))),
+        (global_set_i32, &|i| Instruction(Box::new(GlobalSetI32(i)))),
+        (local_get_f32, &|i| Instruction(Box::new(LocalGetF32(i)))),
+        (local_set_f32, &|i| Instruction(Box::new(LocalSetF32(i)))),
+        (local_tee_f32, &|i| Instruction(Box::new(LocalTeeF32(i)))),
+        (global_get_f32, &|i| Instruction(Box::new(GlobalGetF32(i)))),
+        (global_set_f32, &|i| Instruction(Box::new(GlobalSetF32(i)))),
+        (local_get_i64, &|i| Instruction(Box::new(LocalGetI64(i)))),
+        (local_set_i64, &|i| Instruction(Box::new(LocalSetI64(i)))),
+        (local_tee_i64, &|i| Instruction(Box::new(LocalTeeI64(i)))),
+        (global_get_i64, &|i| Instruction(Box::new(GlobalGetI64(i)))),
+        (global_set_i64, &|i| Instruction(Box::new(GlobalSetI64(i)))),
+        (local_get_f64, &|i| Instruction(Box::new(LocalGetF64(i)))),
+        (local_set_f64, &|i| Instruction(Box::new(LocalSetF64(i)))),
+        (local_tee_f64, &|i| Instruction(Box::new(LocalTeeF64(i)))),
+        (global_get_f64, &|i| Instruction(Box::new(GlobalGetF64(i)))),
+        (global_set_f64, &|i| Instruction(Box::new(GlobalSetF64(i)))),
+        (f32_store, &|i| Instruction(Box::new(F32Store(i)))),
+        (f64_store, &|i| Instruction(Box::new(F64Store(i)))),
+        (i32_store, &|i| Instruction(Box::new(I32Store(i)))),
+        (i64_store, &|i| Instruction(Box::new(I64Store(i)))),
+        (f32_load, &|i| Instruction(Box::new(F32Load(i)))),
+        (f64_load, &|i| Instruction(Box::new(F64Load(i)))),
+        (i32_load, &|i| Instruction(Box::new(I32Load(i)))),
+        (i64_load, &|i| Instruction(Box::new(I64Load(i)))),
+        (ref_func, &|i| Body(Box::new(RefFunc(i)))),
+        (ref_null, &|i| Body(Box::new(RefNull(i)))),
+        (ref_is_null, &|i| Body(Box::new(RefIsNull(i)))),
+        (table_get, &|i| Body(Box::new(TableGet(i)))),
+        (table_set, &|i| Body(Box::new(TableSet(i)))),
+        (table_size, &|i| Body(Box::new(TableSize(i)))),
+        (table_grow, &|i| Body(Box::new(TableGrow(i)))),
+        (table_fill, &|i| Body(Box::new(TableFill(i)))),
         // table copy is currently handled separately
         // table init is currently handled separately
-        (elem_drop, (|i| Box::new(ElemDrop(i)))),
-    ] as [(&Option<WasmExport>, TFn); 84];
+        (elem_drop, &|i| Body(Box::new(ElemDrop(i)))),
+    ];
+
+    // Restricts a target to the functions & sites its hook selects
+    let select = |trap: &WasmExport, strategy: Strategy| Selected {
+        selection: configuration.hook_selection(&trap.name),
+        pointcut: configuration.hook_pointcuts.get(&trap.name).cloned(),
+        strategy,
+    };
 
-    let mut targets: Vec<Box<dyn TransformationStrategy>> = traps_target_generators
+    let mut targets: Vec<Selected> = traps_target_generators
         .into_iter()
         .filter_map(|(export, target_gen)| {
             export
                 .as_ref()
-                .map(|export| module.install(export))
-                .map(target_gen)
+                .map(|export| select(export, target_gen(module.install(export))))
         })
         .collect();
 
+    // basic block target, placed first so its trap precedes all other instrumentation of a block
+    if let Some(basic_block_enter_trap) = basic_block_enter {
+        let basic_block_enter_trap_idx = module.install(basic_block_enter_trap);
+        targets.insert(
+            0,
+            select(
+                basic_block_enter_trap,
+                Body(Box::new(basic_block::Target::new(
+                    basic_block_enter_trap_idx,
+                    &basic_blocks,
+                ))),
+            ),
+        );
+    }
+
+    // coverage target, placed first so the bitmap is updated before the block is reported
+    if *coverage {
+        targets.insert(
+            0,
+            Selected {
+                selection: configuration.default_selection(),
+                pointcut: None,
+                strategy: Body(Box::new(coverage::Target::install(
+                    &mut module,
+                    &basic_blocks,
+                ))),
+            },
+        );
+    }
+
     // table copy target
     if let Some(table_copy_trap) = table_copy {
         let table_copy_trap_idx = module.install(table_copy_trap);
@@ -304,7 +532,7 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
             get_size_idx: table_copy_get_size_idx,
         }) as Box<dyn TransformationStrategy>;
 
-        targets.push(table_copy_target);
+        targets.push(select(table_copy_trap, Body(table_copy_target)));
     }
 
     if let Some(table_init_trap) = table_init {
@@ -329,71 +557,402 @@ pub fn instrument<InstrumentationLanguage: LibGeneratable>(
             get_size_idx: table_init_get_size_idx,
         }) as Box<dyn TransformationStrategy>;
 
-        targets.push(table_init_target);
+        targets.push(select(table_init_trap, Body(table_init_target)));
     }
 
-    let transformed_bodies: Vec<HighLevelBody> = target_high_level_functions
-        .into_iter()
-        .map(|high_level_body| {
-            targets.iter().fold(high_level_body, |transformed, target| {
-                target.transform(&transformed, &mut module)
+    if let Some(memory_fill_trap) = memory_fill {
+        let memory_fill_trap_idx = module.install(memory_fill_trap);
+        let memory_fill_get_dst_idx = install_required(
+            &mut module,
+            memory_fill_get_destination,
+            "memory_fill_get_destination",
+            memory_fill_trap,
+        )?;
+        let memory_fill_get_val_idx = install_required(
+            &mut module,
+            memory_fill_get_value,
+            "memory_fill_get_value",
+            memory_fill_trap,
+        )?;
+        let memory_fill_get_size_idx = install_required(
+            &mut module,
+            memory_fill_get_size,
+            "memory_fill_get_size",
+            memory_fill_trap,
+        )?;
+
+        let memory_fill_target = Instruction(Box::new(MemoryFill {
+            trap_idx: memory_fill_trap_idx,
+            get_dst_idx: memory_fill_get_dst_idx,
+            get_val_idx: memory_fill_get_val_idx,
+            get_size_idx: memory_fill_get_size_idx,
+        }));
+
+        targets.push(select(memory_fill_trap, memory_fill_target));
+    }
+
+    if let Some(memory_copy_trap) = memory_copy {
+        let memory_copy_trap_idx = module.install(memory_copy_trap);
+        let memory_copy_get_dst_idx = install_required(
+            &mut module,
+            memory_copy_get_destination,
+            "memory_copy_get_destination",
+            memory_copy_trap,
+        )?;
+        let memory_copy_get_src_idx = install_required(
+            &mut module,
+            memory_copy_get_source,
+            "memory_copy_get_source",
+            memory_copy_trap,
+        )?;
+        let memory_copy_get_size_idx = install_required(
+            &mut module,
+            memory_copy_get_size,
+            "memory_copy_get_size",
+            memory_copy_trap,
+        )?;
+
+        let memory_copy_target = Instruction(Box::new(MemoryCopy {
+            trap_idx: memory_copy_trap_idx,
+            get_dst_idx: memory_copy_get_dst_idx,
+            get_src_idx: memory_copy_get_src_idx,
+            get_size_idx: memory_copy_get_size_idx,
+        }));
+
+        targets.push(select(memory_copy_trap, memory_copy_target));
+    }
+
+    if let Some(memory_init_trap) = memory_init {
+        let memory_init_trap_idx = module.install(memory_init_trap);
+        let memory_init_get_dst_idx = install_required(
+            &mut module,
+            memory_init_get_destination,
+            "memory_init_get_destination",
+            memory_init_trap,
+        )?;
+        let memory_init_get_src_idx = install_required(
+            &mut module,
+            memory_init_get_source,
+            "memory_init_get_source",
+            memory_init_trap,
+        )?;
+        let memory_init_get_size_idx = install_required(
+            &mut module,
+            memory_init_get_size,
+            "memory_init_get_size",
+            memory_init_trap,
+        )?;
+
+        let memory_init_target = Instruction(Box::new(MemoryInit {
+            trap_idx: memory_init_trap_idx,
+            get_dst_idx: memory_init_get_dst_idx,
+            get_src_idx: memory_init_get_src_idx,
+            get_size_idx: memory_init_get_size_idx,
+        }));
+
+        targets.push(select(memory_init_trap, memory_init_target));
+    }
+
+    // unwinding target, after the branch targets so it sees their final operands
+    let unwound = |post_trap: &Option<WasmExport>, trap_idx: Option<Idx<Function>>| {
+        let post_trap = post_trap.as_ref()?;
+        Some(block_loop::UnwoundTrap {
+            trap_idx: trap_idx?,
+            selection: configuration.hook_selection(&post_trap.name),
+            pointcut: configuration.hook_pointcuts.get(&post_trap.name).cloned(),
+        })
+    };
+    let (block_post, loop_post) = (
+        unwound(post_block, post_block_idx.get()),
+        unwound(post_loop, post_loop_idx.get()),
+    );
+    if *unwinding && (block_post.is_some() || loop_post.is_some()) {
+        // Each post trap selects its own functions & constructs, the branches are never restricted
+        targets.push(Selected {
+            selection: None,
+            pointcut: None,
+            strategy: Body(Box::new(Unwind {
+                block_post,
+                loop_post,
+            })),
+        });
+    }
+
+    // The stack library serves both generic apply & the import call hooks
+    let stack_library_functions: HashSet<Idx<Function>> = generic_apply_targets
+        .union(&import_call_targets)
+        .copied()
+        .collect();
+    let stack_library =
+        (generic_interface.is_some() || !import_call_targets.is_empty()).then(|| {
+            StackLibrary::<InstrumentationLanguage>::from_module(
+                &mut module,
+                &stack_library_functions,
+            )
+        });
+
+    if let Some(StackLibrary {
+        signature_import_links,
+        ..
+    }) = stack_library
+        .as_ref()
+        .filter(|_| !import_call_targets.is_empty())
+    {
+        let import_call_trap = import_call_pre
+            .as_ref()
+            .or(import_call_post.as_ref())
+            .unwrap();
+        let import_name_byte_idx = install_required(
+            &mut module,
+            import_name_byte,
+            "import_name_byte",
+            import_call_trap,
+        )?;
+        let register_names_idx = import_call::install_name_registration(
+            &mut module,
+            &import_call_targets,
+            import_name_byte_idx,
+        );
+        let callee_stack_hooks = import_call_targets
+            .iter()
+            .map(|callee| {
+                let callee_type = module.function(*callee).type_;
+                (*callee, signature_import_links[&callee_type].clone())
             })
+            .collect();
+
+        let import_call_target = Box::new(import_call::Target {
+            pre_trap_idx: import_call_pre.as_ref().map(|e| module.install(e)),
+            post_trap_idx: import_call_post.as_ref().map(|e| module.install(e)),
+            register_names_idx,
+            callee_stack_hooks,
+        }) as Box<dyn TransformationStrategy>;
+
+        targets.push(select(import_call_trap, Body(import_call_target)));
+    }
+
+    // function entry & exit targets, these apply to the function rather than an instruction
+    let function_targets: Vec<(Option<TargetSelection>, function_entry_exit::Target)> = [
+        function_exit.as_ref().map(|e| {
+            (
+                configuration.hook_selection(&e.name),
+                FunctionExit(module.install(e)),
+            )
+        }),
+        function_entry.as_ref().map(|e| {
+            (
+                configuration.hook_selection(&e.name),
+                FunctionEntry(module.install(e)),
+            )
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // The instruction strategies between two body strategies share a single traversal
+    let passes = if *separate_passes {
+        single_pass::separate(targets)
+    } else {
+        single_pass::fuse(targets)
+    };
+
+    // All traps are installed, so the functions are transformed in parallel. Each
+    // transformation adds its locals to a copy of the function, written back below.
+    let (transformed_bodies, transformed_locals): (Vec<HighLevelBody>, Vec<Vec<Local>>) =
+        target_indices
+            .par_iter()
+            .zip(target_high_level_functions)
+            .map(|(target_function_idx, high_level_body)| {
+                let mut function = module.function(*target_function_idx).clone();
+                let transformed = passes.iter().fold(high_level_body, |transformed, target| {
+                    target.transform(*target_function_idx, &transformed, &module, &mut function)
+                });
+                let transformed = function_targets
+                    .iter()
+                    .filter(|(selection, _)| {
+                        selection.as_ref().is_none_or(|selection| {
+                            selection.contains(target_function_idx.to_u32())
+                        })
+                    })
+                    .fold(transformed, |transformed, (_, target)| {
+                        target.transform_function(*target_function_idx, &transformed, &module)
+                    });
+                let locals = function
+                    .code()
+                    .ok_or(InstrumentationError::AttemptInnerInstrumentImport)?
+                    .locals
+                    .clone();
+                Ok((transformed, locals))
+            })
+            .collect::<Result<Vec<_>, InstrumentationError>>()?
+            .into_iter()
+            .unzip();
+
+    let mut trap_calls = Vec::new();
+    for HighLevelBody(body) in &transformed_bodies {
+        manifest::trap_calls(&module, body, &mut trap_calls);
+    }
+    let site_table = compact_locations
+        .then(|| compact_location::SiteTable::new(&trap_calls))
+        .filter(|site_table| !site_table.is_empty());
+    // Before the original bodies are replaced
+    let manifest = code_offsets.map(|code_offsets| {
+        manifest::manifest(&module, &code_offsets, &trap_calls, site_table.as_ref())
+    });
+    let transformed_bodies = match &site_table {
+        Some(site_table) => {
+            compact_location::compact(&mut module, site_table, &trap_calls, transformed_bodies)
+        }
+        None => transformed_bodies,
+    };
+
+    let register_locations_idx = match (source_map, source_location, source_file_byte) {
+        (Some(source_map), Some(source_location), Some(source_file_byte))
+            if !source_map.is_empty() =>
+        {
+            let sites: BTreeSet<(u32, usize)> = trap_calls
+                .iter()
+                .map(|(funct_index, instr_index, _)| (*funct_index, *instr_index))
+                .collect();
+            let source_location_idx = module.install(source_location);
+            let source_file_byte_idx = module.install(source_file_byte);
+            Some(source_location::install_location_registration(
+                &mut module,
+                &source_map,
+                &sites,
+                source_location_idx,
+                source_file_byte_idx,
+            ))
+        }
+        _ => None,
+    };
+
+    let transformed_low_level_bodies: Vec<Vec<wasabi_wasm::Instr>> = transformed_bodies
+        .into_par_iter()
+        .map(|transformed_body| {
+            let LowLevelBody(mut transformed_low_level_body) = transformed_body.into();
+            if let Some(register_locations_idx) = register_locations_idx {
+                // Before any trap of the function is called
+                transformed_low_level_body
+                    .insert(0, wasabi_wasm::Instr::Call(register_locations_idx));
+            }
+            transformed_low_level_body
         })
         .collect();
 
-    for (target_function_idx, transformed_body) in target_indices.iter().zip(transformed_bodies) {
-        let LowLevelBody(transformed_low_level_body) = transformed_body.into();
-        let locals = module
-            .function(*target_function_idx)
-            .code()
-            .ok_or(InstrumentationError::AttemptInnerInstrumentImport)?
-            .locals
-            .clone();
-        module.function_mut(*target_function_idx).code = ImportOrPresent::Present(Code {
-            body: transformed_low_level_body,
-            locals,
-        });
+    for ((target_function_idx, body), locals) in target_indices
+        .iter()
+        .zip(transformed_low_level_bodies)
+        .zip(transformed_locals)
+    {
+        module.function_mut(*target_function_idx).code =
+            ImportOrPresent::Present(Code { body, locals });
     }
 
-    let instrumentation_library =
-        generic_interface
-            .as_ref()
-            .map(|(generic_import, generic_export)| {
-                function_application::instrument::<InstrumentationLanguage>(
+    let instrumentation_library = stack_library.map(
+        |StackLibrary {
+             library,
+             signature_import_links,
+         }| {
+            if let Some((generic_import, generic_export)) = generic_interface {
+                function_application::instrument(
                     &mut module,
-                    &target_indices_including_imports,
+                    &generic_apply_targets,
+                    &signature_import_links,
                     generic_import,
                     generic_export,
-                )
-            });
+                );
+            }
+            library
+        },
+    );
 
-    memory::inject_memory_loads(&mut module);
-    memory::inject_memory_stores(&mut module);
-    memory::inject_memory_grow(&mut module);
-    memory::inject_memory_size(&mut module);
+    memory::inject_memory_loads(&mut module, input_memories);
+    memory::inject_memory_stores(&mut module, input_memories);
+    memory::inject_memory_grow(&mut module, input_memories);
+    memory::inject_memory_size(&mut module, input_memories);
+
+    if *custom_sections == CustomSections::Preserve {
+        custom_sections::preserve(&mut module, first_injected_function);
+    }
 
     Ok(Instrumented {
         module: module
             .to_bytes()
             .map_err(InstrumentationError::EncodeError)?,
         instrumentation_library,
+        basic_blocks,
+        manifest,
     })
 }
 
-fn uses_reference_types(f: &Function) -> bool {
-    for ty_ in f.type_.inputs() {
-        match ty_ {
-            ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 => continue,
-            ValType::Ref(_) => return true,
+/// Only transforms the functions in `selection` and the sites `pointcut` selects
+struct Restricted {
+    selection: Option<TargetSelection>,
+    pointcut: Option<Pointcut>,
+    target: Box<dyn TransformationStrategy>,
+}
+
+impl TransformationStrategy for Restricted {
+    fn transform(
+        &self,
+        function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let is_selected = self
+            .selection
+            .as_ref()
+            .is_none_or(|selection| selection.contains(function_index.to_u32()));
+        match &self.pointcut {
+            _ if !is_selected => high_level_body.clone(),
+            None => self
+                .target
+                .transform(function_index, high_level_body, module, function),
+            // The sites the pointcut rejects look like instrumentation to the target
+            Some(pointcut) => {
+                let masked = HighLevelBody(pointcut::mask(body, pointcut));
+                let HighLevelBody(transformed) =
+                    self.target
+                        .transform(function_index, &masked, module, function);
+                HighLevelBody(pointcut::unmask(transformed))
+            }
         }
     }
-    for ty_ in f.type_.results() {
-        match ty_ {
-            ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 => continue,
-            ValType::Ref(_) => return true,
-        }
+}
+
+fn restrict(
+    selection: Option<TargetSelection>,
+    pointcut: Option<Pointcut>,
+    target: Box<dyn TransformationStrategy>,
+) -> Box<dyn TransformationStrategy> {
+    if selection.is_none() && pointcut.is_none() {
+        return target;
     }
-    false
+    Box::new(Restricted {
+        selection,
+        pointcut,
+        target,
+    })
+}
+
+/// Installs `getter`, without which the trap `required_by` cannot be instrumented
+fn install_required(
+    module: &mut Module,
+    getter: &Option<WasmExport>,
+    getter_name: &'static str,
+    required_by: &WasmExport,
+) -> Result<Idx<Function>, InstrumentationError> {
+    let getter = getter
+        .as_ref()
+        .ok_or_else(|| InstrumentationError::MissingTrap {
+            missing: getter_name,
+            required_by: required_by.name.clone(),
+        })?;
+    Ok(module.install(getter))
 }
 
 trait Instrumentable {
@@ -456,43 +1015,21 @@ impl FunctionTypeConvertible for WasmImport {
     }
 }
 
-pub trait TransformationStrategy {
-    fn transform(&self, high_level_body: &HighLevelBody, module: &mut Module) -> HighLevelBody;
+/// Transforms the body of the function at `function_index`. The module is shared by every
+/// function, which are transformed in parallel, new locals are added to `function`, a copy
+/// of the function.
+pub trait TransformationStrategy: Sync {
+    fn transform(
+        &self,
+        function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody;
 }
 
-#[cfg(test)]
-mod tests {
-    use wasabi_wasm::RefType::{ExternRef, FuncRef};
-    use wasabi_wasm::ValType::{self, Ref, F32, F64, I32, I64};
-    use wasabi_wasm::{Code, Function, FunctionType};
-
-    use super::uses_reference_types;
-
-    #[test]
-    fn test_uses_reference_types() {
-        let assertions: &[(&[ValType], &[ValType], bool)] = &[
-            (&[], &[], false),
-            (&[F32, F64], &[I32, I64], false),
-            (&[I32, I32], &[], false),
-            (&[], &[I32, I32], false),
-            (&[Ref(FuncRef)], &[], true),
-            (&[], &[Ref(FuncRef)], true),
-            (&[Ref(ExternRef)], &[], true),
-            (&[], &[Ref(ExternRef)], true),
-            (&[F32, F32, F32, Ref(FuncRef)], &[], true),
-            (&[], &[F64, F64, F64, Ref(FuncRef)], true),
-            (&[I32, I32, I32, Ref(FuncRef)], &[], true),
-            (&[], &[I64, I64, I64, Ref(FuncRef)], true),
-            (
-                &[I32, I32, I32, Ref(ExternRef)],
-                &[I64, I64, I64, Ref(ExternRef)],
-                true,
-            ),
-        ];
-        for (inputs, results, uses_reference) in assertions {
-            let ft = FunctionType::new(inputs, results);
-            let fc = Function::new(ft, Code::new(), vec![]);
-            assert_eq!(uses_reference_types(&fc), *uses_reference)
-        }
-    }
+/// A strategy that instruments single instructions, independent of the rest of the body
+pub trait InstructionStrategy: Sync {
+    /// Pushes the instrumented `typed_instr` onto `result`, returns whether it was instrumented
+    fn instrument(&self, typed_instr: &TypedHighLevelInstr, result: &mut BodyInner) -> bool;
 }
@@ -0,0 +1,278 @@
+use crate::parse_nesting::{
+    BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
+};
+use crate::pointcut::Pointcut;
+use crate::TargetSelection;
+use wasabi_wasm::{Function, Idx, Module};
+
+use super::{restrict, InstructionStrategy, TransformationStrategy};
+
+// Most hooks instrument single instructions. Rather than have each of them
+// rebuild every body, consecutive instruction strategies are applied in one
+// traversal: every instruction is handed to each of them in turn, exactly as
+// if they were applied one after the other. Strategies that depend on the
+// surrounding body, or extend the module, still transform the whole body.
+
+/// How a target instruments a function body
+pub enum Strategy {
+    Body(Box<dyn TransformationStrategy>),
+    Instruction(Box<dyn InstructionStrategy>),
+}
+
+/// A strategy, restricted to the functions & sites its hook selects
+pub struct Selected {
+    pub selection: Option<TargetSelection>,
+    pub pointcut: Option<Pointcut>,
+    pub strategy: Strategy,
+}
+
+/// Combines every run of consecutive instruction strategies into a single pass
+pub fn fuse(targets: Vec<Selected>) -> Vec<Box<dyn TransformationStrategy>> {
+    let mut passes: Vec<Box<dyn TransformationStrategy>> = Vec::new();
+    let mut run: Vec<InstructionTarget> = Vec::new();
+
+    for Selected {
+        selection,
+        pointcut,
+        strategy,
+    } in targets
+    {
+        match strategy {
+            Strategy::Instruction(strategy) => run.push(InstructionTarget {
+                selection,
+                pointcut,
+                strategy,
+            }),
+            Strategy::Body(target) => {
+                if !run.is_empty() {
+                    passes.push(Box::new(SinglePass(std::mem::take(&mut run))));
+                }
+                passes.push(restrict(selection, pointcut, target));
+            }
+        }
+    }
+    if !run.is_empty() {
+        passes.push(Box::new(SinglePass(run)));
+    }
+    passes
+}
+
+/// Gives every instruction strategy a traversal of its own, as if none were fused
+pub fn separate(targets: Vec<Selected>) -> Vec<Box<dyn TransformationStrategy>> {
+    targets
+        .into_iter()
+        .flat_map(|target| fuse(vec![target]))
+        .collect()
+}
+
+struct InstructionTarget {
+    selection: Option<TargetSelection>,
+    pointcut: Option<Pointcut>,
+    strategy: Box<dyn InstructionStrategy>,
+}
+
+impl InstructionTarget {
+    fn selects_function(&self, funct_index: u32) -> bool {
+        self.selection
+            .as_ref()
+            .is_none_or(|selection| selection.contains(funct_index))
+    }
+
+    /// Whether the site at `typed_instr`, nested in the constructs at
+    /// instruction indices `enclosing`, is selected
+    fn selects(&self, typed_instr: &TypedHighLevelInstr, enclosing: &[usize]) -> bool {
+        self.pointcut
+            .as_ref()
+            .is_none_or(|pointcut| pointcut.selects(typed_instr, enclosing))
+    }
+}
+
+/// Instruction strategies, in order of application
+struct SinglePass(Vec<InstructionTarget>);
+
+impl TransformationStrategy for SinglePass {
+    fn transform(
+        &self,
+        function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        _function: &mut Function,
+    ) -> HighLevelBody {
+        let HighLevelBody(body) = high_level_body;
+        let Self(targets) = self;
+        let targets: Vec<&InstructionTarget> = targets
+            .iter()
+            .filter(|target| target.selects_function(function_index.to_u32()))
+            .collect();
+        if targets.is_empty() {
+            return high_level_body.clone();
+        }
+        HighLevelBody(transform(body, &targets, &mut Vec::new()))
+    }
+}
+
+fn transform(
+    body: &BodyInner,
+    targets: &[&InstructionTarget],
+    enclosing: &mut Vec<usize>,
+) -> BodyInner {
+    let mut result = Vec::with_capacity(body.len());
+
+    for typed_instr in body {
+        match &typed_instr.instr {
+            // DEFAULT TRAVERSAL
+            Instr::Block(..) | Instr::Loop(..) | Instr::If(..) => {
+                if typed_instr.is_original() {
+                    enclosing.push(typed_instr.instr_index);
+                }
+                let instr = match &typed_instr.instr {
+                    Instr::Block(type_, body) => {
+                        Instr::Block(*type_, transform(body, targets, enclosing))
+                    }
+                    Instr::Loop(type_, body) => {
+                        Instr::Loop(*type_, transform(body, targets, enclosing))
+                    }
+                    Instr::If(type_, then, else_) => {
+                        let then = transform(then, targets, enclosing);
+                        let else_ = else_
+                            .as_ref()
+                            .map(|else_| transform(else_, targets, enclosing));
+                        Instr::If(*type_, then, else_)
+                    }
+                    _ => unreachable!("only blocks, loops & ifs nest"),
+                };
+                if typed_instr.is_original() {
+                    enclosing.pop();
+                }
+                result.push(typed_instr.place_untouched(instr));
+            }
+            _ if typed_instr.is_uninstrumented() => {
+                result.extend(instrument(typed_instr, targets, enclosing));
+            }
+            _ => result.push(typed_instr.clone()),
+        }
+    }
+    result
+}
+
+/// Hands `typed_instr` to each target in turn, every target instruments the
+/// original instructions the targets before it placed
+fn instrument(
+    typed_instr: &TypedHighLevelInstr,
+    targets: &[&InstructionTarget],
+    enclosing: &[usize],
+) -> BodyInner {
+    let mut placed = vec![typed_instr.clone()];
+    for target in targets {
+        let mut instrumented = Vec::with_capacity(placed.len());
+        for typed_instr in placed {
+            let is_instrumented = typed_instr.is_uninstrumented()
+                && target.selects(&typed_instr, enclosing)
+                && target.strategy.instrument(&typed_instr, &mut instrumented);
+            if !is_instrumented {
+                instrumented.push(typed_instr);
+            }
+        }
+        placed = instrumented;
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::instrument::function_call_indirect::Target::{Post as CallPost, Pre as CallPre};
+    use crate::instrument::memory::Target::LocalGetI32;
+    use crate::instrument::simple_operations::Target::{BinaryI32I32toI32, ConstI32};
+    use wasabi_wasm::Idx;
+
+    type Both = (
+        Box<dyn TransformationStrategy>,
+        Box<dyn InstructionStrategy>,
+    );
+
+    fn both<T: TransformationStrategy + InstructionStrategy + Copy + 'static>(target: T) -> Both {
+        (Box::new(target), Box::new(target))
+    }
+
+    fn targets() -> Vec<(Option<TargetSelection>, Option<Pointcut>, Both)> {
+        let trap = |index: usize| Idx::<Function>::from(index);
+        vec![
+            (None, None, both(CallPre(trap(10)))),
+            (None, None, both(CallPost(trap(11)))),
+            (
+                None,
+                Some(Pointcut::Within {
+                    function_index: 1,
+                    instr_index: 2,
+                }),
+                both(ConstI32(trap(12))),
+            ),
+            (
+                Some(TargetSelection::Indices(HashSet::from([1]))),
+                Some(Pointcut::Opcodes(HashSet::from(["i32.add".to_string()]))),
+                both(BinaryI32I32toI32(trap(13))),
+            ),
+            (None, None, both(LocalGetI32(trap(14)))),
+        ]
+    }
+
+    #[test]
+    fn single_pass_equals_applying_targets_one_by_one() {
+        let wasm_bytes = wat::parse_str(
+            r#"
+            (module
+                (func $callee (param i32) (result i32)
+                    local.get 0)
+                (func $main (export "main") (param i32) (result i32)
+                    i32.const 1
+                    local.get 0
+                    block (result i32)
+                        i32.const 2
+                        call $callee
+                        local.get 0
+                        i32.add
+                        i32.const 3
+                        i32.mul
+                    end
+                    i32.add
+                    call $callee
+                    i32.add))"#,
+        )
+        .unwrap();
+        let (module, _, _) = Module::from_bytes(&wasm_bytes).unwrap();
+
+        for index in [0_u32, 1] {
+            let index = index.into();
+            let function = module.function(index);
+            let code = function.code().unwrap();
+            let high_level_body: HighLevelBody =
+                (&module, function, code, &index).try_into().unwrap();
+
+            let one_by_one = targets()
+                .into_iter()
+                .map(|(selection, pointcut, (target, _))| restrict(selection, pointcut, target))
+                .fold(high_level_body.clone(), |transformed, target| {
+                    target.transform(index, &transformed, &module, &mut function.clone())
+                });
+            let single_pass = fuse(
+                targets()
+                    .into_iter()
+                    .map(|(selection, pointcut, (_, strategy))| Selected {
+                        selection,
+                        pointcut,
+                        strategy: Strategy::Instruction(strategy),
+                    })
+                    .collect(),
+            )
+            .into_iter()
+            .fold(high_level_body, |transformed, target| {
+                target.transform(index, &transformed, &module, &mut function.clone())
+            });
+
+            assert_eq!(single_pass, one_by_one);
+        }
+    }
+}
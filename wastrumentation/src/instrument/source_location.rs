@@ -0,0 +1,233 @@
+use std::collections::BTreeSet;
+
+use crate::source_map::SourceMap;
+use wasabi_wasm::Instr::{Binary, Block, Br, BrIf, Call, Const, End, Global, Load, Local, Loop};
+use wasabi_wasm::{
+    BinaryOp, Data, Function, FunctionType, GlobalOp, Idx, Limits, LoadOp, LocalOp, Memarg, Memory,
+    Module, Mutability, Val, ValType,
+};
+
+// The source location of every instrumented site is streamed into the analysis,
+// where it is kept in a location table. The paths of the source files are
+// streamed byte by byte first, the sites then refer to a file by its index.
+// This happens once, when the first instrumented function is entered.
+//
+// The locations are a data segment of a dedicated memory, which the
+// registration function walks. It holds, in order:
+// - every site as five little-endian u32: its file index, line, column,
+//   function index & instruction index
+// - the end address of the path of every file, as a little-endian u32
+// - the paths of the files, back to back
+
+/// Export name of the memory holding the source locations
+pub const EXPORT_NAME_SOURCE_LOCATIONS: &str = "__wastrumentation_source_locations";
+
+const SITE_ENTRY_SIZE: usize = 20;
+const FILE_ENTRY_SIZE: usize = 4;
+const PAGE_SIZE: usize = 65536;
+
+/// The source locations of the sites, laid out as described above
+struct LocationTable {
+    bytes: Vec<u8>,
+    sites_end: usize,
+    file_ends: usize,
+    file_count: usize,
+}
+
+impl LocationTable {
+    /// Lays out `locations`, the file index, line & column of a site by its
+    /// function & instruction index, which refer to `files`
+    fn new(
+        files: &[String],
+        locations: impl IntoIterator<Item = ((u32, usize), (usize, u32, u32))>,
+    ) -> Self {
+        let mut bytes = Vec::new();
+        for ((funct_index, instr_index), (file_index, line, column)) in locations {
+            for field in [
+                u32::try_from(file_index).unwrap(),
+                line,
+                column,
+                funct_index,
+                u32::try_from(instr_index).unwrap(),
+            ] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        let sites_end = bytes.len();
+
+        let file_ends = sites_end;
+        let mut path_end = file_ends + files.len() * FILE_ENTRY_SIZE;
+        for path in files {
+            path_end += path.len();
+            bytes.extend_from_slice(&u32::try_from(path_end).unwrap().to_le_bytes());
+        }
+        for path in files {
+            bytes.extend_from_slice(path.as_bytes());
+        }
+
+        Self {
+            bytes,
+            sites_end,
+            file_ends,
+            file_count: files.len(),
+        }
+    }
+}
+
+/// Installs the source location of every site in `sites` as a data segment and
+/// generates a function that streams them into the analysis on its first invocation.
+pub fn install_location_registration(
+    module: &mut Module,
+    source_map: &SourceMap,
+    sites: &BTreeSet<(u32, usize)>,
+    source_location_trap_idx: Idx<Function>,
+    source_file_byte_trap_idx: Idx<Function>,
+) -> Idx<Function> {
+    let LocationTable {
+        bytes,
+        sites_end,
+        file_ends,
+        file_count,
+    } = LocationTable::new(
+        source_map.files(),
+        sites.iter().filter_map(|(funct_index, instr_index)| {
+            let location = source_map.lookup_indexed(*funct_index, *instr_index)?;
+            Some(((*funct_index, *instr_index), location))
+        }),
+    );
+    let paths_start = file_ends + file_count * FILE_ENTRY_SIZE;
+
+    let pages = u32::try_from(bytes.len().div_ceil(PAGE_SIZE).max(1)).unwrap();
+    let table: Idx<Memory> = module.memories.len().into();
+    module.memories.push(Memory {
+        limits: Limits {
+            initial_size: pages,
+            max_size: Some(pages),
+        },
+        import: None,
+        data: vec![Data {
+            offset: vec![Const(Val::I32(0)), End],
+            bytes,
+        }],
+        export: vec![EXPORT_NAME_SOURCE_LOCATIONS.to_string()],
+    });
+
+    let registered =
+        module.add_global(ValType::I32, Mutability::Mut, vec![Const(Val::I32(0)), End]);
+
+    let constant = |value: usize| Const(Val::I32(i32::try_from(value).unwrap()));
+    let memarg = |alignment_exp, offset| Memarg {
+        alignment_exp,
+        offset,
+        memory: table,
+    };
+    // The index of the file, the address of the site or path byte
+    let (file, address) = (Idx::from(0_u32), Idx::from(1_u32));
+
+    let body = vec![
+        // Only register once
+        Global(GlobalOp::Get, registered),
+        BrIf(0_usize.into()),
+        Const(Val::I32(1)),
+        Global(GlobalOp::Set, registered),
+        // Every byte of every path, file by file
+        constant(paths_start),
+        Local(LocalOp::Set, address),
+        Block(FunctionType::empty()),
+        Loop(FunctionType::empty()),
+        Local(LocalOp::Get, file),
+        constant(file_count),
+        Binary(BinaryOp::I32GeU),
+        BrIf(1_usize.into()),
+        Block(FunctionType::empty()),
+        Loop(FunctionType::empty()),
+        // Up to the end of the path of the file
+        Local(LocalOp::Get, address),
+        Local(LocalOp::Get, file),
+        constant(FILE_ENTRY_SIZE.trailing_zeros() as usize),
+        Binary(BinaryOp::I32Shl),
+        Load(
+            LoadOp::I32Load,
+            memarg(2, u32::try_from(file_ends).unwrap()),
+        ),
+        Binary(BinaryOp::I32GeU),
+        BrIf(1_usize.into()),
+        Local(LocalOp::Get, file),
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I32Load8U, memarg(0, 0)),
+        Call(source_file_byte_trap_idx),
+        Local(LocalOp::Get, address),
+        Const(Val::I32(1)),
+        Binary(BinaryOp::I32Add),
+        Local(LocalOp::Set, address),
+        Br(0_usize.into()),
+        End,
+        End,
+        Local(LocalOp::Get, file),
+        Const(Val::I32(1)),
+        Binary(BinaryOp::I32Add),
+        Local(LocalOp::Set, file),
+        Br(0_usize.into()),
+        End,
+        End,
+        // Every site
+        Const(Val::I32(0)),
+        Local(LocalOp::Set, address),
+        Block(FunctionType::empty()),
+        Loop(FunctionType::empty()),
+        Local(LocalOp::Get, address),
+        constant(sites_end),
+        Binary(BinaryOp::I32GeU),
+        BrIf(1_usize.into()),
+        // Lines & columns are reinterpreted as unsigned by the analysis
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I32Load, memarg(2, 0)),
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I32Load, memarg(2, 4)),
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I32Load, memarg(2, 8)),
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I64Load32U, memarg(2, 12)),
+        Local(LocalOp::Get, address),
+        Load(LoadOp::I64Load32U, memarg(2, 16)),
+        Call(source_location_trap_idx),
+        Local(LocalOp::Get, address),
+        constant(SITE_ENTRY_SIZE),
+        Binary(BinaryOp::I32Add),
+        Local(LocalOp::Set, address),
+        Br(0_usize.into()),
+        End,
+        End,
+        End,
+    ];
+
+    module.add_function(
+        FunctionType::new(&[], &[]),
+        vec![ValType::I32, ValType::I32],
+        body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sites_precede_the_paths_they_refer_to() {
+        let files = ["a.c".to_string(), "bc.c".to_string()];
+        let LocationTable {
+            bytes,
+            sites_end,
+            file_ends,
+            file_count,
+        } = LocationTable::new(&files, [((0, 0), (0, 1, 0)), ((1, 3), (1, 7, 2))]);
+
+        assert_eq!((sites_end, file_ends, file_count), (40, 40, 2));
+        let words: Vec<u32> = bytes[..48]
+            .chunks(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        assert_eq!(words, vec![0, 1, 0, 0, 0, 1, 7, 2, 1, 3, 51, 55]);
+        assert_eq!(&bytes[48..], b"a.cbc.c");
+    }
+}
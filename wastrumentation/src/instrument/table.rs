@@ -2,7 +2,7 @@ use super::TransformationStrategy;
 use crate::parse_nesting::{
     BodyInner, HighLevelBody, HighLevelInstr as Instr, TypedHighLevelInstr,
 };
-use wasabi_wasm::{Function, GlobalOp, Idx, Module, Mutability, RefType, Val, ValType};
+use wasabi_wasm::{Function, Idx, LocalOp, Module, RefType, Val, ValType};
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Target {
@@ -30,32 +30,21 @@ pub enum Target {
 }
 
 impl TransformationStrategy for Target {
-    fn transform(&self, high_level_body: &HighLevelBody, module: &mut Module) -> HighLevelBody {
+    fn transform(
+        &self,
+        _function_index: Idx<Function>,
+        high_level_body: &HighLevelBody,
+        _module: &Module,
+        function: &mut Function,
+    ) -> HighLevelBody {
         let HighLevelBody(body) = high_level_body;
-        let transformed_body = transform(body, *self, module);
+        let transformed_body = transform(body, *self, function);
         HighLevelBody(transformed_body)
     }
 }
 
-fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner {
+fn transform(body: &BodyInner, target: Target, function: &mut Function) -> BodyInner {
     let mut result = Vec::new();
-    let global_ref_store = module.add_global(
-        ValType::Ref(RefType::FuncRef),
-        Mutability::Mut,
-        vec![
-            wasabi_wasm::Instr::RefNull(RefType::FuncRef),
-            wasabi_wasm::Instr::End,
-        ],
-    );
-    let global_i32_store = module.add_global(
-        ValType::I32,
-        Mutability::Mut,
-        vec![
-            wasabi_wasm::Instr::Const(Val::I32(0)),
-            wasabi_wasm::Instr::End,
-        ],
-    );
-
     for typed_instr @ TypedHighLevelInstr { instr, .. } in body {
         if typed_instr.is_uninstrumented() {
             match (target, instr) {
@@ -112,10 +101,11 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
 
                 // table.set x: [i, ref] -> []
                 (Target::TableSet(trap_idx), Instr::TableSet(table_idx)) => {
+                    let ref_store = function.add_fresh_local(ValType::Ref(RefType::FuncRef));
                     result.extend_from_slice(&[
                         // Stack: [i:I32, ref]
-                        // temporarily store ref in global ref store
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Set, global_ref_store)),
+                        // temporarily store ref in a local
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Set, ref_store)),
                         // Stack: [i:I32]
                         typed_instr.instrument_with(Instr::Const(Val::I32(
                             i32::try_from(table_idx.to_u32()).unwrap(),
@@ -124,10 +114,8 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     ]);
                     result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // Stack: [i:I32]
-                    // retrieve ref from global ref store
-                    result.push(
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Get, global_ref_store)),
-                    );
+                    // retrieve ref from the local
+                    result.push(typed_instr.instrument_with(Instr::Local(LocalOp::Get, ref_store)));
                     // Stack: [i:I32, ref]
                     result.push(typed_instr.place_original(instr.clone()));
                     // Stack: []
@@ -168,13 +156,15 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
 
                 // table.fill x: [i, val, n] -> []
                 (Target::TableFill(trap_idx), Instr::TableFill(table_idx)) => {
+                    let ref_store = function.add_fresh_local(ValType::Ref(RefType::FuncRef));
+                    let i32_store = function.add_fresh_local(ValType::I32);
                     result.extend_from_slice(&[
                         // Stack: [i:I32, val, n:I32]
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Set, global_i32_store)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Set, i32_store)),
                         // Stack: [i:I32, val]
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Set, global_ref_store)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Set, ref_store)),
                         // Stack: [i:I32]
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Get, global_i32_store)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Get, i32_store)),
                         // Stack: [i:I32, n:I32]
                         typed_instr.instrument_with(Instr::Const(Val::I32(
                             i32::try_from(table_idx.to_u32()).unwrap(),
@@ -184,9 +174,9 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
                     result.extend_from_slice(&typed_instr.to_trap_call(&trap_idx));
                     // Stack: [i_new:I32]
                     result.extend_from_slice(&[
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Get, global_ref_store)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Get, ref_store)),
                         // Stack: [i_new:I32, val]
-                        typed_instr.instrument_with(Instr::Global(GlobalOp::Get, global_i32_store)),
+                        typed_instr.instrument_with(Instr::Local(LocalOp::Get, i32_store)),
                         // Stack: [i_new:I32, val, n:I32]
                     ]);
                     result.push(typed_instr.place_original(instr.clone()));
@@ -299,27 +289,27 @@ fn transform(body: &BodyInner, target: Target, module: &mut Module) -> BodyInner
             (target, Instr::If(type_, then, None)) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target, module),
+                    transform(then, target, function),
                     None,
                 )));
             }
             (target, Instr::If(type_, then, Some(else_))) => {
                 result.push(typed_instr.place_untouched(Instr::If(
                     *type_,
-                    transform(then, target, module),
-                    Some(transform(else_, target, module)),
+                    transform(then, target, function),
+                    Some(transform(else_, target, function)),
                 )))
             }
             (target, Instr::Loop(type_, body)) => {
                 result.push(
                     typed_instr
-                        .place_untouched(Instr::Loop(*type_, transform(body, target, module))),
+                        .place_untouched(Instr::Loop(*type_, transform(body, target, function))),
                 );
             }
             (target, Instr::Block(type_, body)) => {
                 result.push(
                     typed_instr
-                        .place_untouched(Instr::Block(*type_, transform(body, target, module))),
+                        .place_untouched(Instr::Block(*type_, transform(body, target, function))),
                 );
             }
             (_, instr) => result.push(typed_instr.place_untouched(instr.clone())),
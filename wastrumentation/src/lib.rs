@@ -1,28 +1,42 @@
 pub mod analysis;
+mod code_offsets;
 pub mod compiler;
 pub mod error;
+pub mod function_pattern;
 mod instrument;
+pub mod manifest;
 pub mod parse_nesting;
+pub mod pointcut;
+pub mod source_map;
 mod stack_library;
 pub mod wasm_constructs;
 
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use crate::instrument::Instrumented;
 use analysis::ProcessedAnalysis;
-use compiler::{Compiles, DefaultCompilerOptions, LibGeneratable, SourceCodeBound, WasmModule};
+use compiler::{
+    CompileCache, Compiles, DefaultCompilerOptions, LibGeneratable, SourceCodeBound, WasmModule,
+};
+pub use function_pattern::FunctionPattern;
+pub use instrument::basic_block::BasicBlock;
 use instrument::function_application::INSTRUMENTATION_ANALYSIS_MODULE;
 use instrument::function_application::INSTRUMENTATION_INSTRUMENTED_MODULE;
 use instrument::function_application::INSTRUMENTATION_STACK_MODULE;
+pub use manifest::Manifest;
+pub use pointcut::Pointcut;
+pub use source_map::{SourceLocation, SourceMap};
 pub use stack_library::ModuleLinkedStackHooks;
 use wasm_merge::options::BulkMemoryOpt;
 use wasm_merge::options::{
-    BulkMemory, Multimemory, NoValidate, ReferenceTypes, RenameExportConflicts,
+    BulkMemory, DebugInfo, Multimemory, NoValidate, ReferenceTypes, RenameExportConflicts,
 };
 use wasm_merge::{InputModule, MergeOptions};
 
-use crate::error::Error;
+use crate::error::{Error, InstrumentationError};
 
 #[derive(Clone)]
 pub struct Wastrumenter<
@@ -40,12 +54,139 @@ pub struct Wastrumenter<
     instrumentation_language: PhantomData<InstrumentationLanguage>,
     analysis_language_compiler: Box<AnalysisLanguageCompiler>,
     analysis_language: PhantomData<AnalysisLanguage>,
+    compile_cache: Option<CompileCache>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Configuration {
     pub target_indices: Option<Vec<u32>>,
+    /// Selects target functions by name, in addition to `target_indices`.
+    /// Each pattern has to match at least one function.
+    pub target_names: Option<Vec<FunctionPattern>>,
     pub primary_selection: Option<PrimaryTarget>,
+    /// Also call the block & loop post traps of every construct
+    /// a branch or return leaves, innermost first. Each post trap is only
+    /// unwound for the functions & constructs its own hook selects.
+    pub unwinding: bool,
+    /// Inline an AFL-style edge coverage bitmap update at the start of every basic block
+    pub coverage: bool,
+    /// Restricts hooks, by the name of their trap, to a selection of functions.
+    /// Hooks without a selection target `target_indices`.
+    pub hook_targets: HashMap<String, TargetSelection>,
+    /// Restricts instruction hooks, by the name of their trap, to the sites a pointcut selects
+    pub hook_pointcuts: HashMap<String, Pointcut>,
+    /// What becomes of the name section, DWARF & other custom sections
+    pub custom_sections: CustomSections,
+    /// Report the DWARF source location of every instrumented site to the analysis
+    pub source_locations: bool,
+    /// Record every injected trap call, see [`Wastrumenter::wastrument_with_side_tables`]
+    pub manifest: bool,
+    /// Have trap calls pass a dense i32 site id rather than two i64 constants,
+    /// resolved through a site table in the instrumented module
+    pub compact_locations: bool,
+    /// Have every hook traverse the function bodies on its own, rather than have
+    /// consecutive instruction hooks share a traversal. Yields the same module,
+    /// slower, to check & measure the shared traversal against.
+    pub separate_passes: bool,
+}
+
+/// What instrumenting yields besides the instrumented module
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SideTables {
+    /// Every injected trap call, when `configuration.manifest` is set
+    pub manifest: Option<Manifest>,
+    /// The blocks the basic block enter trap & the coverage bitmap report, by id
+    pub basic_blocks: Vec<BasicBlock>,
+}
+
+/// What becomes of the custom sections of the input program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CustomSections {
+    /// Left to the merge, which drops the name section & DWARF
+    #[default]
+    Strip,
+    /// Keeps the name section, naming the injected functions `__wastr_<name>`,
+    /// and every custom section but DWARF, whose code offsets instrumentation invalidates
+    Preserve,
+}
+
+/// A selection of functions, by index
+#[derive(Clone)]
+pub enum TargetSelection {
+    Indices(HashSet<u32>),
+    Predicate(Arc<dyn Fn(u32) -> bool + Send + Sync>),
+}
+
+impl TargetSelection {
+    pub fn contains(&self, function_index: u32) -> bool {
+        match self {
+            TargetSelection::Indices(indices) => indices.contains(&function_index),
+            TargetSelection::Predicate(predicate) => predicate(function_index),
+        }
+    }
+}
+
+impl Debug for TargetSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Indices(indices) => f.debug_tuple("Indices").field(indices).finish(),
+            Self::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+impl Configuration {
+    /// Folds the functions `target_names` selects in `module` into `target_indices`
+    pub(crate) fn with_resolved_names(
+        &self,
+        module: &wasabi_wasm::Module,
+    ) -> Result<Self, InstrumentationError> {
+        let Some(target_names) = &self.target_names else {
+            return Ok(self.clone());
+        };
+        let mut target_indices: Vec<u32> = function_pattern::resolve(target_names, module)?
+            .into_iter()
+            .chain(self.target_indices.iter().flatten().copied())
+            .collect();
+        target_indices.sort_unstable();
+        target_indices.dedup();
+        Ok(Self {
+            target_indices: Some(target_indices),
+            target_names: None,
+            ..self.clone()
+        })
+    }
+
+    /// Whether any hook targets the function at `function_index`
+    pub(crate) fn is_target(&self, function_index: u32) -> bool {
+        self.target_indices
+            .as_ref()
+            .is_none_or(|indices| indices.contains(&function_index))
+            || self
+                .hook_targets
+                .values()
+                .any(|selection| selection.contains(function_index))
+    }
+
+    /// The functions the hook with trap `trap_name` is restricted to,
+    /// `None` when it targets every function that is instrumented.
+    pub(crate) fn hook_selection(&self, trap_name: &str) -> Option<TargetSelection> {
+        match self.hook_targets.get(trap_name) {
+            Some(selection) => Some(selection.clone()),
+            None => self.default_selection(),
+        }
+    }
+
+    /// The functions hooks without a selection are restricted to
+    pub(crate) fn default_selection(&self) -> Option<TargetSelection> {
+        if self.hook_targets.is_empty() {
+            // Only the functions in `target_indices` are instrumented
+            return None;
+        }
+        self.target_indices
+            .as_ref()
+            .map(|indices| TargetSelection::Indices(indices.iter().copied().collect()))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -82,6 +223,18 @@ where
             analysis_language_compiler,
             instrumentation_language: PhantomData,
             analysis_language: PhantomData,
+            compile_cache: None,
+        }
+    }
+
+    /// Caches the compiled analysis & instrumentation library in `compile_cache`,
+    /// compiling them only when their source code or compiler options change.
+    /// Only compilers that provide a cache key are cached, see [`Compiles::cache_key`].
+    #[must_use]
+    pub fn with_compile_cache(self, compile_cache: CompileCache) -> Self {
+        Self {
+            compile_cache: Some(compile_cache),
+            ..self
         }
     }
 
@@ -93,9 +246,25 @@ where
         analysis: ProcessedAnalysis<AnalysisLanguage>,
         configuration: &Configuration,
     ) -> Result<WasmModule, Error<AnalysisLanguage, InstrumentationLanguage>> {
+        self.wastrument_with_side_tables(input_program, analysis, configuration)
+            .map(|(module, _side_tables)| module)
+    }
+
+    /// Like [`Self::wastrument`], also yielding the side tables that map what
+    /// the analysis observes back onto the input program.
+    ///
+    /// # Errors
+    /// Errors upon failing to compile, instrument or merge.
+    pub fn wastrument_with_side_tables(
+        &self,
+        input_program: &[u8],
+        analysis: ProcessedAnalysis<AnalysisLanguage>,
+        configuration: &Configuration,
+    ) -> Result<(WasmModule, SideTables), Error<AnalysisLanguage, InstrumentationLanguage>> {
         let Configuration {
-            target_indices,
             primary_selection,
+            custom_sections,
+            ..
         } = configuration;
         // 1. Compile analysis
         let ProcessedAnalysis {
@@ -104,18 +273,22 @@ where
         } = analysis;
         let analysis_compiler_options =
             AnalysisLanguageCompiler::CompilerOptions::default_for(analysis_library);
-        let analysis_wasm = self
-            .analysis_language_compiler
-            .compile(&analysis_compiler_options)
-            .map_err(Error::CompilationErrorAnalysis)?;
+        let analysis_wasm = compiler::cache::compile(
+            self.compile_cache.as_ref(),
+            self.analysis_language_compiler.as_ref(),
+            &analysis_compiler_options,
+        )
+        .map_err(Error::CompilationErrorAnalysis)?;
         // 2. Instrument the input program
         let Instrumented {
             module: instrumented_input,
             instrumentation_library,
+            basic_blocks,
+            manifest,
         } = instrument::instrument::<InstrumentationLanguage>(
             input_program,
             &analysis_interface,
-            target_indices,
+            configuration,
         )
         .map_err(Error::InstrumentationError)?;
         // 3. Compile the instrumentation lib
@@ -123,9 +296,12 @@ where
             let instrumentation_compiler_options =
                 InstrumentationLanguageCompiler::CompilerOptions::default_for(library.content);
             Some(
-                self.instrumentation_language_compiler
-                    .compile(&instrumentation_compiler_options)
-                    .map_err(Error::CompilationErrorInstrumentation)?,
+                compiler::cache::compile(
+                    self.compile_cache.as_ref(),
+                    self.instrumentation_language_compiler.as_ref(),
+                    &instrumentation_compiler_options,
+                )
+                .map_err(Error::CompilationErrorInstrumentation)?,
             )
         } else {
             None
@@ -134,17 +310,23 @@ where
         // 4. Merge them all together
         let instrumented_input = Self::merge(
             primary_selection,
+            *custom_sections,
             &instrumented_input,
             &analysis_wasm,
             compiled_instrumentation_lib.as_deref(),
         )?;
 
         // 5. Yield expected result
-        Ok(instrumented_input)
+        let side_tables = SideTables {
+            manifest,
+            basic_blocks,
+        };
+        Ok((instrumented_input, side_tables))
     }
 
     fn merge(
         primary_selection: &Option<PrimaryTarget>,
+        custom_sections: CustomSections,
         instrumented_input: &[u8],
         compiled_analysis: &[u8],
         compiled_instrumentation_lib: Option<&[u8]>,
@@ -189,10 +371,17 @@ where
 
         let input_modules = input_modules.into_iter().flatten().collect();
 
+        // Without debug info, the merge drops the name section
+        let debug_info = match custom_sections {
+            CustomSections::Strip => DebugInfo::Disable,
+            CustomSections::Preserve => DebugInfo::Enable,
+        };
+
         let merge_options = MergeOptions {
             primary,
             input_modules,
             no_validation: NoValidate::Enable,
+            debug_info,
             rename_export_conflicts: RenameExportConflicts::Enable,
             multimemory: Multimemory::Enable,
             multivalue: wasm_merge::options::Multivalue::Enable,
@@ -202,6 +391,10 @@ where
             nontrapping_float_to_int: wasm_merge::options::NontrappingFloatToInt::Enable,
             ..Default::default()
         };
-        merge_options.merge().map_err(Error::MergeError)
+        #[cfg(feature = "binaryen")]
+        let merged = merge_options.merge_with_binaryen();
+        #[cfg(not(feature = "binaryen"))]
+        let merged = merge_options.merge();
+        merged.map_err(Error::MergeError)
     }
 }
@@ -0,0 +1,195 @@
+use serde::Serialize;
+use wasabi_wasm::{Function, Idx, ImportOrPresent, Module};
+
+use crate::code_offsets::CodeOffsets;
+use crate::instrument::compact_location::SiteTable;
+use crate::parse_nesting::{BodyInner, HighLevelInstr as Instr, TypedHighLevelInstr};
+use crate::pointcut::mnemonic;
+
+use crate::instrument::function_application::INSTRUMENTATION_ANALYSIS_MODULE;
+
+/// A record of every trap call instrumentation injected, in order of site, to
+/// join the events of an analysis with static information about their site offline.
+///
+/// Only trap calls at a site are recorded. The calls of `generic_apply`, which
+/// wraps whole functions, and the calls registering import names & source
+/// locations, which happen once, have no site and are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// A trap call, reported with `(funct_index, instr_index)` as its location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestEntry {
+    /// The name of the trap, which identifies the hook, e.g. `trap_load`
+    pub trap: String,
+    pub funct_index: u32,
+    /// The name-section name of the function, or else its first export
+    pub function_name: Option<String>,
+    pub instr_index: usize,
+    /// The mnemonic of the original instruction at the site, e.g. `i32.load`
+    pub opcode: Option<String>,
+    /// The offset of the original instruction, relative to the start of the code section
+    pub code_offset: Option<u64>,
+    pub operands: Operands,
+    /// The id the trap call passes in compact-location mode, instead of its location
+    pub site: Option<u32>,
+}
+
+/// The static operands of the original instruction at a site
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operands {
+    None,
+    Label {
+        label: u32,
+    },
+    LabelTable {
+        table: Vec<u32>,
+        default: u32,
+    },
+    Function {
+        function: u32,
+    },
+    Indirect {
+        table: u32,
+    },
+    Local {
+        local: u32,
+    },
+    Global {
+        global: u32,
+    },
+    Memarg {
+        offset: u32,
+        alignment_exp: u8,
+        memory: u32,
+    },
+    /// The constant, formatted as in the text format
+    Const {
+        value: String,
+    },
+}
+
+impl Manifest {
+    /// # Errors
+    /// When serialization fails.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// The trap calls in `body`, by the (function, instruction) index of their site
+pub(crate) fn trap_calls(
+    module: &Module,
+    body: &BodyInner,
+    calls: &mut Vec<(u32, usize, Idx<Function>)>,
+) {
+    for typed_instr @ TypedHighLevelInstr {
+        funct_index,
+        instr_index,
+        instr,
+        ..
+    } in body
+    {
+        match instr {
+            Instr::Call(callee) if !typed_instr.is_original() && is_trap(module, *callee) => {
+                calls.push((*funct_index, *instr_index, *callee));
+            }
+            Instr::Block(_, body) | Instr::Loop(_, body) => trap_calls(module, body, calls),
+            Instr::If(_, then, else_) => {
+                trap_calls(module, then, calls);
+                if let Some(else_) = else_ {
+                    trap_calls(module, else_, calls);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn is_trap(module: &Module, function: Idx<Function>) -> bool {
+    matches!(
+        &module.function(function).code,
+        ImportOrPresent::Import(module_name, _) if module_name == INSTRUMENTATION_ANALYSIS_MODULE
+    )
+}
+
+/// Describes the trap calls, `module` still holds the original function bodies
+pub(crate) fn manifest(
+    module: &Module,
+    code_offsets: &CodeOffsets,
+    trap_calls: &[(u32, usize, Idx<Function>)],
+    site_table: Option<&SiteTable>,
+) -> Manifest {
+    let mut entries: Vec<ManifestEntry> = trap_calls
+        .iter()
+        .map(|(funct_index, instr_index, trap)| {
+            let function = module.function((*funct_index).into());
+            let original = function.code().and_then(|code| code.body.get(*instr_index));
+            let ImportOrPresent::Import(_, trap_name) = &module.function(*trap).code else {
+                unreachable!("traps are imported from the analysis")
+            };
+            ManifestEntry {
+                trap: trap_name.clone(),
+                funct_index: *funct_index,
+                function_name: function.name.clone().or(function.export.first().cloned()),
+                instr_index: *instr_index,
+                opcode: original.map(opcode),
+                code_offset: code_offsets.offset(*funct_index, *instr_index),
+                operands: original.map_or(Operands::None, operands),
+                site: site_table.and_then(|table| table.site(*funct_index, *instr_index)),
+            }
+        })
+        .collect();
+    // Functions are instrumented in no particular order
+    entries.sort_by_key(|entry| (entry.funct_index, entry.instr_index));
+    Manifest { entries }
+}
+
+fn opcode(instr: &wasabi_wasm::Instr) -> String {
+    match instr {
+        wasabi_wasm::Instr::Block(_) => "block".into(),
+        wasabi_wasm::Instr::Loop(_) => "loop".into(),
+        wasabi_wasm::Instr::If(_) => "if".into(),
+        wasabi_wasm::Instr::Else => "else".into(),
+        wasabi_wasm::Instr::End => "end".into(),
+        instr => Instr::try_from(instr.clone())
+            .map_or_else(|_| "unknown".into(), |instr| mnemonic(&instr).into()),
+    }
+}
+
+fn operands(instr: &wasabi_wasm::Instr) -> Operands {
+    use wasabi_wasm::Instr as I;
+    match instr {
+        I::Br(label) | I::BrIf(label) => Operands::Label {
+            label: label.to_u32(),
+        },
+        I::BrTable { table, default } => Operands::LabelTable {
+            table: table.iter().map(|label| label.to_u32()).collect(),
+            default: default.to_u32(),
+        },
+        I::Call(function) | I::RefFunc(function) => Operands::Function {
+            function: function.to_u32(),
+        },
+        I::CallIndirect(_, table) => Operands::Indirect {
+            table: table.to_u32(),
+        },
+        I::Local(_, local) => Operands::Local {
+            local: local.to_u32(),
+        },
+        I::Global(_, global) => Operands::Global {
+            global: global.to_u32(),
+        },
+        I::Load(_, memarg) | I::Store(_, memarg) => Operands::Memarg {
+            offset: memarg.offset,
+            alignment_exp: memarg.alignment_exp,
+            memory: memarg.memory.to_u32(),
+        },
+        I::Const(value) => Operands::Const {
+            value: value.to_string(),
+        },
+        _ => Operands::None,
+    }
+}
@@ -21,6 +21,8 @@ pub struct TypedHighLevelInstr {
     pub instr: Instr,
 
     instrumentation_instruction: bool,
+    // An original instruction hidden from the transformations by a pointcut
+    masked: bool,
 }
 
 impl TypedHighLevelInstr {
@@ -36,6 +38,7 @@ impl TypedHighLevelInstr {
             type_,
             instr,
             instrumentation_instruction: false,
+            masked: false,
         }
     }
 
@@ -68,6 +71,7 @@ impl TypedHighLevelInstr {
             type_: InferredInstructionType::Unreachable, // We will not infer the type, nor is this ever relevant!
 
             instrumentation_instruction: true,
+            masked: false,
         }
     }
 
@@ -83,6 +87,7 @@ impl TypedHighLevelInstr {
             type_: self.type_.clone(),
 
             instrumentation_instruction: self.instrumentation_instruction,
+            masked: self.masked,
         }
     }
 
@@ -95,12 +100,22 @@ impl TypedHighLevelInstr {
             type_: self.type_.clone(),
 
             instrumentation_instruction: self.instrumentation_instruction,
+            masked: self.masked,
         }
     }
 
     pub fn is_uninstrumented(&self) -> bool {
+        !self.instrumentation_instruction && !self.masked
+    }
+
+    /// Whether the instruction is part of the original body, masked or not
+    pub(crate) fn is_original(&self) -> bool {
         !self.instrumentation_instruction
     }
+
+    pub(crate) fn set_masked(&mut self, masked: bool) {
+        self.masked = masked;
+    }
 }
 
 /// Typed instructions with high-level body.
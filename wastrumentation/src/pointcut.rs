@@ -0,0 +1,422 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::error::InstrumentationError;
+use crate::parse_nesting::{BodyInner, HighLevelInstr as Instr, TypedHighLevelInstr};
+use wasabi_wasm::{BinaryOp, GlobalOp, LoadOp, LocalOp, StoreOp, UnaryOp, Val};
+
+/// Selects the instruction sites a hook instruments.
+///
+/// Sites are identified by their original instruction index, a pointcut is
+/// evaluated before any instrumentation is added to the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pointcut {
+    /// Instructions with one of the given mnemonics, e.g. `i32.div_s` or `local.get`
+    Opcodes(HashSet<String>),
+    /// Instructions of a function within a range of instruction indices
+    InstructionRange {
+        function_index: u32,
+        instructions: Range<usize>,
+    },
+    /// Instructions nested, at any depth, in the `block`, `loop` or `if` at an instruction index
+    Within {
+        function_index: u32,
+        instr_index: usize,
+    },
+    /// Sites selected by every pointcut
+    All(Vec<Pointcut>),
+    /// Sites selected by any pointcut
+    Any(Vec<Pointcut>),
+}
+
+impl Pointcut {
+    /// Instructions with one of the given mnemonics, fails on a mnemonic no instruction has
+    pub fn opcodes<S: Into<String>>(
+        mnemonics: impl IntoIterator<Item = S>,
+    ) -> Result<Self, InstrumentationError> {
+        let pointcut = Pointcut::Opcodes(mnemonics.into_iter().map(Into::into).collect());
+        pointcut.validate()?;
+        Ok(pointcut)
+    }
+
+    /// Fails on the first mnemonic, at any depth, no instruction has
+    pub fn validate(&self) -> Result<(), InstrumentationError> {
+        match self {
+            Pointcut::Opcodes(opcodes) => match opcodes.iter().find(|name| !is_mnemonic(name)) {
+                Some(mnemonic) => Err(InstrumentationError::UnknownMnemonic {
+                    mnemonic: mnemonic.clone(),
+                }),
+                None => Ok(()),
+            },
+            Pointcut::InstructionRange { .. } | Pointcut::Within { .. } => Ok(()),
+            Pointcut::All(pointcuts) | Pointcut::Any(pointcuts) => {
+                pointcuts.iter().try_for_each(Pointcut::validate)
+            }
+        }
+    }
+
+    /// Whether the site at `typed_instr`, nested in the constructs at
+    /// instruction indices `enclosing`, is selected
+    pub(crate) fn selects(&self, typed_instr: &TypedHighLevelInstr, enclosing: &[usize]) -> bool {
+        let TypedHighLevelInstr {
+            funct_index,
+            instr_index,
+            instr,
+            ..
+        } = typed_instr;
+        match self {
+            Pointcut::Opcodes(opcodes) => opcodes.contains(mnemonic(instr)),
+            Pointcut::InstructionRange {
+                function_index,
+                instructions,
+            } => funct_index == function_index && instructions.contains(instr_index),
+            Pointcut::Within {
+                function_index,
+                instr_index: construct_index,
+            } => funct_index == function_index && enclosing.contains(construct_index),
+            Pointcut::All(pointcuts) => pointcuts
+                .iter()
+                .all(|pointcut| pointcut.selects(typed_instr, enclosing)),
+            Pointcut::Any(pointcuts) => pointcuts
+                .iter()
+                .any(|pointcut| pointcut.selects(typed_instr, enclosing)),
+        }
+    }
+}
+
+/// Hides the original instructions the pointcut does not select from the transformations
+pub(crate) fn mask(body: &BodyInner, pointcut: &Pointcut) -> BodyInner {
+    mask_nested(body, pointcut, &mut Vec::new())
+}
+
+fn mask_nested(body: &BodyInner, pointcut: &Pointcut, enclosing: &mut Vec<usize>) -> BodyInner {
+    body.iter()
+        .map(|typed_instr| {
+            let mut masked = typed_instr.clone();
+            if typed_instr.is_original() {
+                masked.set_masked(!pointcut.selects(typed_instr, enclosing));
+                enclosing.push(typed_instr.instr_index);
+            }
+            match &mut masked.instr {
+                Instr::Block(_, body) | Instr::Loop(_, body) => {
+                    *body = mask_nested(body, pointcut, enclosing);
+                }
+                Instr::If(_, then, else_) => {
+                    *then = mask_nested(then, pointcut, enclosing);
+                    if let Some(else_) = else_ {
+                        *else_ = mask_nested(else_, pointcut, enclosing);
+                    }
+                }
+                _ => (),
+            }
+            if typed_instr.is_original() {
+                enclosing.pop();
+            }
+            masked
+        })
+        .collect()
+}
+
+/// Reveals all instructions hidden by `mask`
+pub(crate) fn unmask(body: BodyInner) -> BodyInner {
+    body.into_iter()
+        .map(|mut typed_instr| {
+            typed_instr.set_masked(false);
+            match &mut typed_instr.instr {
+                Instr::Block(_, body) | Instr::Loop(_, body) => {
+                    *body = unmask(std::mem::take(body));
+                }
+                Instr::If(_, then, else_) => {
+                    *then = unmask(std::mem::take(then));
+                    if let Some(else_) = else_ {
+                        *else_ = unmask(std::mem::take(else_));
+                    }
+                }
+                _ => (),
+            }
+            typed_instr
+        })
+        .collect()
+}
+
+/// The text format mnemonic of an instruction, e.g. `i32.div_s`
+pub fn mnemonic(instr: &Instr) -> &'static str {
+    match instr {
+        Instr::Unreachable => "unreachable",
+        Instr::Nop => "nop",
+        Instr::Block(..) => "block",
+        Instr::Loop(..) => "loop",
+        Instr::If(..) => "if",
+        Instr::Br(_) => "br",
+        Instr::BrIf(_) => "br_if",
+        Instr::BrTable { .. } => "br_table",
+        Instr::Return => "return",
+        Instr::Call(_) => "call",
+        Instr::CallIndirect(..) => "call_indirect",
+        Instr::RefNull(_) => "ref.null",
+        Instr::RefIsNull => "ref.is_null",
+        Instr::RefFunc(_) => "ref.func",
+        Instr::Drop => "drop",
+        Instr::Select | Instr::TypedSelect(_) => "select",
+        Instr::Local(LocalOp::Get, _) => "local.get",
+        Instr::Local(LocalOp::Set, _) => "local.set",
+        Instr::Local(LocalOp::Tee, _) => "local.tee",
+        Instr::Global(GlobalOp::Get, _) => "global.get",
+        Instr::Global(GlobalOp::Set, _) => "global.set",
+        Instr::TableGet(_) => "table.get",
+        Instr::TableSet(_) => "table.set",
+        Instr::TableSize(_) => "table.size",
+        Instr::TableGrow(_) => "table.grow",
+        Instr::TableFill(_) => "table.fill",
+        Instr::TableCopy(..) => "table.copy",
+        Instr::TableInit(..) => "table.init",
+        Instr::ElemDrop(_) => "elem.drop",
+        Instr::Load(op, _) => lookup(&LOAD_MNEMONICS, op),
+        Instr::Store(op, _) => lookup(&STORE_MNEMONICS, op),
+        Instr::MemorySize(_) => "memory.size",
+        Instr::MemoryGrow(_) => "memory.grow",
+        Instr::MemoryFill => "memory.fill",
+        Instr::MemoryCopy => "memory.copy",
+        Instr::MemoryInit(_) => "memory.init",
+        Instr::DataDrop(_) => "data.drop",
+        Instr::Const(Val::I32(_)) => "i32.const",
+        Instr::Const(Val::I64(_)) => "i64.const",
+        Instr::Const(Val::F32(_)) => "f32.const",
+        Instr::Const(Val::F64(_)) => "f64.const",
+        Instr::Unary(op) => lookup(&UNARY_MNEMONICS, op),
+        Instr::Binary(op) => lookup(&BINARY_MNEMONICS, op),
+    }
+}
+
+/// The mnemonics of the instructions that have no operator table below
+const INSTRUCTION_MNEMONICS: [&str; 39] = [
+    "unreachable",
+    "nop",
+    "block",
+    "loop",
+    "if",
+    "br",
+    "br_if",
+    "br_table",
+    "return",
+    "call",
+    "call_indirect",
+    "ref.null",
+    "ref.is_null",
+    "ref.func",
+    "drop",
+    "select",
+    "local.get",
+    "local.set",
+    "local.tee",
+    "global.get",
+    "global.set",
+    "table.get",
+    "table.set",
+    "table.size",
+    "table.grow",
+    "table.fill",
+    "table.copy",
+    "table.init",
+    "elem.drop",
+    "memory.size",
+    "memory.grow",
+    "memory.fill",
+    "memory.copy",
+    "memory.init",
+    "data.drop",
+    "i32.const",
+    "i64.const",
+    "f32.const",
+    "f64.const",
+];
+
+/// Whether `name` is the mnemonic of any instruction, see `mnemonic`
+pub fn is_mnemonic(name: &str) -> bool {
+    fn in_table<Op>(table: &[(Op, &str)], name: &str) -> bool {
+        table.iter().any(|(_, mnemonic)| *mnemonic == name)
+    }
+    INSTRUCTION_MNEMONICS.contains(&name)
+        || in_table(&LOAD_MNEMONICS, name)
+        || in_table(&STORE_MNEMONICS, name)
+        || in_table(&UNARY_MNEMONICS, name)
+        || in_table(&BINARY_MNEMONICS, name)
+}
+
+fn lookup<Op: PartialEq>(table: &[(Op, &'static str)], op: &Op) -> &'static str {
+    table
+        .iter()
+        .find_map(|(entry, mnemonic)| (entry == op).then_some(*mnemonic))
+        .expect("every operator has a mnemonic")
+}
+
+const LOAD_MNEMONICS: [(LoadOp, &str); 14] = [
+    (LoadOp::I32Load, "i32.load"),
+    (LoadOp::I64Load, "i64.load"),
+    (LoadOp::F32Load, "f32.load"),
+    (LoadOp::F64Load, "f64.load"),
+    (LoadOp::I32Load8S, "i32.load8_s"),
+    (LoadOp::I32Load8U, "i32.load8_u"),
+    (LoadOp::I32Load16S, "i32.load16_s"),
+    (LoadOp::I32Load16U, "i32.load16_u"),
+    (LoadOp::I64Load8S, "i64.load8_s"),
+    (LoadOp::I64Load8U, "i64.load8_u"),
+    (LoadOp::I64Load16S, "i64.load16_s"),
+    (LoadOp::I64Load16U, "i64.load16_u"),
+    (LoadOp::I64Load32S, "i64.load32_s"),
+    (LoadOp::I64Load32U, "i64.load32_u"),
+];
+
+const STORE_MNEMONICS: [(StoreOp, &str); 9] = [
+    (StoreOp::I32Store, "i32.store"),
+    (StoreOp::I64Store, "i64.store"),
+    (StoreOp::F32Store, "f32.store"),
+    (StoreOp::F64Store, "f64.store"),
+    (StoreOp::I32Store8, "i32.store8"),
+    (StoreOp::I32Store16, "i32.store16"),
+    (StoreOp::I64Store8, "i64.store8"),
+    (StoreOp::I64Store16, "i64.store16"),
+    (StoreOp::I64Store32, "i64.store32"),
+];
+
+const UNARY_MNEMONICS: [(UnaryOp, &str); 60] = [
+    (UnaryOp::I32Eqz, "i32.eqz"),
+    (UnaryOp::I64Eqz, "i64.eqz"),
+    (UnaryOp::I32Clz, "i32.clz"),
+    (UnaryOp::I32Ctz, "i32.ctz"),
+    (UnaryOp::I32Popcnt, "i32.popcnt"),
+    (UnaryOp::I64Clz, "i64.clz"),
+    (UnaryOp::I64Ctz, "i64.ctz"),
+    (UnaryOp::I64Popcnt, "i64.popcnt"),
+    (UnaryOp::F32Abs, "f32.abs"),
+    (UnaryOp::F32Neg, "f32.neg"),
+    (UnaryOp::F32Ceil, "f32.ceil"),
+    (UnaryOp::F32Floor, "f32.floor"),
+    (UnaryOp::F32Trunc, "f32.trunc"),
+    (UnaryOp::F32Nearest, "f32.nearest"),
+    (UnaryOp::F32Sqrt, "f32.sqrt"),
+    (UnaryOp::F64Abs, "f64.abs"),
+    (UnaryOp::F64Neg, "f64.neg"),
+    (UnaryOp::F64Ceil, "f64.ceil"),
+    (UnaryOp::F64Floor, "f64.floor"),
+    (UnaryOp::F64Trunc, "f64.trunc"),
+    (UnaryOp::F64Nearest, "f64.nearest"),
+    (UnaryOp::F64Sqrt, "f64.sqrt"),
+    (UnaryOp::I32WrapI64, "i32.wrap_i64"),
+    (UnaryOp::I32TruncF32S, "i32.trunc_f32_s"),
+    (UnaryOp::I32TruncF32U, "i32.trunc_f32_u"),
+    (UnaryOp::I32TruncF64S, "i32.trunc_f64_s"),
+    (UnaryOp::I32TruncF64U, "i32.trunc_f64_u"),
+    (UnaryOp::I32TruncSatF32S, "i32.trunc_sat_f32_s"),
+    (UnaryOp::I32TruncSatF32U, "i32.trunc_sat_f32_u"),
+    (UnaryOp::I32TruncSatF64S, "i32.trunc_sat_f64_s"),
+    (UnaryOp::I32TruncSatF64U, "i32.trunc_sat_f64_u"),
+    (UnaryOp::I64ExtendI32S, "i64.extend_i32_s"),
+    (UnaryOp::I64ExtendI32U, "i64.extend_i32_u"),
+    (UnaryOp::I64TruncF32S, "i64.trunc_f32_s"),
+    (UnaryOp::I64TruncF32U, "i64.trunc_f32_u"),
+    (UnaryOp::I64TruncF64S, "i64.trunc_f64_s"),
+    (UnaryOp::I64TruncF64U, "i64.trunc_f64_u"),
+    (UnaryOp::I64TruncSatF32S, "i64.trunc_sat_f32_s"),
+    (UnaryOp::I64TruncSatF32U, "i64.trunc_sat_f32_u"),
+    (UnaryOp::I64TruncSatF64S, "i64.trunc_sat_f64_s"),
+    (UnaryOp::I64TruncSatF64U, "i64.trunc_sat_f64_u"),
+    (UnaryOp::F32ConvertI32S, "f32.convert_i32_s"),
+    (UnaryOp::F32ConvertI32U, "f32.convert_i32_u"),
+    (UnaryOp::F32ConvertI64S, "f32.convert_i64_s"),
+    (UnaryOp::F32ConvertI64U, "f32.convert_i64_u"),
+    (UnaryOp::F32DemoteF64, "f32.demote_f64"),
+    (UnaryOp::F64ConvertI32S, "f64.convert_i32_s"),
+    (UnaryOp::F64ConvertI32U, "f64.convert_i32_u"),
+    (UnaryOp::F64ConvertI64S, "f64.convert_i64_s"),
+    (UnaryOp::F64ConvertI64U, "f64.convert_i64_u"),
+    (UnaryOp::F64PromoteF32, "f64.promote_f32"),
+    (UnaryOp::I32ReinterpretF32, "i32.reinterpret_f32"),
+    (UnaryOp::I64ReinterpretF64, "i64.reinterpret_f64"),
+    (UnaryOp::F32ReinterpretI32, "f32.reinterpret_i32"),
+    (UnaryOp::F64ReinterpretI64, "f64.reinterpret_i64"),
+    (UnaryOp::I32Extend8S, "i32.extend8_s"),
+    (UnaryOp::I32Extend16S, "i32.extend16_s"),
+    (UnaryOp::I64Extend8S, "i64.extend8_s"),
+    (UnaryOp::I64Extend16S, "i64.extend16_s"),
+    (UnaryOp::I64Extend32S, "i64.extend32_s"),
+];
+
+const BINARY_MNEMONICS: [(BinaryOp, &str); 76] = [
+    (BinaryOp::I32Eq, "i32.eq"),
+    (BinaryOp::I32Ne, "i32.ne"),
+    (BinaryOp::I32LtS, "i32.lt_s"),
+    (BinaryOp::I32LtU, "i32.lt_u"),
+    (BinaryOp::I32GtS, "i32.gt_s"),
+    (BinaryOp::I32GtU, "i32.gt_u"),
+    (BinaryOp::I32LeS, "i32.le_s"),
+    (BinaryOp::I32LeU, "i32.le_u"),
+    (BinaryOp::I32GeS, "i32.ge_s"),
+    (BinaryOp::I32GeU, "i32.ge_u"),
+    (BinaryOp::I64Eq, "i64.eq"),
+    (BinaryOp::I64Ne, "i64.ne"),
+    (BinaryOp::I64LtS, "i64.lt_s"),
+    (BinaryOp::I64LtU, "i64.lt_u"),
+    (BinaryOp::I64GtS, "i64.gt_s"),
+    (BinaryOp::I64GtU, "i64.gt_u"),
+    (BinaryOp::I64LeS, "i64.le_s"),
+    (BinaryOp::I64LeU, "i64.le_u"),
+    (BinaryOp::I64GeS, "i64.ge_s"),
+    (BinaryOp::I64GeU, "i64.ge_u"),
+    (BinaryOp::F32Eq, "f32.eq"),
+    (BinaryOp::F32Ne, "f32.ne"),
+    (BinaryOp::F32Lt, "f32.lt"),
+    (BinaryOp::F32Gt, "f32.gt"),
+    (BinaryOp::F32Le, "f32.le"),
+    (BinaryOp::F32Ge, "f32.ge"),
+    (BinaryOp::F64Eq, "f64.eq"),
+    (BinaryOp::F64Ne, "f64.ne"),
+    (BinaryOp::F64Lt, "f64.lt"),
+    (BinaryOp::F64Gt, "f64.gt"),
+    (BinaryOp::F64Le, "f64.le"),
+    (BinaryOp::F64Ge, "f64.ge"),
+    (BinaryOp::I32Add, "i32.add"),
+    (BinaryOp::I32Sub, "i32.sub"),
+    (BinaryOp::I32Mul, "i32.mul"),
+    (BinaryOp::I32DivS, "i32.div_s"),
+    (BinaryOp::I32DivU, "i32.div_u"),
+    (BinaryOp::I32RemS, "i32.rem_s"),
+    (BinaryOp::I32RemU, "i32.rem_u"),
+    (BinaryOp::I32And, "i32.and"),
+    (BinaryOp::I32Or, "i32.or"),
+    (BinaryOp::I32Xor, "i32.xor"),
+    (BinaryOp::I32Shl, "i32.shl"),
+    (BinaryOp::I32ShrS, "i32.shr_s"),
+    (BinaryOp::I32ShrU, "i32.shr_u"),
+    (BinaryOp::I32Rotl, "i32.rotl"),
+    (BinaryOp::I32Rotr, "i32.rotr"),
+    (BinaryOp::I64Add, "i64.add"),
+    (BinaryOp::I64Sub, "i64.sub"),
+    (BinaryOp::I64Mul, "i64.mul"),
+    (BinaryOp::I64DivS, "i64.div_s"),
+    (BinaryOp::I64DivU, "i64.div_u"),
+    (BinaryOp::I64RemS, "i64.rem_s"),
+    (BinaryOp::I64RemU, "i64.rem_u"),
+    (BinaryOp::I64And, "i64.and"),
+    (BinaryOp::I64Or, "i64.or"),
+    (BinaryOp::I64Xor, "i64.xor"),
+    (BinaryOp::I64Shl, "i64.shl"),
+    (BinaryOp::I64ShrS, "i64.shr_s"),
+    (BinaryOp::I64ShrU, "i64.shr_u"),
+    (BinaryOp::I64Rotl, "i64.rotl"),
+    (BinaryOp::I64Rotr, "i64.rotr"),
+    (BinaryOp::F32Add, "f32.add"),
+    (BinaryOp::F32Sub, "f32.sub"),
+    (BinaryOp::F32Mul, "f32.mul"),
+    (BinaryOp::F32Div, "f32.div"),
+    (BinaryOp::F32Min, "f32.min"),
+    (BinaryOp::F32Max, "f32.max"),
+    (BinaryOp::F32Copysign, "f32.copysign"),
+    (BinaryOp::F64Add, "f64.add"),
+    (BinaryOp::F64Sub, "f64.sub"),
+    (BinaryOp::F64Mul, "f64.mul"),
+    (BinaryOp::F64Div, "f64.div"),
+    (BinaryOp::F64Min, "f64.min"),
+    (BinaryOp::F64Max, "f64.max"),
+    (BinaryOp::F64Copysign, "f64.copysign"),
+];
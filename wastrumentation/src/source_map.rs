@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use gimli::{ColumnType, Dwarf, EndianSlice, LittleEndian, SectionId};
+use wasmparser::{Parser, Payload};
+
+use crate::code_offsets::CodeOffsets;
+use crate::error::InstrumentationError;
+
+// DWARF for WebAssembly addresses instructions by their byte offset relative to
+// the start of the contents of the code section, the line table is joined with
+// the instructions on those offsets.
+
+/// Where in the source an instruction originates from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    /// Zero when the instruction spans a whole line
+    pub column: u32,
+}
+
+/// The source locations of the instructions of a module, read from its DWARF line table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    files: Vec<String>,
+    /// File index, line & column by (function, instruction) index
+    locations: HashMap<(u32, usize), (usize, u32, u32)>,
+}
+
+impl SourceMap {
+    /// Reads the line table of `module`. A module without DWARF yields an empty map.
+    ///
+    /// # Errors
+    /// When the module or its DWARF sections are malformed.
+    pub fn from_module(module: &[u8]) -> Result<Self, InstrumentationError> {
+        let mut debug_sections: HashMap<&str, &[u8]> = HashMap::new();
+        for payload in Parser::new(0).parse_all(module) {
+            if let Payload::CustomSection(section) =
+                payload.map_err(InstrumentationError::CodeSectionParseError)?
+            {
+                if section.name().starts_with(".debug_") {
+                    debug_sections.insert(section.name(), section.data());
+                }
+            }
+        }
+
+        if debug_sections.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut source_map = Self::default();
+        let line_table = source_map
+            .read_line_table(&debug_sections)
+            .map_err(InstrumentationError::DwarfError)?;
+
+        let code_offsets =
+            CodeOffsets::read(module).map_err(InstrumentationError::CodeSectionParseError)?;
+        for (site, address) in code_offsets.instructions() {
+            if let Some(location) = lookup(&line_table, address) {
+                source_map.locations.insert(site, location);
+            }
+        }
+        Ok(source_map)
+    }
+
+    /// Collects the rows of every line program, sorted by address. The end of a
+    /// sequence is recorded without location, so addresses past it resolve to none.
+    fn read_line_table(
+        &mut self,
+        debug_sections: &HashMap<&str, &[u8]>,
+    ) -> Result<Vec<(u64, Option<(usize, u32, u32)>)>, gimli::Error> {
+        let load = |section: SectionId| -> Result<_, gimli::Error> {
+            let data = debug_sections.get(section.name()).copied().unwrap_or(&[]);
+            Ok(EndianSlice::new(data, LittleEndian))
+        };
+        let dwarf = Dwarf::load(load)?;
+
+        let mut line_table = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            let mut rows = program.rows();
+            while let Some((header, row)) = rows.next_row()? {
+                if row.end_sequence() {
+                    line_table.push((row.address(), None));
+                    continue;
+                }
+                let Some(file) = row.file(header) else {
+                    continue;
+                };
+                let mut path = String::new();
+                if let Some(directory) = file.directory(header) {
+                    path.push_str(&dwarf.attr_string(&unit, directory)?.to_string_lossy());
+                    path.push('/');
+                }
+                path.push_str(
+                    &dwarf
+                        .attr_string(&unit, file.path_name())?
+                        .to_string_lossy(),
+                );
+
+                let line = row.line().map_or(0, |line| line.get());
+                let column = match row.column() {
+                    ColumnType::LeftEdge => 0,
+                    ColumnType::Column(column) => column.get(),
+                };
+                line_table.push((
+                    row.address(),
+                    Some((
+                        self.file_index(path),
+                        u32::try_from(line).unwrap_or(u32::MAX),
+                        u32::try_from(column).unwrap_or(u32::MAX),
+                    )),
+                ));
+            }
+        }
+        // A sequence may start where another one ends
+        line_table.sort_by_key(|(address, location)| (*address, location.is_some()));
+        Ok(line_table)
+    }
+
+    fn file_index(&mut self, path: String) -> usize {
+        match self.files.iter().position(|file| *file == path) {
+            Some(index) => index,
+            None => {
+                self.files.push(path);
+                self.files.len() - 1
+            }
+        }
+    }
+
+    /// The source location of the original instruction at `instr_index` in function `funct_index`
+    pub fn lookup(&self, funct_index: u32, instr_index: usize) -> Option<SourceLocation> {
+        self.locations
+            .get(&(funct_index, instr_index))
+            .map(|(file, line, column)| SourceLocation {
+                file: self.files[*file].clone(),
+                line: *line,
+                column: *column,
+            })
+    }
+
+    /// The source files the line table refers to, by file index
+    pub(crate) fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// The file index, line & column of the instruction at `instr_index` in function `funct_index`
+    pub(crate) fn lookup_indexed(
+        &self,
+        funct_index: u32,
+        instr_index: usize,
+    ) -> Option<(usize, u32, u32)> {
+        self.locations.get(&(funct_index, instr_index)).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
+
+/// The location of the last row at or before `address`
+fn lookup(
+    line_table: &[(u64, Option<(usize, u32, u32)>)],
+    address: u64,
+) -> Option<(usize, u32, u32)> {
+    let rows_at_or_before = line_table.partition_point(|(row_address, _)| *row_address <= address);
+    line_table
+        .get(rows_at_or_before.checked_sub(1)?)
+        .and_then(|(_, location)| *location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_without_dwarf_has_no_locations() {
+        let module = wat::parse_str(r#"(module (func (export "main") nop))"#).unwrap();
+        let source_map = SourceMap::from_module(&module).unwrap();
+        assert!(source_map.is_empty());
+        assert_eq!(source_map.lookup(0, 0), None);
+    }
+
+    fn append_custom_section(module: &mut Vec<u8>, name: &str, data: &[u8]) {
+        let size = 1 + name.len() + data.len();
+        module.extend_from_slice(&[0, u8::try_from(size).unwrap()]);
+        module.push(u8::try_from(name.len()).unwrap());
+        module.extend_from_slice(name.as_bytes());
+        module.extend_from_slice(data);
+    }
+
+    #[test]
+    fn instructions_resolve_to_their_line_table_row() {
+        // Code section contents: [count, size, no locals, 41 01, 41 02, 6a, 0b],
+        // the instructions start at code offsets 3, 5, 7 & 8
+        let mut module = wat::parse_str(
+            r#"
+            (module
+                (func (export "main") (result i32)
+                    i32.const 1
+                    i32.const 2
+                    i32.add))"#,
+        )
+        .unwrap();
+
+        #[rustfmt::skip]
+        let debug_abbrev = [
+            // compile unit without children, with a `DW_AT_stmt_list` section offset
+            0x01, 0x11, 0x00, 0x10, 0x17, 0x00, 0x00,
+            0x00,
+        ];
+        #[rustfmt::skip]
+        let debug_info = [
+            // length, DWARF 4, abbreviation offset, address size
+            0x0c, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+            // the compile unit, its line program at offset 0
+            0x01, 0x00, 0x00, 0x00, 0x00,
+        ];
+        #[rustfmt::skip]
+        let debug_line = [
+            // length, DWARF 4, header length
+            0x41, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x00,
+            // instruction length, operations, is_stmt, line base, line range, opcode base
+            0x01, 0x01, 0x01, 0xfb, 0x0e, 0x0d,
+            // standard opcode lengths
+            0x00, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01,
+            // directories: "/src"
+            b'/', b's', b'r', b'c', 0x00, 0x00,
+            // files: "main.c" in directory 1
+            b'm', b'a', b'i', b'n', b'.', b'c', 0x00, 0x01, 0x00, 0x00, 0x00,
+            // address 3: line 3, column 5
+            0x00, 0x05, 0x02, 0x03, 0x00, 0x00, 0x00, 0x03, 0x02, 0x05, 0x05, 0x01,
+            // address 7: line 4, column 1
+            0x02, 0x04, 0x03, 0x01, 0x05, 0x01, 0x01,
+            // end of the sequence at address 9
+            0x02, 0x02, 0x00, 0x01, 0x01,
+        ];
+        append_custom_section(&mut module, ".debug_abbrev", &debug_abbrev);
+        append_custom_section(&mut module, ".debug_info", &debug_info);
+        append_custom_section(&mut module, ".debug_line", &debug_line);
+
+        let source_map = SourceMap::from_module(&module).unwrap();
+        let location = |line, column| {
+            Some(SourceLocation {
+                file: "/src/main.c".into(),
+                line,
+                column,
+            })
+        };
+        assert_eq!(source_map.lookup(0, 0), location(3, 5));
+        assert_eq!(source_map.lookup(0, 1), location(3, 5));
+        assert_eq!(source_map.lookup(0, 2), location(4, 1));
+        assert_eq!(source_map.lookup(0, 3), location(4, 1));
+        assert_eq!(source_map.lookup(0, 4), None);
+    }
+
+    #[test]
+    fn lookup_takes_the_last_row_of_the_sequence() {
+        let line_table = vec![
+            (2, Some((0, 1, 0))),
+            (5, Some((0, 2, 4))),
+            (9, None),
+            (9, Some((1, 7, 1))),
+            (12, None),
+        ];
+        assert_eq!(lookup(&line_table, 1), None);
+        assert_eq!(lookup(&line_table, 2), Some((0, 1, 0)));
+        assert_eq!(lookup(&line_table, 8), Some((0, 2, 4)));
+        assert_eq!(lookup(&line_table, 9), Some((1, 7, 1)));
+        assert_eq!(lookup(&line_table, 12), None);
+    }
+}
@@ -33,11 +33,13 @@ impl<Language: LibGeneratable> StackLibrary<Language> {
                 acc.insert(function_type, stack_library);
                 acc
             });
-        let signatures: Vec<Signature> = signature_import_links
+        let mut signatures: Vec<Signature> = signature_import_links
             .keys()
             .map(WasabiFunctionType)
             .map(Into::into)
             .collect();
+        // The same signatures generate the same library, so the compile cache can hit
+        signatures.sort();
 
         let library = Language::generate_lib(&signatures);
         Self {
@@ -86,7 +88,20 @@ impl From<WasabiFunctionType<'_>> for Signature {
     }
 }
 
+/// References cannot be stored in linear memory, they cross the stack library as `i32` handles
+pub fn stack_val_type(val_type: ValType) -> ValType {
+    match val_type {
+        ValType::Ref(_) => ValType::I32,
+        val_type => val_type,
+    }
+}
+
+fn stack_val_types(val_types: &[ValType]) -> Vec<ValType> {
+    val_types.iter().copied().map(stack_val_type).collect()
+}
+
 // TODO: remove the dead code, this might be related to the specialized instrumentation code
+#[derive(Clone)]
 pub struct ModuleLinkedStackHooks {
     #[allow(dead_code)]
     pub function_type: FunctionType,
@@ -114,7 +129,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
     fn from((function_type, module): (FunctionType, &mut Module)) -> Self {
         let lib_gen_signature: Signature = WasabiFunctionType(&function_type).into();
         let allocate_values_buffer_type =
-            FunctionType::new(function_type.inputs(), &[ValType::I32]);
+            FunctionType::new(&stack_val_types(function_type.inputs()), &[ValType::I32]);
         let allocate_values_buffer = module.add_function_import(
             allocate_values_buffer_type,
             INSTRUMENTATION_STACK_MODULE.into(),
@@ -148,7 +163,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
             .enumerate()
             .map(|(index, val_type)| {
                 module.add_function_import(
-                    FunctionType::new(&[ValType::I32], &[*val_type]),
+                    FunctionType::new(&[ValType::I32], &[stack_val_type(*val_type)]),
                     INSTRUMENTATION_STACK_MODULE.into(),
                     lib_gen_signature.generate_load_name(SignatureSide::Argument, index),
                 )
@@ -161,7 +176,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
             .enumerate()
             .map(|(index, val_type)| {
                 module.add_function_import(
-                    FunctionType::new(&[ValType::I32], &[*val_type]),
+                    FunctionType::new(&[ValType::I32], &[stack_val_type(*val_type)]),
                     INSTRUMENTATION_STACK_MODULE.into(),
                     lib_gen_signature.generate_load_name(SignatureSide::Return, index),
                 )
@@ -174,7 +189,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
             .enumerate()
             .map(|(index, val_type)| {
                 module.add_function_import(
-                    FunctionType::new(&[ValType::I32, *val_type], &[]),
+                    FunctionType::new(&[ValType::I32, stack_val_type(*val_type)], &[]),
                     INSTRUMENTATION_STACK_MODULE.into(),
                     lib_gen_signature.generate_store_name(SignatureSide::Argument, index),
                 )
@@ -187,7 +202,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
             .enumerate()
             .map(|(index, val_type)| {
                 module.add_function_import(
-                    FunctionType::new(&[ValType::I32, *val_type], &[]),
+                    FunctionType::new(&[ValType::I32, stack_val_type(*val_type)], &[]),
                     INSTRUMENTATION_STACK_MODULE.into(),
                     lib_gen_signature.generate_store_name(SignatureSide::Return, index),
                 )
@@ -195,7 +210,7 @@ impl From<(FunctionType, &mut Module)> for ModuleLinkedStackHooks {
             .collect();
 
         let mut store_rets_signature = vec![ValType::I32];
-        store_rets_signature.extend(function_type.results());
+        store_rets_signature.extend(stack_val_types(function_type.results()));
         let ret_store_all = module.add_function_import(
             FunctionType::new(&store_rets_signature, &[]),
             INSTRUMENTATION_STACK_MODULE.into(),
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WasmType {
     I32,
     F32,
@@ -9,7 +9,7 @@ pub enum WasmType {
     Ref(RefType),
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RefType {
     FuncRef,
     ExternRef,
@@ -48,6 +48,15 @@ impl WasmType {
             WasmType::Ref(RefType::ExternRef) => "ref_extern",
         }
     }
+
+    /// The type used for this value across the stack library boundary.
+    /// References cannot live in linear memory, so they cross as `i32` handles.
+    pub fn stack_type_name(&self) -> &'static str {
+        match self {
+            WasmType::Ref(_) => "i32",
+            _ => self.as_str(),
+        }
+    }
 }
 
 impl Display for WasmType {
@@ -56,7 +65,7 @@ impl Display for WasmType {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Signature {
     pub return_types: Vec<WasmType>,
     pub argument_types: Vec<WasmType>,